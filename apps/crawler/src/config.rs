@@ -1,4 +1,5 @@
 use std::env;
+use std::net::IpAddr;
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -13,7 +14,8 @@ pub struct Config {
     pub max_concurrent_fetches: usize,
     pub max_concurrent_lighthouse: usize,
     /// Audit backend: `"psi"` (PageSpeed Insights, the default — no local
-    /// browser), `"local"` (Chromium subprocess; hangs on Fly), or `"off"`.
+    /// browser), `"local"` (Chromium subprocess; hangs on Fly, and its redirects
+    /// bypass the egress policy), or `"off"`.
     pub lighthouse_mode: String,
     /// PageSpeed Insights API key (optional; PSI works keyless at lower quota).
     pub pagespeed_api_key: Option<String>,
@@ -39,6 +41,19 @@ pub struct Config {
     pub renderer_timeout_s: u64,
     pub batch_page_threshold: usize,
     pub batch_interval_secs: u64,
+    /// Directory for crawl checkpoints and undelivered batches (required).
+    /// Point it at a Fly volume so unfinished jobs survive a machine restart
+    /// and are resumed on startup.
+    pub checkpoint_dir: String,
+    /// Job-state registry backend: `"memory"` (default) or `"redis"`. Redis
+    /// keeps status and cancel requests across deploys and instances.
+    pub job_store: String,
+    /// Redis connection URL, required when `job_store` is `"redis"`.
+    pub redis_url: Option<String>,
+    /// Addresses exempt from the egress block list (`EGRESS_ALLOW_IPS`,
+    /// comma-separated). Only for local development and tests, where the
+    /// target site runs on loopback or a private network.
+    pub egress_allow_ips: Vec<IpAddr>,
 }
 
 impl Config {
@@ -152,6 +167,27 @@ impl Config {
             .parse::<u64>()
             .map_err(|_| ConfigError::InvalidValue("BATCH_INTERVAL_SECS", "must be a valid u64"))?;
 
+        // No default: a temp directory is wiped with the container, which
+        // would silently turn resume off.
+        let checkpoint_dir = env::var("CHECKPOINT_DIR")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .ok_or(ConfigError::Missing("CHECKPOINT_DIR"))?;
+
+        let job_store = env::var("JOB_STORE")
+            .ok()
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "memory".to_string());
+
+        let redis_url = env::var("REDIS_URL").ok().filter(|s| !s.trim().is_empty());
+
+        let egress_allow_ips = parse_ip_list(&env::var("EGRESS_ALLOW_IPS").unwrap_or_default())
+            .ok_or(ConfigError::InvalidValue(
+                "EGRESS_ALLOW_IPS",
+                "must be a comma-separated list of IP addresses",
+            ))?;
+
         Ok(Config {
             shared_secret,
             api_base_url,
@@ -174,6 +210,10 @@ impl Config {
             renderer_timeout_s,
             batch_page_threshold,
             batch_interval_secs,
+            checkpoint_dir,
+            job_store,
+            redis_url,
+            egress_allow_ips,
         })
     }
 }
@@ -187,6 +227,16 @@ fn parse_bool_flag(raw: &str) -> bool {
     )
 }
 
+/// Parse a comma-separated IP list; blank entries are skipped. `None` if any
+/// entry isn't an IP address.
+fn parse_ip_list(raw: &str) -> Option<Vec<IpAddr>> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().ok())
+        .collect()
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
     #[error("Missing required environment variable: {0}")]
@@ -197,7 +247,7 @@ pub enum ConfigError {
 
 #[cfg(test)]
 mod tests {
-    use super::parse_bool_flag;
+    use super::{parse_bool_flag, parse_ip_list};
 
     #[test]
     fn truthy_values_parse_true() {
@@ -212,4 +262,14 @@ mod tests {
             assert!(!parse_bool_flag(v), "expected {v:?} to be falsy");
         }
     }
+
+    #[test]
+    fn ip_list_parses_and_rejects_garbage() {
+        assert_eq!(parse_ip_list(""), Some(vec![]));
+        assert_eq!(
+            parse_ip_list(" 127.0.0.1, ::1 ,"),
+            Some(vec!["127.0.0.1".parse().unwrap(), "::1".parse().unwrap()])
+        );
+        assert_eq!(parse_ip_list("127.0.0.1,localhost"), None);
+    }
 }
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use url::Url;
+
+use super::robots::RobotsChecker;
+use crate::models::{AiCrawlerAccess, AiCrawlerPurpose};
+use AiCrawlerPurpose::{SearchRetrieval, Training, UserFetch};
+
+/// A known AI crawler: its robots.txt product token, who runs it and why.
+#[derive(Debug, Clone, Copy)]
+pub struct AiCrawler {
+    pub user_agent: &'static str,
+    pub vendor: &'static str,
+    pub purpose: AiCrawlerPurpose,
+}
+
+const fn crawler(
+    user_agent: &'static str,
+    vendor: &'static str,
+    purpose: AiCrawlerPurpose,
+) -> AiCrawler {
+    AiCrawler {
+        user_agent,
+        vendor,
+        purpose,
+    }
+}
+
+/// Catalog of AI crawlers, grouped by vendor. Keep it in line with each
+/// vendor's published crawler documentation; a bot that serves several
+/// purposes is listed under the one its robots.txt token controls.
+pub const AI_CRAWLERS: &[AiCrawler] = &[
+    crawler("GPTBot", "OpenAI", Training),
+    crawler("OAI-SearchBot", "OpenAI", SearchRetrieval),
+    crawler("ChatGPT-User", "OpenAI", UserFetch),
+    crawler("ClaudeBot", "Anthropic", Training),
+    crawler("anthropic-ai", "Anthropic", Training),
+    crawler("Claude-SearchBot", "Anthropic", SearchRetrieval),
+    crawler("Claude-User", "Anthropic", UserFetch),
+    crawler("Google-Extended", "Google", Training),
+    crawler("GoogleOther", "Google", Training),
+    crawler("Applebot-Extended", "Apple", Training),
+    crawler("PerplexityBot", "Perplexity", SearchRetrieval),
+    crawler("Perplexity-User", "Perplexity", UserFetch),
+    crawler("meta-externalagent", "Meta", Training),
+    crawler("meta-externalfetcher", "Meta", UserFetch),
+    crawler("FacebookBot", "Meta", Training),
+    crawler("Amazonbot", "Amazon", SearchRetrieval),
+    crawler("Bytespider", "ByteDance", Training),
+    crawler("CCBot", "Common Crawl", Training),
+    crawler("cohere-ai", "Cohere", Training),
+    crawler("MistralAI-User", "Mistral", UserFetch),
+    crawler("DuckAssistBot", "DuckDuckGo", SearchRetrieval),
+    crawler("YouBot", "You.com", SearchRetrieval),
+    crawler("Diffbot", "Diffbot", Training),
+];
+
+/// Page types worth checking individually, with the path segments that mark
+/// them. The first sampled URL whose path contains one of the segments stands
+/// in for the type.
+const KEY_PAGE_TYPES: &[(&str, &[&str])] = &[
+    ("pricing", &["pricing", "plans"]),
+    ("product", &["product", "products", "shop", "store"]),
+    ("blog", &["blog", "news", "articles", "posts"]),
+    (
+        "docs",
+        &["docs", "documentation", "help", "support", "guides"],
+    ),
+    ("faq", &["faq", "faqs"]),
+    ("about", &["about", "about-us", "company"]),
+    ("contact", &["contact", "contact-us"]),
+];
+
+/// Sections sampled per site, most-populated first.
+const MAX_SAMPLED_SECTIONS: usize = 10;
+
+/// Paths probed against robots.txt for every catalog bot, drawn from the
+/// site's known URLs (seeds and sitemap entries).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccessSample {
+    /// Top-level sections such as `/blog/`, most URLs first.
+    pub sections: Vec<String>,
+    /// `(page type, path)` for each key page type found.
+    pub page_types: Vec<(&'static str, String)>,
+}
+
+impl AccessSample {
+    pub fn from_urls(urls: &[String]) -> Self {
+        let paths: Vec<String> = urls
+            .iter()
+            .filter_map(|u| Url::parse(u).ok())
+            .map(|u| u.path().to_string())
+            .collect();
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for path in &paths {
+            // Only paths below a section: `/blog/post` counts, `/about` doesn't.
+            let mut segments = path.trim_start_matches('/').splitn(2, '/');
+            if let (Some(first), Some(_)) = (segments.next(), segments.next()) {
+                if !first.is_empty() {
+                    *counts.entry(format!("/{}/", first)).or_default() += 1;
+                }
+            }
+        }
+        let mut sections: Vec<(String, usize)> = counts.into_iter().collect();
+        sections.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let sections = sections
+            .into_iter()
+            .take(MAX_SAMPLED_SECTIONS)
+            .map(|(section, _)| section)
+            .collect();
+
+        let page_types = KEY_PAGE_TYPES
+            .iter()
+            .filter_map(|(page_type, markers)| {
+                paths
+                    .iter()
+                    .find(|path| {
+                        path.split('/')
+                            .any(|seg| markers.contains(&seg.to_lowercase().as_str()))
+                    })
+                    .map(|path| (*page_type, path.clone()))
+            })
+            .collect();
+
+        AccessSample {
+            sections,
+            page_types,
+        }
+    }
+}
+
+/// robots.txt access at the root, the sampled sections and the key page types
+/// for every crawler in the catalog.
+pub fn crawler_access(
+    robots: &RobotsChecker,
+    domain: &str,
+    sample: &AccessSample,
+) -> Vec<AiCrawlerAccess> {
+    let url = |path: &str| format!("https://{}{}", domain, path);
+    AI_CRAWLERS
+        .iter()
+        .map(|bot| AiCrawlerAccess {
+            user_agent: bot.user_agent.to_string(),
+            vendor: bot.vendor.to_string(),
+            purpose: bot.purpose,
+            named_in_robots: robots.names_agent(bot.user_agent),
+            root_allowed: robots.is_allowed(&url("/"), bot.user_agent),
+            blocked_sections: sample
+                .sections
+                .iter()
+                .filter(|section| !robots.is_allowed(&url(section), bot.user_agent))
+                .cloned()
+                .collect(),
+            blocked_page_types: sample
+                .page_types
+                .iter()
+                .filter(|(_, path)| !robots.is_allowed(&url(path), bot.user_agent))
+                .map(|(page_type, _)| page_type.to_string())
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(paths: &[&str]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| format!("https://example.com{p}"))
+            .collect()
+    }
+
+    #[test]
+    fn test_sample_ranks_sections_and_finds_page_types() {
+        let sample = AccessSample::from_urls(&urls(&[
+            "/",
+            "/pricing",
+            "/blog/one",
+            "/blog/two",
+            "/blog/three",
+            "/docs/start",
+            "/about",
+        ]));
+        assert_eq!(sample.sections, vec!["/blog/", "/docs/"]);
+        assert_eq!(
+            sample.page_types,
+            vec![
+                ("pricing", "/pricing".to_string()),
+                ("blog", "/blog/one".to_string()),
+                ("docs", "/docs/start".to_string()),
+                ("about", "/about".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_access_distinguishes_training_from_retrieval() {
+        let robots = RobotsChecker::from_content(
+            "User-agent: GPTBot\nUser-agent: CCBot\nDisallow: /\n\n\
+             User-agent: *\nDisallow: /pricing\n",
+        );
+        let sample = AccessSample::from_urls(&urls(&["/pricing", "/blog/a"]));
+        let access = crawler_access(&robots, "example.com", &sample);
+        assert_eq!(access.len(), AI_CRAWLERS.len());
+
+        let gpt = access.iter().find(|a| a.user_agent == "GPTBot").unwrap();
+        assert_eq!(gpt.purpose, AiCrawlerPurpose::Training);
+        assert!(gpt.named_in_robots);
+        assert!(!gpt.root_allowed);
+        assert_eq!(gpt.blocked_sections, vec!["/blog/"]);
+        assert_eq!(gpt.blocked_page_types, vec!["pricing", "blog"]);
+
+        // The search bot falls back to `*`: root allowed, pricing blocked.
+        let search = access
+            .iter()
+            .find(|a| a.user_agent == "OAI-SearchBot")
+            .unwrap();
+        assert_eq!(search.purpose, AiCrawlerPurpose::SearchRetrieval);
+        assert!(!search.named_in_robots);
+        assert!(search.root_allowed);
+        assert!(search.blocked_sections.is_empty());
+        assert_eq!(search.blocked_page_types, vec!["pricing"]);
+    }
+
+    #[test]
+    fn test_catalog_has_unique_user_agents() {
+        let mut seen = std::collections::HashSet::new();
+        for bot in AI_CRAWLERS {
+            assert!(
+                seen.insert(bot.user_agent.to_lowercase()),
+                "{}",
+                bot.user_agent
+            );
+        }
+    }
+}
@@ -1,29 +1,156 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
-#[derive(Serialize, Deserialize)]
+use super::near_duplicates::NearDuplicateIndex;
+use super::traps::TrapState;
+use crate::models::CrawlJobPayload;
+
+const CHECKPOINT_PREFIX: &str = "crawl-checkpoint-";
+
+/// Resumable snapshot of a running crawl, written after every delivered batch.
+///
+/// Captures everything `run_crawl_job` needs to pick up where it left off after
+/// a machine restart: the original payload, the frontier (seen URLs plus queued
+/// entries, including pages that were in flight when the snapshot was taken),
+/// the content hashes already delivered, and the counters / next batch index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CrawlCheckpoint {
     pub job_id: String,
+    pub payload: CrawlJobPayload,
+    /// Normalized URLs the frontier has already accepted (queued or crawled).
     pub seen_urls: HashSet<String>,
     pub pending_urls: Vec<(String, u32, u32)>, // (url, depth, priority)
+    /// SHA-256 body hash -> URL of pages already delivered, for content dedup.
+    #[serde(default)]
+    pub content_hashes: HashMap<String, String>,
     pub pages_crawled: usize,
+    #[serde(default)]
+    pub pages_errored: u32,
+    /// Index of the next batch to emit.
     pub batch_index: u32,
+    /// URLs the include/exclude rules have filtered so far, per rule.
+    #[serde(default)]
+    pub filtered_by_rule: BTreeMap<String, u32>,
+    /// Trap detector counters and the traps found so far.
+    #[serde(default)]
+    pub trap_state: TrapState,
+    /// Fingerprints and near-duplicate clusters of the pages delivered so far.
+    #[serde(default)]
+    pub near_duplicates: NearDuplicateIndex,
+    /// URLs handed out per host, for `max_pages_per_host`.
+    #[serde(default)]
+    pub pages_per_host: HashMap<String, u32>,
 }
 
 impl CrawlCheckpoint {
+    /// Write the checkpoint atomically (temp file + rename) so a crash mid-write
+    /// never leaves a truncated file behind.
     pub fn save(&self, path: &Path) -> std::io::Result<()> {
-        let json = serde_json::to_string(self)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        std::fs::write(path, json)
+        let json = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)
     }
 
     pub fn load(path: &Path) -> std::io::Result<Self> {
         let json = std::fs::read_to_string(path)?;
-        serde_json::from_str(&json).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+        serde_json::from_str(&json).map_err(std::io::Error::other)
+    }
+
+    pub fn path_for(dir: &Path, job_id: &str) -> PathBuf {
+        dir.join(format!("{}{}.json", CHECKPOINT_PREFIX, job_id))
     }
 
-    pub fn path_for(job_id: &str) -> PathBuf {
-        PathBuf::from(format!("/tmp/crawl-checkpoint-{}.json", job_id))
+    /// Load every checkpoint in `dir`. Unreadable or corrupt files are skipped
+    /// with a warning rather than failing the scan.
+    pub fn load_all(dir: &Path) -> Vec<Self> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(_) => return vec![],
+        };
+
+        let mut checkpoints = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_checkpoint = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(CHECKPOINT_PREFIX) && n.ends_with(".json"))
+                .unwrap_or(false);
+            if !is_checkpoint {
+                continue;
+            }
+            match Self::load(&path) {
+                Ok(cp) => checkpoints.push(cp),
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "Skipping unreadable checkpoint")
+                }
+            }
+        }
+        checkpoints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CrawlConfig;
+
+    fn sample(job_id: &str) -> CrawlCheckpoint {
+        let config: CrawlConfig = serde_json::from_value(serde_json::json!({
+            "seed_urls": ["https://example.com/"],
+            "max_pages": 100,
+            "max_depth": 3
+        }))
+        .unwrap();
+        CrawlCheckpoint {
+            job_id: job_id.to_string(),
+            payload: CrawlJobPayload {
+                job_id: job_id.to_string(),
+                callback_url: "http://localhost/cb".to_string(),
+                config,
+            },
+            seen_urls: ["https://example.com/".to_string()].into_iter().collect(),
+            pending_urls: vec![("https://example.com/a".to_string(), 1, 50)],
+            content_hashes: [("abc".to_string(), "https://example.com/".to_string())]
+                .into_iter()
+                .collect(),
+            pages_crawled: 1,
+            pages_errored: 0,
+            batch_index: 1,
+            filtered_by_rule: [("exclude:glob:/tag/**".to_string(), 2)]
+                .into_iter()
+                .collect(),
+            trap_state: TrapState::default(),
+            near_duplicates: NearDuplicateIndex::default(),
+            pages_per_host: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_save_load_roundtrip_and_scan() {
+        let dir = std::env::temp_dir().join(format!("cp-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let cp = sample("job-1");
+        cp.save(&CrawlCheckpoint::path_for(&dir, "job-1")).unwrap();
+        // Unrelated and corrupt files are ignored by the scan.
+        std::fs::write(dir.join("other.json"), "{}").unwrap();
+        std::fs::write(CrawlCheckpoint::path_for(&dir, "broken"), "not json").unwrap();
+
+        let loaded = CrawlCheckpoint::load(&CrawlCheckpoint::path_for(&dir, "job-1")).unwrap();
+        assert_eq!(loaded.pending_urls, cp.pending_urls);
+        assert_eq!(loaded.batch_index, 1);
+        assert_eq!(loaded.content_hashes["abc"], "https://example.com/");
+
+        let all = CrawlCheckpoint::load_all(&dir);
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].job_id, "job-1");
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
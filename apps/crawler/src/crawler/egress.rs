@@ -0,0 +1,352 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use thiserror::Error;
+use url::{Host, Url};
+
+use crate::config::Config;
+
+/// Redirect hops followed by clients built from `EgressPolicy::client_builder`.
+const MAX_REDIRECTS: usize = 10;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum EgressError {
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("Scheme not allowed: {0}")]
+    SchemeNotAllowed(String),
+    #[error("Refusing to connect to {host} ({ip}): {reason} address")]
+    Blocked {
+        host: String,
+        ip: IpAddr,
+        reason: &'static str,
+    },
+}
+
+/// Outbound-request policy shared by every component that fetches a URL taken
+/// from user input or a crawled page: the page fetcher, robots.txt, llms.txt,
+/// sitemaps and local Lighthouse.
+///
+/// Only `http`/`https` are allowed, and a host must not resolve to a loopback,
+/// private, link-local (cloud metadata), shared, multicast or otherwise
+/// reserved address. Hostnames are checked when they are resolved, so DNS
+/// answers can't sneak past a check done earlier; IP literals are checked
+/// before each request and redirect hop.
+#[derive(Debug, Clone, Default)]
+pub struct EgressPolicy {
+    /// Addresses exempt from the block list, e.g. a test server on 127.0.0.1.
+    allowed_ips: Arc<Vec<IpAddr>>,
+}
+
+impl EgressPolicy {
+    pub fn new(allowed_ips: Vec<IpAddr>) -> Self {
+        EgressPolicy {
+            allowed_ips: Arc::new(allowed_ips),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.egress_allow_ips.clone())
+    }
+
+    /// Check a resolved address.
+    pub fn check_ip(&self, host: &str, ip: IpAddr) -> Result<(), EgressError> {
+        if self.allowed_ips.contains(&ip) {
+            return Ok(());
+        }
+        match blocked_reason(ip) {
+            Some(reason) => Err(EgressError::Blocked {
+                host: host.to_string(),
+                ip,
+                reason,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Check a URL before requesting it: scheme, and the address when the host
+    /// is an IP literal. Hostnames are checked by the guarded resolver.
+    pub fn check_url(&self, url: &str) -> Result<(), EgressError> {
+        let parsed = Url::parse(url).map_err(|_| EgressError::InvalidUrl(url.to_string()))?;
+        self.check_parsed(&parsed)
+    }
+
+    fn check_parsed(&self, url: &Url) -> Result<(), EgressError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(EgressError::SchemeNotAllowed(url.scheme().to_string()));
+        }
+        match url.host() {
+            Some(Host::Ipv4(ip)) => self.check_ip(&ip.to_string(), IpAddr::V4(ip)),
+            Some(Host::Ipv6(ip)) => self.check_ip(&ip.to_string(), IpAddr::V6(ip)),
+            Some(Host::Domain(_)) => Ok(()),
+            None => Err(EgressError::InvalidUrl(url.to_string())),
+        }
+    }
+
+    /// `check_url`, plus a lookup of the hostname. For targets we don't fetch
+    /// ourselves (a Chromium subprocess resolves on its own), so this narrows
+    /// rather than closes the window a rebinding DNS server could use.
+    pub async fn check_resolved(&self, url: &str) -> Result<(), EgressError> {
+        let parsed = Url::parse(url).map_err(|_| EgressError::InvalidUrl(url.to_string()))?;
+        self.check_parsed(&parsed)?;
+        if let Some(Host::Domain(host)) = parsed.host() {
+            // A name that doesn't resolve can't reach anything either.
+            if let Ok(addrs) = tokio::net::lookup_host((host, 0)).await {
+                for addr in addrs {
+                    self.check_ip(host, addr.ip())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A `reqwest` client builder whose DNS resolution and automatic redirects
+    /// are both held to this policy. Callers still `check_url` the first URL,
+    /// since IP literals never reach the resolver.
+    pub fn client_builder(&self) -> reqwest::ClientBuilder {
+        self.client_builder_with_redirects(MAX_REDIRECTS)
+    }
+
+    /// `client_builder`, following at most `max_redirects` hops. Past that the
+    /// redirect response itself is returned.
+    pub fn client_builder_with_redirects(&self, max_redirects: usize) -> reqwest::ClientBuilder {
+        let redirect_policy = self.clone();
+        reqwest::Client::builder()
+            .dns_resolver(Arc::new(GuardedResolver {
+                policy: self.clone(),
+            }))
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() > max_redirects {
+                    return attempt.stop();
+                }
+                match redirect_policy.check_parsed(attempt.url()) {
+                    Ok(()) => attempt.follow(),
+                    Err(e) => attempt.error(e),
+                }
+            }))
+    }
+}
+
+/// DNS resolver that refuses names resolving to any blocked address. Refusing
+/// the whole name (rather than filtering) keeps a mixed public/private answer
+/// from being used to reach the private side.
+struct GuardedResolver {
+    policy: EgressPolicy,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.policy.clone();
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            for addr in &addrs {
+                policy.check_ip(&host, addr.ip())?;
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Find an `EgressError` in a request error's source chain.
+pub fn find_egress_error<'a>(
+    err: &'a (dyn std::error::Error + 'static),
+) -> Option<&'a EgressError> {
+    let mut current = Some(err);
+    while let Some(e) = current {
+        if let Some(egress) = e.downcast_ref::<EgressError>() {
+            return Some(egress);
+        }
+        current = e.source();
+    }
+    None
+}
+
+/// Why an address must not be fetched, or `None` if it is publicly routable.
+fn blocked_reason(ip: IpAddr) -> Option<&'static str> {
+    match ip {
+        IpAddr::V4(v4) => blocked_reason_v4(v4),
+        IpAddr::V6(v6) => blocked_reason_v6(v6),
+    }
+}
+
+fn blocked_reason_v4(ip: Ipv4Addr) -> Option<&'static str> {
+    let [a, b, c, _] = ip.octets();
+    if ip.is_loopback() {
+        Some("loopback")
+    } else if ip.is_private() {
+        Some("private")
+    } else if ip.is_link_local() {
+        // 169.254.0.0/16, including the 169.254.169.254 metadata endpoint.
+        Some("link-local")
+    } else if ip.is_unspecified() || a == 0 {
+        Some("unspecified")
+    } else if a == 100 && (64..128).contains(&b) {
+        // 100.64.0.0/10 carrier-grade NAT; also hosts some cloud metadata services.
+        Some("shared")
+    } else if ip.is_multicast() {
+        Some("multicast")
+    } else if ip.is_broadcast() || a >= 240 {
+        Some("reserved")
+    } else if (a == 192 && b == 0 && c == 0) || (a == 198 && (18..20).contains(&b)) {
+        // 192.0.0.0/24 protocol assignments, 198.18.0.0/15 benchmarking.
+        Some("reserved")
+    } else if ip.is_documentation() {
+        Some("documentation")
+    } else {
+        None
+    }
+}
+
+fn blocked_reason_v6(ip: Ipv6Addr) -> Option<&'static str> {
+    // IPv4-mapped (::ffff:a.b.c.d) and NAT64 (64:ff9b::a.b.c.d) addresses reach
+    // the embedded IPv4 host.
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return blocked_reason_v4(v4);
+    }
+    let segments = ip.segments();
+    if segments[0] == 0x64 && segments[1] == 0xff9b && segments[2..6] == [0, 0, 0, 0] {
+        let [a, b] = segments[6].to_be_bytes();
+        let [c, d] = segments[7].to_be_bytes();
+        return blocked_reason_v4(Ipv4Addr::new(a, b, c, d));
+    }
+
+    if ip.is_loopback() {
+        Some("loopback")
+    } else if ip.is_unspecified() {
+        Some("unspecified")
+    } else if (segments[0] & 0xfe00) == 0xfc00 {
+        // fc00::/7 unique local, including AWS's fd00:ec2::254 metadata endpoint.
+        Some("private")
+    } else if (segments[0] & 0xffc0) == 0xfe80 || (segments[0] & 0xffc0) == 0xfec0 {
+        Some("link-local")
+    } else if ip.is_multicast() {
+        Some("multicast")
+    } else if segments[0] == 0x2001 && segments[1] == 0x0db8 {
+        Some("documentation")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocked(url: &str) -> bool {
+        matches!(
+            EgressPolicy::default().check_url(url),
+            Err(EgressError::Blocked { .. })
+        )
+    }
+
+    #[test]
+    fn test_blocks_internal_ip_literals() {
+        for url in [
+            "http://127.0.0.1/",
+            "http://127.8.9.10:8080/admin",
+            "http://10.0.0.5/",
+            "http://172.16.3.4/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://100.100.100.200/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[fd00:ec2::254]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[::ffff:169.254.169.254]/",
+            "http://[64:ff9b::a00:1]/",
+        ] {
+            assert!(blocked(url), "expected {url} to be blocked");
+        }
+    }
+
+    #[test]
+    fn test_allows_public_addresses_and_hostnames() {
+        let policy = EgressPolicy::default();
+        for url in [
+            "https://93.184.216.34/",
+            "http://[2606:4700:4700::1111]/",
+            "https://example.com/page",
+        ] {
+            assert_eq!(policy.check_url(url), Ok(()), "expected {url} to pass");
+        }
+    }
+
+    #[test]
+    fn test_rejects_non_http_schemes() {
+        let policy = EgressPolicy::default();
+        assert_eq!(
+            policy.check_url("file:///etc/passwd"),
+            Err(EgressError::SchemeNotAllowed("file".to_string()))
+        );
+        assert!(matches!(
+            policy.check_url("gopher://example.com/"),
+            Err(EgressError::SchemeNotAllowed(_))
+        ));
+    }
+
+    #[test]
+    fn test_allowlisted_ip_passes() {
+        let policy = EgressPolicy::new(vec!["127.0.0.1".parse().unwrap()]);
+        assert_eq!(policy.check_url("http://127.0.0.1:3000/"), Ok(()));
+        // Only the exact address is exempt.
+        assert!(policy.check_url("http://127.0.0.2/").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolved_hostnames_are_checked() {
+        // `localhost` only ever resolves to loopback addresses.
+        let client = EgressPolicy::default().client_builder().build().unwrap();
+        let err = client.get("http://localhost:9/").send().await.unwrap_err();
+        let egress = find_egress_error(&err).expect("egress error in source chain");
+        assert!(matches!(
+            egress,
+            EgressError::Blocked {
+                reason: "loopback",
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_resolved_looks_up_hostnames() {
+        let policy = EgressPolicy::default();
+        assert_eq!(policy.check_url("http://localhost/"), Ok(()));
+        assert!(matches!(
+            policy.check_resolved("http://localhost/").await,
+            Err(EgressError::Blocked { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_redirect_into_blocked_range_is_refused() {
+        use axum::{response::Redirect, routing::get, Router};
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { Redirect::temporary("http://169.254.169.254/latest/meta-data/") }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // The local server itself is allowed; the hop it redirects to is not.
+        let policy = EgressPolicy::new(vec!["127.0.0.1".parse().unwrap()]);
+        let client = policy.client_builder().build().unwrap();
+        let err = client
+            .get(format!("http://{addr}/"))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            find_egress_error(&err),
+            Some(EgressError::Blocked {
+                reason: "link-local",
+                ..
+            })
+        ));
+    }
+}
@@ -1,87 +1,374 @@
 use regex::Regex;
 use scraper::{Html, Selector};
-use serde::{Deserialize, Serialize};
-
-/// Configuration for a custom data extractor — value object.
-#[derive(Debug, Clone, Deserialize)]
-pub struct ExtractorConfig {
-    pub name: String,
-    #[serde(rename = "type")]
-    pub extractor_type: String, // "css_selector" | "regex"
-    pub selector: String,
-    pub attribute: Option<String>,
+use std::collections::HashSet;
+use thiserror::Error;
+
+use crate::models::{ExtractorConfig, ExtractorMode, ExtractorResult, ExtractorType};
+
+/// Rules per job.
+pub const MAX_EXTRACTORS: usize = 50;
+
+/// Values kept per rule and page in `values` mode.
+const MAX_MATCHES: usize = 50;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ExtractorError {
+    #[error("Extractor {name:?}: invalid CSS selector {selector:?}")]
+    InvalidSelector { name: String, selector: String },
+    #[error("Extractor {name:?}: invalid regex {pattern:?}: {message}")]
+    InvalidRegex {
+        name: String,
+        pattern: String,
+        message: String,
+    },
+    #[error("Extractor {name:?}: unsupported path {path:?}: {message}")]
+    InvalidPath {
+        name: String,
+        path: String,
+        message: String,
+    },
+    #[error("Extractor name is empty")]
+    EmptyName,
+    #[error("Duplicate extractor name {0:?}")]
+    DuplicateName(String),
+    #[error("At most {MAX_EXTRACTORS} extractors per job")]
+    TooMany,
 }
 
-/// Result of running a custom extractor — value object.
-#[derive(Debug, Clone, Serialize)]
-pub struct ExtractorResult {
-    pub name: String,
-    pub matches: Vec<String>,
+#[derive(Debug, Clone)]
+enum Matcher {
+    /// CSS selectors and translated XPath; reads `attribute` or the text.
+    Element {
+        selector: Selector,
+        attribute: Option<String>,
+    },
+    Regex(Regex),
+    JsonLd {
+        schema_type: Option<String>,
+        path: Vec<String>,
+    },
 }
 
-/// Execute all custom extractors against the document.
-pub fn run_extractors(
-    document: &Html,
-    raw_html: &str,
-    configs: &[ExtractorConfig],
-) -> Vec<ExtractorResult> {
-    configs
-        .iter()
-        .map(|config| run_single_extractor(document, raw_html, config))
-        .collect()
+#[derive(Debug, Clone)]
+struct CompiledExtractor {
+    name: String,
+    mode: ExtractorMode,
+    matcher: Matcher,
 }
 
-fn run_single_extractor(
-    document: &Html,
-    raw_html: &str,
-    config: &ExtractorConfig,
-) -> ExtractorResult {
-    let matches = match config.extractor_type.as_str() {
-        "css_selector" => extract_by_css(document, &config.selector, config.attribute.as_deref()),
-        "regex" => extract_by_regex(raw_html, &config.selector),
-        _ => vec![],
-    };
+/// A job's `custom_extractors`, compiled. Invalid rules are rejected up front
+/// so a typo fails the submission instead of silently matching nothing.
+#[derive(Debug, Clone, Default)]
+pub struct Extractors {
+    extractors: Vec<CompiledExtractor>,
+}
+
+impl Extractors {
+    pub fn from_config(configs: &[ExtractorConfig]) -> Result<Self, ExtractorError> {
+        if configs.len() > MAX_EXTRACTORS {
+            return Err(ExtractorError::TooMany);
+        }
+        let mut names = HashSet::new();
+        let extractors = configs
+            .iter()
+            .map(|config| {
+                if config.name.trim().is_empty() {
+                    return Err(ExtractorError::EmptyName);
+                }
+                if !names.insert(config.name.as_str()) {
+                    return Err(ExtractorError::DuplicateName(config.name.clone()));
+                }
+                Ok(CompiledExtractor {
+                    name: config.name.clone(),
+                    mode: config.mode,
+                    matcher: compile(config)?,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Extractors { extractors })
+    }
 
-    ExtractorResult {
-        name: config.name.clone(),
-        matches,
+    pub fn is_empty(&self) -> bool {
+        self.extractors.is_empty()
+    }
+
+    /// Run every rule against a page. `json_ld` holds the page's raw JSON-LD
+    /// script bodies.
+    pub fn run(&self, document: &Html, raw_html: &str, json_ld: &[String]) -> Vec<ExtractorResult> {
+        let needs_json_ld = self
+            .extractors
+            .iter()
+            .any(|e| matches!(e.matcher, Matcher::JsonLd { .. }));
+        let mut nodes = Vec::new();
+        if needs_json_ld {
+            for script in json_ld {
+                if let Ok(value) = serde_json::from_str(script) {
+                    super::flatten_schema_nodes(value, &mut nodes);
+                }
+            }
+        }
+
+        self.extractors
+            .iter()
+            .map(|e| {
+                let values: Box<dyn Iterator<Item = String>> = match &e.matcher {
+                    Matcher::Element {
+                        selector,
+                        attribute,
+                    } if e.mode != ExtractorMode::Values => {
+                        // Counting elements; their values don't matter, but
+                        // the attribute filter does.
+                        Box::new(
+                            document
+                                .select(selector)
+                                .filter(move |el| {
+                                    attribute
+                                        .as_deref()
+                                        .is_none_or(|attr| el.value().attr(attr).is_some())
+                                })
+                                .map(|_| String::new()),
+                        )
+                    }
+                    Matcher::Element {
+                        selector,
+                        attribute,
+                    } => Box::new(document.select(selector).filter_map(
+                        move |el| match attribute {
+                            Some(attr) => el.value().attr(attr).map(str::to_string),
+                            None => {
+                                let text = el.text().collect::<String>().trim().to_string();
+                                (!text.is_empty()).then_some(text)
+                            }
+                        },
+                    )),
+                    Matcher::Regex(re) => Box::new(re.captures_iter(raw_html).filter_map(|cap| {
+                        cap.get(1)
+                            .or_else(|| cap.get(0))
+                            .map(|m| m.as_str().to_string())
+                    })),
+                    Matcher::JsonLd { schema_type, path } => {
+                        let mut found = Vec::new();
+                        for node in nodes
+                            .iter()
+                            .filter(|n| schema_type.as_deref().is_none_or(|t| has_type(n, t)))
+                        {
+                            json_path_values(node, path, &mut found);
+                        }
+                        Box::new(found.into_iter())
+                    }
+                };
+                result(&e.name, e.mode, values)
+            })
+            .collect()
     }
 }
 
-fn extract_by_css(document: &Html, selector_str: &str, attribute: Option<&str>) -> Vec<String> {
-    let selector = match Selector::parse(selector_str) {
-        Ok(s) => s,
-        Err(_) => return vec![],
+fn result(
+    name: &str,
+    mode: ExtractorMode,
+    mut values: impl Iterator<Item = String>,
+) -> ExtractorResult {
+    let mut out = ExtractorResult {
+        name: name.to_string(),
+        matches: Vec::new(),
+        count: None,
+        exists: None,
     };
-
-    document
-        .select(&selector)
-        .filter_map(|el| match attribute {
-            Some(attr) => el.value().attr(attr).map(|s| s.to_string()),
-            None => {
-                let text = el.text().collect::<String>().trim().to_string();
-                if text.is_empty() {
-                    None
-                } else {
-                    Some(text)
+    match mode {
+        ExtractorMode::Values => {
+            let mut count = 0u32;
+            for value in values {
+                if out.matches.len() < MAX_MATCHES {
+                    out.matches.push(value);
                 }
+                count += 1;
             }
-        })
-        .collect()
+            out.count = Some(count);
+        }
+        ExtractorMode::Count => out.count = Some(values.count() as u32),
+        ExtractorMode::Exists => out.exists = Some(values.next().is_some()),
+    }
+    out
 }
 
-fn extract_by_regex(html: &str, pattern: &str) -> Vec<String> {
-    match Regex::new(pattern) {
-        Ok(re) => re
-            .captures_iter(html)
-            .filter_map(|cap| {
-                cap.get(1)
-                    .or_else(|| cap.get(0))
-                    .map(|m| m.as_str().to_string())
+fn compile(config: &ExtractorConfig) -> Result<Matcher, ExtractorError> {
+    let name = config.name.clone();
+    match config.extractor_type {
+        ExtractorType::CssSelector => Ok(Matcher::Element {
+            selector: Selector::parse(&config.selector).map_err(|_| {
+                ExtractorError::InvalidSelector {
+                    name,
+                    selector: config.selector.clone(),
+                }
+            })?,
+            attribute: config.attribute.clone(),
+        }),
+        ExtractorType::Regex => Regex::new(&config.selector)
+            .map(Matcher::Regex)
+            .map_err(|e| ExtractorError::InvalidRegex {
+                name,
+                pattern: config.selector.clone(),
+                message: e.to_string(),
+            }),
+        ExtractorType::Xpath => {
+            let invalid = |message: String| ExtractorError::InvalidPath {
+                name: name.clone(),
+                path: config.selector.clone(),
+                message,
+            };
+            let (css, path_attribute) = xpath_to_css(&config.selector).map_err(invalid)?;
+            let selector = Selector::parse(&css)
+                .map_err(|_| invalid(format!("translates to invalid CSS {:?}", css)))?;
+            Ok(Matcher::Element {
+                selector,
+                attribute: path_attribute.or_else(|| config.attribute.clone()),
             })
-            .take(50) // Limit results to prevent abuse
-            .collect(),
-        Err(_) => vec![],
+        }
+        ExtractorType::JsonLdPath => {
+            let path: Vec<String> = config
+                .selector
+                .trim_start_matches("$.")
+                .split('.')
+                .map(str::to_string)
+                .collect();
+            if path.iter().any(|key| key.is_empty()) {
+                return Err(ExtractorError::InvalidPath {
+                    name,
+                    path: config.selector.clone(),
+                    message: "empty key".to_string(),
+                });
+            }
+            Ok(Matcher::JsonLd {
+                schema_type: config.schema_type.clone(),
+                path,
+            })
+        }
+    }
+}
+
+/// Translate the supported XPath subset (see `ExtractorType::Xpath`) into a
+/// CSS selector plus the attribute named by a final `@attr` step.
+fn xpath_to_css(path: &str) -> Result<(String, Option<String>), String> {
+    let mut rest = path.trim();
+    if !rest.starts_with('/') {
+        return Err("must start with / or //".to_string());
+    }
+    let mut css = String::new();
+    let mut first = true;
+    while !rest.is_empty() {
+        let descendant = rest.starts_with("//");
+        rest = rest
+            .strip_prefix(if descendant { "//" } else { "/" })
+            .ok_or("expected /")?;
+
+        if let Some(attr) = rest.strip_prefix('@') {
+            if first || !is_name(attr) {
+                return Err(format!("bad attribute step @{}", attr));
+            }
+            return Ok((css, Some(attr.to_string())));
+        }
+        if rest == "text()" && !first {
+            return Ok((css, None));
+        }
+
+        let end = rest.find(['/', '[']).unwrap_or(rest.len());
+        let tag = &rest[..end];
+        if tag != "*" && !is_name(tag) {
+            return Err(format!("bad step {:?}", tag));
+        }
+        rest = &rest[end..];
+
+        if !first {
+            css.push_str(if descendant { " " } else { " > " });
+        }
+        css.push_str(tag);
+        if first && !descendant {
+            css.push_str(":root");
+        }
+        while let Some(after) = rest.strip_prefix('[') {
+            let close = after.find(']').ok_or("unclosed [")?;
+            css.push_str(&predicate_to_css(after[..close].trim())?);
+            rest = &after[close + 1..];
+        }
+        first = false;
+    }
+    Ok((css, None))
+}
+
+fn predicate_to_css(predicate: &str) -> Result<String, String> {
+    if let Ok(n) = predicate.parse::<u32>() {
+        return Ok(format!(":nth-of-type({})", n));
+    }
+    for (function, operator) in [("contains(", "*="), ("starts-with(", "^=")] {
+        if let Some(args) = predicate
+            .strip_prefix(function)
+            .and_then(|a| a.strip_suffix(')'))
+        {
+            let (attr, value) = args.split_once(',').ok_or("expected two arguments")?;
+            return attribute_selector(attr.trim(), operator, value.trim());
+        }
+    }
+    match predicate.split_once('=') {
+        Some((attr, value)) => attribute_selector(attr.trim(), "=", value.trim()),
+        None => {
+            let attr = predicate.strip_prefix('@').ok_or("unsupported predicate")?;
+            if !is_name(attr) {
+                return Err(format!("bad attribute {:?}", attr));
+            }
+            Ok(format!("[{}]", attr))
+        }
+    }
+}
+
+fn attribute_selector(attr: &str, operator: &str, quoted: &str) -> Result<String, String> {
+    let attr = attr
+        .strip_prefix('@')
+        .filter(|a| is_name(a))
+        .ok_or_else(|| format!("expected @attribute, got {:?}", attr))?;
+    let value = ['\'', '"']
+        .iter()
+        .find_map(|q| quoted.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+        .ok_or_else(|| format!("expected a quoted string, got {:?}", quoted))?;
+    Ok(format!(
+        "[{}{}\"{}\"]",
+        attr,
+        operator,
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+fn is_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ':')
+}
+
+fn has_type(node: &serde_json::Value, schema_type: &str) -> bool {
+    match node.get("@type") {
+        Some(serde_json::Value::String(t)) => t == schema_type,
+        Some(serde_json::Value::Array(types)) => types.iter().any(|t| t == schema_type),
+        _ => false,
+    }
+}
+
+/// Collect the values at `path` under `value`, descending into arrays.
+fn json_path_values(value: &serde_json::Value, path: &[String], out: &mut Vec<String>) {
+    use serde_json::Value;
+    match (value, path.split_first()) {
+        (Value::Array(items), _) => {
+            for item in items {
+                json_path_values(item, path, out);
+            }
+        }
+        (Value::Object(map), Some((key, rest))) => {
+            if let Some(child) = map.get(key) {
+                json_path_values(child, rest, out);
+            }
+        }
+        (_, Some(_)) | (Value::Null, None) => {}
+        (Value::String(s), None) => out.push(s.clone()),
+        (Value::Object(_), None) => out.push(value.to_string()),
+        (other, None) => out.push(other.to_string()),
     }
 }
 
@@ -89,57 +376,154 @@ fn extract_by_regex(html: &str, pattern: &str) -> Vec<String> {
 mod tests {
     use super::*;
 
+    fn extractors(rules: serde_json::Value) -> Result<Extractors, ExtractorError> {
+        let configs: Vec<ExtractorConfig> = serde_json::from_value(rules).unwrap();
+        Extractors::from_config(&configs)
+    }
+
+    fn run(rules: serde_json::Value, html: &str) -> Vec<ExtractorResult> {
+        let json_ld: Vec<String> = Html::parse_document(html)
+            .select(&Selector::parse(r#"script[type="application/ld+json"]"#).unwrap())
+            .map(|s| s.inner_html())
+            .collect();
+        extractors(rules)
+            .unwrap()
+            .run(&Html::parse_document(html), html, &json_ld)
+    }
+
     #[test]
     fn test_css_text_extraction() {
-        let html =
-            Html::parse_document(r#"<div class="price">$99</div><div class="price">$149</div>"#);
-        let config = ExtractorConfig {
-            name: "prices".to_string(),
-            extractor_type: "css_selector".to_string(),
-            selector: ".price".to_string(),
-            attribute: None,
-        };
-        let results = run_extractors(&html, "", &[config]);
+        let results = run(
+            serde_json::json!([{ "name": "prices", "type": "css_selector", "selector": ".price" }]),
+            r#"<div class="price">$99</div><div class="price">$149</div>"#,
+        );
         assert_eq!(results[0].matches, vec!["$99", "$149"]);
+        assert_eq!(results[0].count, Some(2));
     }
 
     #[test]
     fn test_css_attribute_extraction() {
-        let html = Html::parse_document(r#"<a href="/page1">A</a><a href="/page2">B</a>"#);
-        let config = ExtractorConfig {
-            name: "links".to_string(),
-            extractor_type: "css_selector".to_string(),
-            selector: "a".to_string(),
-            attribute: Some("href".to_string()),
-        };
-        let results = run_extractors(&html, "", &[config]);
+        let results = run(
+            serde_json::json!([{
+                "name": "links", "type": "css_selector", "selector": "a", "attribute": "href"
+            }]),
+            r#"<a href="/page1">A</a><a href="/page2">B</a>"#,
+        );
         assert_eq!(results[0].matches, vec!["/page1", "/page2"]);
     }
 
     #[test]
     fn test_regex_extraction() {
-        let html = r#"<span>Price: $99.00</span><span>Price: $149.00</span>"#;
-        let doc = Html::parse_document(html);
-        let config = ExtractorConfig {
-            name: "prices".to_string(),
-            extractor_type: "regex".to_string(),
-            selector: r"\$(\d+\.\d{2})".to_string(),
-            attribute: None,
-        };
-        let results = run_extractors(&doc, html, &[config]);
+        let results = run(
+            serde_json::json!([{ "name": "prices", "type": "regex", "selector": r"\$(\d+\.\d{2})" }]),
+            r#"<span>Price: $99.00</span><span>Price: $149.00</span>"#,
+        );
         assert_eq!(results[0].matches, vec!["99.00", "149.00"]);
     }
 
     #[test]
-    fn test_invalid_selector() {
-        let html = Html::parse_document("<p>text</p>");
-        let config = ExtractorConfig {
-            name: "bad".to_string(),
-            extractor_type: "css_selector".to_string(),
-            selector: "[[invalid".to_string(),
-            attribute: None,
-        };
-        let results = run_extractors(&html, "", &[config]);
+    fn test_invalid_rules_are_rejected() {
+        let err = extractors(serde_json::json!([
+            { "name": "bad", "type": "css_selector", "selector": "[[invalid" }
+        ]))
+        .unwrap_err();
+        assert!(matches!(err, ExtractorError::InvalidSelector { .. }));
+        assert!(err.to_string().contains("[[invalid"));
+
+        assert!(matches!(
+            extractors(serde_json::json!([{ "name": "r", "type": "regex", "selector": "(" }])),
+            Err(ExtractorError::InvalidRegex { .. })
+        ));
+        assert!(matches!(
+            extractors(serde_json::json!([{ "name": "x", "type": "xpath", "selector": "div" }])),
+            Err(ExtractorError::InvalidPath { .. })
+        ));
+        assert!(matches!(
+            extractors(serde_json::json!([
+                { "name": "a", "type": "regex", "selector": "x" },
+                { "name": "a", "type": "regex", "selector": "y" }
+            ])),
+            Err(ExtractorError::DuplicateName(_))
+        ));
+    }
+
+    #[test]
+    fn test_xpath_translation() {
+        assert_eq!(
+            xpath_to_css("//div[@class='price']/span").unwrap(),
+            (r#"div[class="price"] > span"#.to_string(), None)
+        );
+        assert_eq!(
+            xpath_to_css("/html/body//ul/li[2]/a/@href").unwrap(),
+            (
+                "html:root > body ul > li:nth-of-type(2) > a".to_string(),
+                Some("href".to_string())
+            )
+        );
+        assert_eq!(
+            xpath_to_css("//*[contains(@class, 'sku')]/text()").unwrap(),
+            (r#"*[class*="sku"]"#.to_string(), None)
+        );
+        assert!(xpath_to_css("//div[last()]").is_err());
+    }
+
+    #[test]
+    fn test_xpath_extraction() {
+        let results = run(
+            serde_json::json!([
+                { "name": "author", "type": "xpath", "selector": "//meta[@name='author']/@content" },
+                { "name": "second", "type": "xpath", "selector": "/html/body/ul/li[2]" }
+            ]),
+            r#"<html><head><meta name="author" content="Ada Lovelace"></head>
+               <body><ul><li>One</li><li>Two</li></ul></body></html>"#,
+        );
+        assert_eq!(results[0].matches, vec!["Ada Lovelace"]);
+        assert_eq!(results[1].matches, vec!["Two"]);
+    }
+
+    #[test]
+    fn test_json_ld_paths() {
+        let html = r#"<script type="application/ld+json">
+            {"@context": "https://schema.org", "@graph": [
+                {"@type": "Product", "sku": "A-1",
+                 "offers": [{"price": 19.99}, {"price": "24.00"}]},
+                {"@type": "Organization", "sku": "ignored"}
+            ]}
+        </script>"#;
+        let results = run(
+            serde_json::json!([
+                { "name": "price", "type": "json_ld_path", "selector": "offers.price" },
+                { "name": "sku", "type": "json_ld_path", "selector": "sku", "schema_type": "Product" }
+            ]),
+            html,
+        );
+        assert_eq!(results[0].matches, vec!["19.99", "24.00"]);
+        assert_eq!(results[1].matches, vec!["A-1"]);
+    }
+
+    #[test]
+    fn test_count_and_exists_modes() {
+        let html = r#"<img src="a"><img src="b" alt="B"><img src="c">"#;
+        let results = run(
+            serde_json::json!([
+                { "name": "images", "type": "css_selector", "selector": "img", "mode": "count" },
+                { "name": "has_video", "type": "css_selector", "selector": "video", "mode": "exists" },
+                {
+                    "name": "with_alt", "type": "css_selector", "selector": "img",
+                    "attribute": "alt", "mode": "count"
+                },
+                {
+                    "name": "has_title", "type": "css_selector", "selector": "img",
+                    "attribute": "title", "mode": "exists"
+                }
+            ]),
+            html,
+        );
+        assert_eq!(results[0].count, Some(3));
         assert!(results[0].matches.is_empty());
+        assert_eq!(results[1].exists, Some(false));
+        assert_eq!(results[1].count, None);
+        assert_eq!(results[2].count, Some(1));
+        assert_eq!(results[3].exists, Some(false));
     }
 }
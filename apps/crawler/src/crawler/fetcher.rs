@@ -1,6 +1,6 @@
 use governor::{Quota, RateLimiter};
 use reqwest::Client;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::time::Duration;
@@ -10,6 +10,8 @@ use tokio::time::Instant;
 use url::Url;
 
 use super::circuit_breaker::CircuitBreaker;
+use super::egress::{self, EgressPolicy};
+use crate::models::PreviousValidators;
 
 /// A single hop in a redirect chain — immutable value object.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -34,12 +36,16 @@ pub enum FetchError {
     CircuitOpen,
     #[error("Too many redirects (max 10)")]
     TooManyRedirects,
+    #[error("Blocked by egress policy: {0}")]
+    EgressBlocked(String),
 }
 
 impl FetchError {
     /// Classify a reqwest error into a more specific FetchError variant.
     fn classify(e: reqwest::Error) -> Self {
-        if e.is_timeout() {
+        if let Some(blocked) = egress::find_egress_error(&e) {
+            FetchError::EgressBlocked(blocked.to_string())
+        } else if e.is_timeout() {
             FetchError::TimeoutError(e.to_string())
         } else if e.is_connect() {
             // Check for DNS errors by inspecting the error chain
@@ -58,6 +64,29 @@ impl FetchError {
         }
     }
 
+    /// Stable, machine-readable name of the failure class, reported to the API
+    /// in page outcome records.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FetchError::DnsError(_) => "dns",
+            FetchError::ConnectionError(_) => "connection",
+            FetchError::TimeoutError(_) => "timeout",
+            FetchError::RequestFailed(_) => "request_failed",
+            FetchError::RateLimitError => "rate_limited",
+            FetchError::CircuitOpen => "circuit_open",
+            FetchError::TooManyRedirects => "too_many_redirects",
+            FetchError::EgressBlocked(_) => "egress_blocked",
+        }
+    }
+
+    /// HTTP status of the failed response, when the failure carried one.
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            FetchError::RequestFailed(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+
     /// Whether this error is retryable (timeouts, connection errors).
     fn is_retryable(&self) -> bool {
         matches!(
@@ -84,10 +113,28 @@ type DomainLimiter = RateLimiter<
 >;
 
 /// Per-domain adaptive backoff state.
+#[derive(Default)]
 struct DomainStats {
     consecutive_successes: u32,
     backoff_until: Option<Instant>,
     backoff_count: u32,
+    /// robots.txt `Crawl-delay` being honoured for this domain.
+    crawl_delay: Option<Duration>,
+    /// Longest pause a 429/503 (its `Retry-After`, or our own backoff) put
+    /// on this domain.
+    longest_backoff: Option<Duration>,
+}
+
+impl DomainStats {
+    /// Slowest requests per second the domain was held to: `rate_per_second`,
+    /// or one request per `Crawl-delay` or longest backoff when slower.
+    fn effective_rate(&self, rate_per_second: u32) -> f64 {
+        [self.crawl_delay, self.longest_backoff]
+            .into_iter()
+            .flatten()
+            .map(|d| 1.0 / d.as_secs_f64())
+            .fold(rate_per_second as f64, f64::min)
+    }
 }
 
 /// HTTP fetcher with per-domain rate limiting, adaptive backoff,
@@ -99,6 +146,7 @@ pub struct RateLimitedFetcher {
     rate_per_second: u32,
     domain_stats: Arc<RwLock<HashMap<String, DomainStats>>>,
     circuit_breaker: Arc<CircuitBreaker>,
+    egress: EgressPolicy,
 }
 
 const MAX_RETRY_ATTEMPTS: u32 = 3;
@@ -111,6 +159,24 @@ const BASE_RETRY_DELAY_MS: u64 = 500;
 const SERVER_RETRY_DELAY_MS: u64 = 2000;
 const MAX_BACKOFF_SECS: u64 = 60;
 const BACKOFF_BASE_SECS: u64 = 5;
+/// Upper bound on a robots.txt `Crawl-delay`. Larger values (some sites ask
+/// for an hour) would keep a crawl from ever finishing; we slow to one request
+/// a minute instead.
+const MAX_CRAWL_DELAY_SECS: u64 = 60;
+
+/// Parse a `Retry-After` header: delay-seconds or an HTTP date. A date in the
+/// past means "now". Capped at MAX_BACKOFF_SECS like our own backoff.
+fn parse_retry_after(value: &str, now: std::time::SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(secs) => Duration::from_secs(secs),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()?
+            .duration_since(now)
+            .unwrap_or(Duration::ZERO),
+    };
+    Some(delay.min(Duration::from_secs(MAX_BACKOFF_SECS)))
+}
 
 /// Exponential backoff before a retry attempt. When the previous attempt hit a
 /// server-overload status (429/503), `server_overloaded` selects the longer
@@ -132,8 +198,15 @@ impl RateLimitedFetcher {
     /// - `rate_per_second`: maximum requests per second per domain (e.g. 2)
     /// - `timeout_secs`: per-request timeout in seconds (e.g. 30)
     /// - `user_agent`: custom User-Agent header string
-    pub fn new(rate_per_second: u32, timeout_secs: u64, user_agent: &str) -> Self {
-        let client = Client::builder()
+    /// - `egress`: policy every request and redirect hop is checked against
+    pub fn new(
+        rate_per_second: u32,
+        timeout_secs: u64,
+        user_agent: &str,
+        egress: EgressPolicy,
+    ) -> Self {
+        let client = egress
+            .client_builder()
             .user_agent(user_agent)
             .timeout(Duration::from_secs(timeout_secs))
             .redirect(reqwest::redirect::Policy::none())
@@ -148,6 +221,7 @@ impl RateLimitedFetcher {
             rate_per_second: rate_per_second.max(1),
             domain_stats: Arc::new(RwLock::new(HashMap::new())),
             circuit_breaker: Arc::new(CircuitBreaker::new(5, 30)),
+            egress,
         }
     }
 
@@ -173,6 +247,49 @@ impl RateLimitedFetcher {
             .clone()
     }
 
+    /// Honour a robots.txt `Crawl-delay` for `domain`: its limiter is replaced
+    /// by one that allows a single request per `delay` (capped at
+    /// MAX_CRAWL_DELAY_SECS). Never speeds a domain up past `rate_per_second`.
+    pub async fn set_crawl_delay(&self, domain: &str, delay: Duration) {
+        let domain = domain.to_lowercase();
+        let delay = delay.min(Duration::from_secs(MAX_CRAWL_DELAY_SECS));
+        let base_period = Duration::from_secs(1) / self.rate_per_second;
+        if delay <= base_period {
+            return;
+        }
+        let quota = Quota::with_period(delay).expect("crawl delay is non-zero");
+        self.domain_limiters
+            .write()
+            .await
+            .insert(domain.clone(), Arc::new(RateLimiter::direct(quota)));
+        self.domain_stats
+            .write()
+            .await
+            .entry(domain)
+            .or_default()
+            .crawl_delay = Some(delay);
+    }
+
+    /// Requests per second `domain` is paced at: `rate_per_second`, or slower
+    /// when a `Crawl-delay` or `Retry-After` applied.
+    pub async fn effective_rate(&self, domain: &str) -> f64 {
+        let stats = self.domain_stats.read().await;
+        match stats.get(&domain.to_lowercase()) {
+            Some(s) => s.effective_rate(self.rate_per_second),
+            None => self.rate_per_second as f64,
+        }
+    }
+
+    /// `effective_rate` of every domain fetched so far.
+    pub async fn effective_rates(&self) -> BTreeMap<String, f64> {
+        self.domain_stats
+            .read()
+            .await
+            .iter()
+            .map(|(domain, s)| (domain.clone(), s.effective_rate(self.rate_per_second)))
+            .collect()
+    }
+
     /// Check and apply adaptive backoff for a domain.
     async fn apply_adaptive_backoff(&self, domain: &str) {
         let backoff_until = {
@@ -188,16 +305,19 @@ impl RateLimitedFetcher {
     }
 
     /// Record a server-side rate limit or error (429/503) for adaptive backoff.
-    async fn record_server_backoff(&self, domain: &str) {
+    /// The server's `Retry-After`, when it sent one, replaces our exponential
+    /// guess.
+    async fn record_server_backoff(&self, domain: &str, retry_after: Option<Duration>) {
         let mut stats = self.domain_stats.write().await;
-        let entry = stats.entry(domain.to_string()).or_insert(DomainStats {
-            consecutive_successes: 0,
-            backoff_until: None,
-            backoff_count: 0,
-        });
+        let entry = stats.entry(domain.to_string()).or_default();
 
-        let delay_secs = (BACKOFF_BASE_SECS * 2u64.pow(entry.backoff_count)).min(MAX_BACKOFF_SECS);
-        entry.backoff_until = Some(Instant::now() + Duration::from_secs(delay_secs));
+        let delay = retry_after.unwrap_or_else(|| {
+            Duration::from_secs(
+                (BACKOFF_BASE_SECS * 2u64.pow(entry.backoff_count)).min(MAX_BACKOFF_SECS),
+            )
+        });
+        entry.backoff_until = Some(Instant::now() + delay);
+        entry.longest_backoff = entry.longest_backoff.max(Some(delay));
         entry.backoff_count += 1;
         entry.consecutive_successes = 0;
     }
@@ -205,11 +325,7 @@ impl RateLimitedFetcher {
     /// Record a successful request for adaptive backoff tracking.
     async fn record_success(&self, domain: &str) {
         let mut stats = self.domain_stats.write().await;
-        let entry = stats.entry(domain.to_string()).or_insert(DomainStats {
-            consecutive_successes: 0,
-            backoff_until: None,
-            backoff_count: 0,
-        });
+        let entry = stats.entry(domain.to_string()).or_default();
         entry.consecutive_successes += 1;
         entry.backoff_count = 0;
     }
@@ -217,6 +333,17 @@ impl RateLimitedFetcher {
     /// Fetch a URL with rate limiting, adaptive backoff, circuit breaker,
     /// retry with exponential backoff, and manual redirect tracking.
     pub async fn fetch(&self, url: &str) -> Result<FetchResult, FetchError> {
+        self.fetch_conditional(url, None).await
+    }
+
+    /// Like `fetch`, but sends `If-None-Match` / `If-Modified-Since` from the
+    /// previous crawl's validators. A `304` comes back as a normal
+    /// `FetchResult` with an empty body; the caller decides what to reuse.
+    pub async fn fetch_conditional(
+        &self,
+        url: &str,
+        validators: Option<&PreviousValidators>,
+    ) -> Result<FetchResult, FetchError> {
         // Extract domain for per-domain rate limiting
         let domain = Url::parse(url)
             .ok()
@@ -240,10 +367,14 @@ impl RateLimitedFetcher {
         // Set when the previous attempt returned 429/503, so the next attempt
         // backs off harder (SERVER_RETRY_DELAY_MS) to outlast origin overload.
         let mut server_overloaded = false;
+        // The previous attempt's `Retry-After`; the next attempt waits at
+        // least this long.
+        let mut retry_after: Option<Duration> = None;
 
         for attempt in 0..MAX_RETRY_ATTEMPTS {
             if attempt > 0 {
-                let delay = Duration::from_millis(retry_delay_ms(server_overloaded, attempt));
+                let delay = Duration::from_millis(retry_delay_ms(server_overloaded, attempt))
+                    .max(retry_after.unwrap_or_default());
                 tracing::warn!(
                     url = %url,
                     attempt = attempt + 1,
@@ -254,6 +385,7 @@ impl RateLimitedFetcher {
                 tokio::time::sleep(delay).await;
             }
             server_overloaded = false;
+            retry_after = None;
 
             // Manual redirect-following loop (max 10 hops)
             let mut current_url = url.to_string();
@@ -262,7 +394,22 @@ impl RateLimitedFetcher {
 
             let result: Result<Option<FetchResult>, FetchError> = 'redirect: {
                 for _ in 0..10 {
-                    match self.client.get(&current_url).send().await {
+                    // Every hop, not just the seed: a public page can redirect
+                    // to an internal address.
+                    if let Err(e) = self.egress.check_url(&current_url) {
+                        break 'redirect Err(FetchError::EgressBlocked(e.to_string()));
+                    }
+                    let mut request = self.client.get(&current_url);
+                    if let Some(v) = validators {
+                        if let Some(ref etag) = v.etag {
+                            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+                        }
+                        if let Some(ref last_modified) = v.last_modified {
+                            request =
+                                request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+                        }
+                    }
+                    match request.send().await {
                         Ok(response) => {
                             let status_code = response.status().as_u16();
 
@@ -330,7 +477,11 @@ impl RateLimitedFetcher {
 
                     // 429 or 503: may retry
                     if status_code == 429 || status_code == 503 {
-                        self.record_server_backoff(&domain).await;
+                        retry_after = fetch_result
+                            .headers
+                            .get("retry-after")
+                            .and_then(|v| parse_retry_after(v, std::time::SystemTime::now()));
+                        self.record_server_backoff(&domain, retry_after).await;
                         self.circuit_breaker.record_failure(&domain).await;
 
                         if attempt + 1 < MAX_RETRY_ATTEMPTS {
@@ -361,8 +512,8 @@ impl RateLimitedFetcher {
                         }
                     }
 
-                    // Success (2xx) or non-retryable response
-                    if (200..300).contains(&status_code) {
+                    // Success (2xx / 304 Not Modified) or non-retryable response
+                    if (200..300).contains(&status_code) || status_code == 304 {
                         self.record_success(&domain).await;
                         self.circuit_breaker.record_success(&domain).await;
                     }
@@ -373,6 +524,10 @@ impl RateLimitedFetcher {
                     // Should not reach here (handled by too_many_redirects above)
                     return Err(FetchError::TooManyRedirects);
                 }
+                Err(classified @ FetchError::EgressBlocked(_)) => {
+                    // A policy refusal says nothing about the domain's health.
+                    return Err(classified);
+                }
                 Err(classified) => {
                     if classified.is_retryable() && attempt + 1 < MAX_RETRY_ATTEMPTS {
                         self.circuit_breaker.record_failure(&domain).await;
@@ -425,4 +580,86 @@ mod tests {
             "overloaded window {overloaded}ms should be >= 3x normal {normal}ms"
         );
     }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_http_dates() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+        assert_eq!(parse_retry_after("7", now), Some(Duration::from_secs(7)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        // A date already past means retry now; huge values are capped.
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            parse_retry_after("86400", now),
+            Some(Duration::from_secs(MAX_BACKOFF_SECS))
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn crawl_delay_slows_the_domain_limiter() {
+        let fetcher = RateLimitedFetcher::new(4, 5, "TestBot", EgressPolicy::default());
+        assert_eq!(fetcher.effective_rate("example.com").await, 4.0);
+
+        // A delay shorter than our own pacing changes nothing.
+        fetcher
+            .set_crawl_delay("example.com", Duration::from_millis(100))
+            .await;
+        assert_eq!(fetcher.effective_rate("example.com").await, 4.0);
+
+        fetcher
+            .set_crawl_delay("Example.com", Duration::from_secs(2))
+            .await;
+        assert_eq!(fetcher.effective_rate("example.com").await, 0.5);
+        let limiter = fetcher.get_limiter("example.com").await;
+        assert!(limiter.check().is_ok());
+        assert!(limiter.check().is_err(), "second request must wait 2s");
+
+        fetcher
+            .set_crawl_delay("slow.example", Duration::from_secs(3600))
+            .await;
+        assert_eq!(
+            fetcher.effective_rate("slow.example").await,
+            1.0 / MAX_CRAWL_DELAY_SECS as f64
+        );
+    }
+
+    #[tokio::test]
+    async fn retry_after_sets_domain_backoff() {
+        let fetcher = RateLimitedFetcher::new(2, 5, "TestBot", EgressPolicy::default());
+        fetcher
+            .record_server_backoff("example.com", Some(Duration::from_secs(42)))
+            .await;
+        let until = fetcher.domain_stats.read().await["example.com"]
+            .backoff_until
+            .unwrap();
+        let remaining = until - Instant::now();
+        assert!(remaining > Duration::from_secs(40) && remaining <= Duration::from_secs(42));
+        assert_eq!(fetcher.effective_rate("example.com").await, 1.0 / 42.0);
+    }
+
+    #[tokio::test]
+    async fn effective_rates_cover_every_domain() {
+        let fetcher = RateLimitedFetcher::new(2, 5, "TestBot", EgressPolicy::default());
+        fetcher
+            .set_crawl_delay("docs.example.com", Duration::from_secs(4))
+            .await;
+        fetcher
+            .record_server_backoff("api.example.com", Some(Duration::from_secs(10)))
+            .await;
+        fetcher.record_success("example.com").await;
+        assert_eq!(
+            fetcher.effective_rates().await,
+            BTreeMap::from([
+                ("api.example.com".to_string(), 0.1),
+                ("docs.example.com".to_string(), 0.25),
+                ("example.com".to_string(), 2.0),
+            ])
+        );
+    }
 }
@@ -1,7 +1,12 @@
 use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashSet};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use url::Url;
 
+use super::scope::{normalize_host, ScopeMatcher};
+use super::traps::TrapDetector;
+use super::url_rules::{FilteredCounts, UrlFilter};
+use crate::models::CanonicalizationPolicy;
+
 /// A URL entry in the frontier queue, ordered by priority then depth (shallow first).
 #[derive(Debug, Clone, Eq, PartialEq)]
 struct FrontierEntry {
@@ -25,22 +30,51 @@ impl PartialOrd for FrontierEntry {
     }
 }
 
-/// BFS URL frontier with deduplication and max-depth support.
+/// Filter stat key for URLs dropped because their host's page budget ran out.
+pub const HOST_BUDGET: &str = "max_pages_per_host";
+
+/// BFS URL frontier with deduplication, max-depth support, optional
+/// include/exclude rules, crawl scope, per-host page budgets and crawler-trap
+/// detection for discovered URLs.
 pub struct Frontier {
     queue: BinaryHeap<FrontierEntry>,
     seen: HashSet<String>,
     max_depth: u32,
     crawled: usize,
+    filter: UrlFilter,
+    /// URLs the filter rejected, per rule. Each URL is counted once.
+    filtered: FilteredCounts,
+    canonicalization: CanonicalizationPolicy,
+    traps: TrapDetector,
+    /// Hosts discovered URLs must be on; `None` accepts any host.
+    scope: Option<ScopeMatcher>,
+    max_pages_per_host: Option<u32>,
+    /// URLs handed out per host, counted against `max_pages_per_host`.
+    /// Keyed by `normalize_host`, so `www.` shares its bare host's budget.
+    host_counts: HashMap<String, u32>,
+}
+
+/// The `host_counts` key a URL is budgeted under.
+fn budget_host(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(normalize_host))
+        .unwrap_or_default()
 }
 
 impl Frontier {
     /// Create a new frontier seeded with the given URLs (all at depth 0).
-    pub fn new(seed_urls: &[String], max_depth: u32) -> Self {
+    /// Every URL is canonicalized under `canonicalization` before dedup.
+    pub fn new(
+        seed_urls: &[String],
+        max_depth: u32,
+        canonicalization: CanonicalizationPolicy,
+    ) -> Self {
         let mut queue = BinaryHeap::new();
         let mut seen = HashSet::new();
 
         for raw_url in seed_urls {
-            if let Some(normalized) = normalize_url(raw_url) {
+            if let Some(normalized) = normalize_url(raw_url, &canonicalization) {
                 if seen.insert(normalized.clone()) {
                     queue.push(FrontierEntry {
                         url: normalized,
@@ -56,18 +90,125 @@ impl Frontier {
             seen,
             max_depth,
             crawled: 0,
+            filter: UrlFilter::default(),
+            filtered: FilteredCounts::new(),
+            canonicalization,
+            traps: TrapDetector::default(),
+            scope: None,
+            max_pages_per_host: None,
+            host_counts: HashMap::new(),
         }
     }
 
+    /// The canonical form of `url` under this frontier's policy.
+    pub fn canonicalize(&self, url: &str) -> Option<String> {
+        normalize_url(url, &self.canonicalization)
+    }
+
+    /// Apply include/exclude rules to URLs discovered from here on. Seeds are
+    /// never filtered.
+    pub fn with_filter(mut self, filter: UrlFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Carry over filter counts from before a restart.
+    pub fn with_filtered_counts(mut self, filtered: FilteredCounts) -> Self {
+        self.filtered = filtered;
+        self
+    }
+
+    /// Whether the rules allow `url`. A rejected URL is counted against its
+    /// rule and marked seen, so it is neither counted nor queued again.
+    pub fn admits(&mut self, url: &str) -> bool {
+        let Some(normalized) = self.canonicalize(url) else {
+            return false;
+        };
+        if self.seen.contains(&normalized) {
+            return true;
+        }
+        match self.filter.rejecting_rule(&normalized) {
+            Some(rule) => {
+                *self.filtered.entry(rule.to_string()).or_default() += 1;
+                self.seen.insert(normalized);
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Cut off suspected crawler traps among URLs discovered from here on.
+    pub fn with_trap_detection(mut self, traps: TrapDetector) -> Self {
+        self.traps = traps;
+        self
+    }
+
+    pub fn traps(&self) -> &TrapDetector {
+        &self.traps
+    }
+
+    /// Drop discovered URLs outside `scope`. Seeds are always crawled.
+    pub fn with_scope(mut self, scope: ScopeMatcher) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    /// Hand out at most `max` URLs per host; the rest are dropped when popped
+    /// and counted under `HOST_BUDGET`. `counts` carries over the URLs handed
+    /// out before a restart.
+    pub fn with_host_budget(mut self, max: Option<u32>, counts: HashMap<String, u32>) -> Self {
+        self.max_pages_per_host = max;
+        self.host_counts = HashMap::new();
+        for (host, count) in counts {
+            *self.host_counts.entry(normalize_host(&host)).or_default() += count;
+        }
+        self
+    }
+
+    /// URLs handed out per host so far (only tracked under a host budget).
+    pub fn host_counts(&self) -> &HashMap<String, u32> {
+        &self.host_counts
+    }
+
+    /// `host_counts` for a checkpoint that puts `requeued` URLs back in the
+    /// queue. Each was counted when handed out and is counted again when
+    /// popped after the resume, so it comes off its host's count here.
+    pub fn host_counts_requeuing<'a>(
+        &self,
+        requeued: impl IntoIterator<Item = &'a str>,
+    ) -> HashMap<String, u32> {
+        let mut counts = self.host_counts.clone();
+        if self.max_pages_per_host.is_some() {
+            for url in requeued {
+                if let Some(count) = counts.get_mut(&budget_host(url)) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+        }
+        counts
+    }
+
+    /// URLs kept out by each rule so far.
+    pub fn filtered_counts(&self) -> &FilteredCounts {
+        &self.filtered
+    }
+
     /// Pop the next URL to crawl (shallowest depth first).
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<(String, u32)> {
-        if let Some(entry) = self.queue.pop() {
+        while let Some(entry) = self.queue.pop() {
+            if let Some(max) = self.max_pages_per_host {
+                let count = self.host_counts.entry(budget_host(&entry.url)).or_default();
+                if *count >= max {
+                    *self.filtered.entry(HOST_BUDGET.to_string()).or_default() += 1;
+                    continue;
+                }
+                *count += 1;
+            }
             self.crawled += 1;
-            Some((entry.url, entry.depth))
-        } else {
-            None
+            return Some((entry.url, entry.depth));
         }
+        None
     }
 
     /// Add newly discovered URLs at the given depth with default priority 50.
@@ -83,15 +224,39 @@ impl Frontier {
             return;
         }
         for raw_url in urls {
-            if let Some(normalized) = normalize_url(raw_url) {
-                if self.seen.insert(normalized.clone()) {
-                    self.queue.push(FrontierEntry {
-                        url: normalized,
-                        depth,
-                        priority,
-                    });
-                }
-            }
+            self.push(raw_url, depth, priority, false);
+        }
+    }
+
+    /// Queue a URL the site lists itself (its sitemap) at depth 0. Listed
+    /// URLs skip the trap detector's per-template caps.
+    pub fn add_listed(&mut self, url: &str, priority: u32) {
+        self.push(url, 0, priority, true);
+    }
+
+    fn push(&mut self, raw_url: &str, depth: u32, priority: u32, listed: bool) {
+        let Some(normalized) = self.canonicalize(raw_url) else {
+            return;
+        };
+        if self
+            .scope
+            .as_ref()
+            .is_some_and(|s| !s.contains(&normalized))
+        {
+            return;
+        }
+        if self.seen.contains(&normalized) || !self.admits(&normalized) {
+            return;
+        }
+        // Trapped URLs are marked seen so each is counted once.
+        let admitted = self.traps.admit(&normalized, listed);
+        self.seen.insert(normalized.clone());
+        if admitted {
+            self.queue.push(FrontierEntry {
+                url: normalized,
+                depth,
+                priority,
+            });
         }
     }
 
@@ -104,27 +269,234 @@ impl Frontier {
     pub fn crawled_count(&self) -> usize {
         self.crawled
     }
+
+    /// Snapshot the frontier for checkpointing: every URL seen so far and the
+    /// queued `(url, depth, priority)` entries in pop order.
+    pub fn snapshot(&self) -> (HashSet<String>, Vec<(String, u32, u32)>) {
+        let pending = self
+            .queue
+            .clone()
+            .into_sorted_vec()
+            .into_iter()
+            .rev()
+            .map(|e| (e.url, e.depth, e.priority))
+            .collect();
+        (self.seen.clone(), pending)
+    }
+
+    /// Rebuild a frontier from a checkpoint snapshot. Pending entries are queued
+    /// as-is (they were canonicalized under the same policy and are already in
+    /// `seen`).
+    pub fn restore(
+        seen: HashSet<String>,
+        pending: Vec<(String, u32, u32)>,
+        max_depth: u32,
+        canonicalization: CanonicalizationPolicy,
+    ) -> Self {
+        let mut seen = seen;
+        let queue = pending
+            .into_iter()
+            .map(|(url, depth, priority)| {
+                seen.insert(url.clone());
+                FrontierEntry {
+                    url,
+                    depth,
+                    priority,
+                }
+            })
+            .collect();
+
+        Frontier {
+            queue,
+            seen,
+            max_depth,
+            crawled: 0,
+            filter: UrlFilter::default(),
+            filtered: FilteredCounts::new(),
+            canonicalization,
+            traps: TrapDetector::default(),
+            scope: None,
+            max_pages_per_host: None,
+            host_counts: HashMap::new(),
+        }
+    }
 }
 
-/// Normalize a URL by:
-/// - Parsing it
+/// Tracking and session parameters dropped by `strip_tracking_params`. A
+/// trailing `*` matches a prefix. Compared case-insensitively.
+const TRACKING_PARAMS: &[&str] = &[
+    "utm_*",
+    "gclid",
+    "gclsrc",
+    "dclid",
+    "gbraid",
+    "wbraid",
+    "fbclid",
+    "msclkid",
+    "yclid",
+    "twclid",
+    "ttclid",
+    "li_fat_id",
+    "igshid",
+    "mc_cid",
+    "mc_eid",
+    "_ga",
+    "_gl",
+    "_hsenc",
+    "_hsmi",
+    "hsa_*",
+    "mkt_tok",
+    "jsessionid",
+    "phpsessid",
+    "sessionid",
+    "aspsessionid*",
+];
+
+/// Canonicalize a URL under `policy`. Always:
+/// - Parsing it (which lowercases scheme and host and drops default ports)
 /// - Removing the fragment
 /// - Removing trailing slash from the path (unless path is just "/")
-/// - Lowercasing the scheme and host
-fn normalize_url(raw: &str) -> Option<String> {
+///
+/// then applies the policy's query and path rules. The query is only
+/// rebuilt when a query rule is on, so by default empty parameters and a
+/// bare `?` survive as they did before the policy existed. Idempotent, so
+/// re-canonicalizing an already canonical URL is a no-op.
+pub(crate) fn normalize_url(raw: &str, policy: &CanonicalizationPolicy) -> Option<String> {
     let mut parsed = Url::parse(raw).ok()?;
     parsed.set_fragment(None);
 
-    // Url crate already lowercases scheme and host, so we just need
-    // to handle trailing slash normalization.
-    let path = parsed.path().to_string();
+    let rewrites_query = policy.strip_tracking_params
+        || policy.sort_query_params
+        || policy.normalize_percent_encoding;
+    if let Some(query) = parsed.query().filter(|_| rewrites_query) {
+        let mut params: Vec<String> = query
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .filter(|p| !(policy.strip_tracking_params && is_tracking_param(p, policy)))
+            .map(|p| {
+                if policy.normalize_percent_encoding {
+                    normalize_percent_encoding(p)
+                } else {
+                    p.to_string()
+                }
+            })
+            .collect();
+        if policy.sort_query_params {
+            // Stable, so repeated names keep their relative order.
+            params.sort_by(|a, b| param_name(a).cmp(param_name(b)));
+        }
+        if params.is_empty() {
+            parsed.set_query(None);
+        } else {
+            parsed.set_query(Some(&params.join("&")));
+        }
+    }
+
+    let mut path = parsed.path().to_string();
+    if policy.normalize_percent_encoding {
+        path = normalize_percent_encoding(&path);
+    }
+    if policy.strip_tracking_params {
+        path = strip_session_path_params(&path);
+    }
+    if policy.collapse_index_html {
+        for index in ["/index.html", "/index.htm"] {
+            if path.len() >= index.len()
+                && path[path.len() - index.len()..].eq_ignore_ascii_case(index)
+            {
+                path.truncate(path.len() - index.len() + 1);
+                break;
+            }
+        }
+    }
+    if policy.lowercase_path {
+        path = path.to_lowercase();
+        if policy.normalize_percent_encoding {
+            // Put the escapes back in uppercase.
+            path = normalize_percent_encoding(&path);
+        }
+    }
     if path.len() > 1 && path.ends_with('/') {
-        parsed.set_path(&path[..path.len() - 1]);
+        path.pop();
+    }
+    if path != parsed.path() {
+        parsed.set_path(&path);
     }
 
     Some(parsed.to_string())
 }
 
+fn param_name(param: &str) -> &str {
+    param.split('=').next().unwrap_or(param)
+}
+
+fn is_tracking_param(param: &str, policy: &CanonicalizationPolicy) -> bool {
+    let name = param_name(param);
+    TRACKING_PARAMS
+        .iter()
+        .copied()
+        .chain(policy.extra_tracking_params.iter().map(String::as_str))
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => {
+                name.len() >= prefix.len() && name[..prefix.len()].eq_ignore_ascii_case(prefix)
+            }
+            None => name.eq_ignore_ascii_case(pattern),
+        })
+}
+
+/// Drop `;jsessionid=...`-style parameters from path segments.
+fn strip_session_path_params(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            let mut parts = segment.split(';');
+            let head = parts.next().unwrap_or_default();
+            let kept: Vec<&str> = parts
+                .filter(|p| {
+                    let name = param_name(p);
+                    !(name.eq_ignore_ascii_case("jsessionid")
+                        || name.eq_ignore_ascii_case("phpsessid"))
+                })
+                .collect();
+            std::iter::once(head)
+                .chain(kept)
+                .collect::<Vec<_>>()
+                .join(";")
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Uppercase percent-escapes and decode those of unreserved characters
+/// (RFC 3986 section 6.2.2.2).
+fn normalize_percent_encoding(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = String::with_capacity(input.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            let hex = &input[i + 1..i + 3];
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+                    out.push(byte as char);
+                } else {
+                    out.push('%');
+                    out.push_str(&hex.to_ascii_uppercase());
+                }
+                i += 3;
+                continue;
+            }
+        }
+        let ch = input[i..].chars().next().expect("char boundary");
+        out.push(ch);
+        i += ch.len_utf8();
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -136,14 +508,14 @@ mod tests {
             "https://example.com/page".to_string(),
             "https://example.com/page#section".to_string(), // same after normalization
         ];
-        let frontier = Frontier::new(&seeds, 3);
+        let frontier = Frontier::new(&seeds, 3, Default::default());
         assert_eq!(frontier.pending_count(), 1);
     }
 
     #[test]
     fn test_depth_limit() {
         let seeds = vec!["https://example.com".to_string()];
-        let mut frontier = Frontier::new(&seeds, 2);
+        let mut frontier = Frontier::new(&seeds, 2, Default::default());
 
         // Consume seed
         let _ = frontier.next();
@@ -160,7 +532,7 @@ mod tests {
     #[test]
     fn test_bfs_ordering() {
         let seeds = vec!["https://example.com".to_string()];
-        let mut frontier = Frontier::new(&seeds, 5);
+        let mut frontier = Frontier::new(&seeds, 5, Default::default());
 
         // Consume seed (depth 0)
         let (url, depth) = frontier.next().unwrap();
@@ -192,7 +564,7 @@ mod tests {
             "https://example.com/a".to_string(),
             "https://example.com/b".to_string(),
         ];
-        let mut frontier = Frontier::new(&seeds, 3);
+        let mut frontier = Frontier::new(&seeds, 3, Default::default());
         assert_eq!(frontier.crawled_count(), 0);
 
         frontier.next();
@@ -208,19 +580,352 @@ mod tests {
             "https://example.com/page/".to_string(),
             "https://example.com/page".to_string(),
         ];
-        let frontier = Frontier::new(&seeds, 3);
+        let frontier = Frontier::new(&seeds, 3, Default::default());
         // Both should normalize to the same URL
         assert_eq!(frontier.pending_count(), 1);
     }
 
+    #[test]
+    fn test_snapshot_restore_roundtrip() {
+        let seeds = vec!["https://example.com".to_string()];
+        let mut frontier = Frontier::new(&seeds, 3, Default::default());
+        let _ = frontier.next();
+        frontier.add_discovered(&["https://example.com/a".to_string()], 1);
+        frontier.add_discovered_with_priority(&["https://example.com/b".to_string()], 2, 80);
+
+        let (seen, pending) = frontier.snapshot();
+        assert_eq!(seen.len(), 3);
+        // Pop order: higher priority first.
+        assert_eq!(pending[0], ("https://example.com/b".to_string(), 2, 80));
+
+        let mut restored = Frontier::restore(seen, pending, 3, Default::default());
+        assert_eq!(restored.pending_count(), 2);
+        // Already-crawled seed must not be re-queued.
+        restored.add_discovered(&["https://example.com".to_string()], 1);
+        assert_eq!(restored.pending_count(), 2);
+        assert_eq!(restored.next().unwrap().0, "https://example.com/b");
+    }
+
     #[test]
     fn test_add_discovered_dedup() {
         let seeds = vec!["https://example.com".to_string()];
-        let mut frontier = Frontier::new(&seeds, 3);
+        let mut frontier = Frontier::new(&seeds, 3, Default::default());
         let _ = frontier.next();
 
         frontier.add_discovered(&["https://example.com/a".to_string()], 1);
         frontier.add_discovered(&["https://example.com/a".to_string()], 1);
         assert_eq!(frontier.pending_count(), 1);
     }
+
+    #[test]
+    fn test_filtered_urls_are_counted_once() {
+        let config = serde_json::from_value(serde_json::json!({
+            "seed_urls": [], "max_pages": 10, "max_depth": 3,
+            "exclude_patterns": [{ "type": "glob", "pattern": "/tag/**" }]
+        }))
+        .unwrap();
+        let seeds = vec!["https://example.com/tag/seed".to_string()];
+        let mut frontier = Frontier::new(&seeds, 3, Default::default())
+            .with_filter(UrlFilter::from_config(&config).unwrap());
+        // Seeds bypass the rules.
+        assert_eq!(frontier.next().unwrap().0, "https://example.com/tag/seed");
+
+        let links = vec![
+            "https://example.com/tag/a".to_string(),
+            "https://example.com/post".to_string(),
+        ];
+        frontier.add_discovered(&links, 1);
+        frontier.add_discovered(&links, 2);
+        assert_eq!(frontier.pending_count(), 1);
+        assert_eq!(
+            frontier.filtered_counts().get("exclude:glob:/tag/**"),
+            Some(&1)
+        );
+    }
+
+    /// Deny rules see the canonical URL: with tracking parameters stripped a
+    /// `utm_*` rule never fires and the page is crawled without them; with
+    /// stripping off, the rule rejects the URL.
+    #[test]
+    fn test_denied_params_apply_after_canonicalization() {
+        let config = serde_json::from_value(serde_json::json!({
+            "seed_urls": [], "max_pages": 10, "max_depth": 3,
+            "denied_query_params": ["utm_*"]
+        }))
+        .unwrap();
+        let filter = UrlFilter::from_config(&config).unwrap();
+        let link = vec!["https://example.com/post?utm_source=x".to_string()];
+
+        let stripping = CanonicalizationPolicy {
+            strip_tracking_params: true,
+            ..Default::default()
+        };
+        let mut frontier = Frontier::new(&[], 3, stripping).with_filter(filter.clone());
+        frontier.add_discovered(&link, 0);
+        assert_eq!(frontier.next().unwrap().0, "https://example.com/post");
+        assert!(frontier.filtered_counts().is_empty());
+
+        let keeping = CanonicalizationPolicy {
+            strip_tracking_params: false,
+            ..Default::default()
+        };
+        let mut frontier = Frontier::new(&[], 3, keeping).with_filter(filter);
+        frontier.add_discovered(&link, 0);
+        assert!(frontier.next().is_none());
+        assert_eq!(
+            frontier.filtered_counts().get("denied_query_param:utm_*"),
+            Some(&1)
+        );
+    }
+
+    #[test]
+    fn test_scope_and_host_budget() {
+        let seeds = vec!["https://www.example.com/".to_string()];
+        let scope = ScopeMatcher::new(&crate::models::CrawlScope::Domain, &seeds);
+        let mut frontier = Frontier::new(&seeds, 3, Default::default())
+            .with_scope(scope)
+            .with_host_budget(Some(2), HashMap::new());
+        assert!(frontier.next().is_some());
+
+        let links: Vec<String> = [
+            "https://www.example.com/a",
+            "https://www.example.com/b",
+            "https://example.com/c",
+            "https://blog.example.com/post",
+            "https://other.org/page",
+        ]
+        .iter()
+        .map(|u| u.to_string())
+        .collect();
+        frontier.add_discovered(&links, 1);
+        // other.org is out of scope and never queued.
+        assert_eq!(frontier.pending_count(), 4);
+
+        let mut popped = Vec::new();
+        while let Some((url, _)) = frontier.next() {
+            popped.push(url);
+        }
+        // www.example.com had used one of its two pages on the seed, and
+        // example.com shares that budget.
+        assert_eq!(popped.len(), 2);
+        assert!(popped.contains(&"https://blog.example.com/post".to_string()));
+        assert_eq!(frontier.filtered_counts().get(HOST_BUDGET), Some(&2));
+        assert_eq!(frontier.host_counts().get("example.com"), Some(&2));
+    }
+
+    #[test]
+    fn test_resume_at_host_budget_keeps_in_flight_urls() {
+        let seeds = vec![
+            "https://example.com/".to_string(),
+            "https://example.com/a".to_string(),
+        ];
+        let mut frontier =
+            Frontier::new(&seeds, 3, Default::default()).with_host_budget(Some(2), HashMap::new());
+        frontier.next().unwrap();
+        let (in_flight, depth) = frontier.next().unwrap();
+        assert_eq!(frontier.host_counts().get("example.com"), Some(&2));
+
+        // The checkpoint re-queues the in-flight URL, as the job loop does.
+        let counts = frontier.host_counts_requeuing([in_flight.as_str()]);
+        let (seen, mut pending) = frontier.snapshot();
+        pending.insert(0, (in_flight.clone(), depth, u32::MAX));
+        let mut restored = Frontier::restore(seen, pending, 3, Default::default())
+            .with_host_budget(Some(2), counts);
+
+        assert_eq!(restored.next(), Some((in_flight, depth)));
+        assert_eq!(restored.next(), None);
+        assert_eq!(restored.filtered_counts().get(HOST_BUDGET), None);
+    }
+
+    fn canon(url: &str, policy: &CanonicalizationPolicy) -> String {
+        normalize_url(url, policy).unwrap()
+    }
+
+    /// The default is the canonicalization from before the policy existed:
+    /// query strings and escapes are left exactly as found.
+    #[test]
+    fn test_default_canonicalization() {
+        let policy = CanonicalizationPolicy::default();
+        assert_eq!(
+            canon(
+                "HTTPS://Example.com:443/a/?utm_source=x&b=2&gclid=1&a=1#top",
+                &policy
+            ),
+            "https://example.com/a?utm_source=x&b=2&gclid=1&a=1"
+        );
+        assert_eq!(
+            canon("https://example.com/%7euser/caf%c3%a9?q=%2f", &policy),
+            "https://example.com/%7euser/caf%c3%a9?q=%2f"
+        );
+        assert_eq!(
+            canon("https://example.com/shop;jsessionid=ABC123/item", &policy),
+            "https://example.com/shop;jsessionid=ABC123/item"
+        );
+        assert_eq!(
+            canon("https://example.com/Docs/index.html", &policy),
+            "https://example.com/Docs/index.html"
+        );
+    }
+
+    /// `normalize_url` as it was before `CanonicalizationPolicy`.
+    fn baseline_normalize(raw: &str) -> Option<String> {
+        let mut parsed = Url::parse(raw).ok()?;
+        parsed.set_fragment(None);
+        let path = parsed.path().to_string();
+        if path.len() > 1 && path.ends_with('/') {
+            parsed.set_path(&path[..path.len() - 1]);
+        }
+        Some(parsed.to_string())
+    }
+
+    #[test]
+    fn test_default_matches_baseline() {
+        let policy = CanonicalizationPolicy::default();
+        for url in [
+            "https://example.com/a?",
+            "https://example.com/a/?",
+            "https://example.com/a?&b=1&&c=&d",
+            "https://example.com/?q=a%20b&q=c+d#frag",
+            "http://Example.com:80/%7e/Path/;x=1/",
+            "https://example.com/index.html?utm_source=x",
+            "https://example.com",
+        ] {
+            assert_eq!(
+                normalize_url(url, &policy),
+                baseline_normalize(url),
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_query_and_encoding_rules() {
+        let policy = CanonicalizationPolicy {
+            strip_tracking_params: true,
+            sort_query_params: true,
+            normalize_percent_encoding: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            canon(
+                "HTTPS://Example.com:443/a/?utm_source=x&b=2&gclid=1&a=1#top",
+                &policy
+            ),
+            "https://example.com/a?a=1&b=2"
+        );
+        // Repeated names keep their order.
+        assert_eq!(
+            canon("https://example.com/?z=1&a=2&a=1", &policy),
+            "https://example.com/?a=2&a=1&z=1"
+        );
+        assert_eq!(
+            canon("https://example.com/%7euser/caf%c3%a9?q=%2f", &policy),
+            "https://example.com/~user/caf%C3%A9?q=%2F"
+        );
+        assert_eq!(
+            canon(
+                "https://example.com/shop;jsessionid=ABC123/item?PHPSESSID=x",
+                &policy
+            ),
+            "https://example.com/shop/item"
+        );
+    }
+
+    #[test]
+    fn test_optional_canonicalization_rules() {
+        let policy = CanonicalizationPolicy {
+            strip_tracking_params: true,
+            normalize_percent_encoding: true,
+            collapse_index_html: true,
+            lowercase_path: true,
+            extra_tracking_params: vec!["ref".into(), "src_*".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            canon(
+                "https://example.com/Docs/INDEX.HTML?ref=nav&src_a=1",
+                &policy
+            ),
+            "https://example.com/docs"
+        );
+        assert_eq!(
+            canon("https://example.com/A%2fB", &policy),
+            "https://example.com/a%2Fb"
+        );
+    }
+
+    #[test]
+    fn test_canonicalization_is_idempotent() {
+        let policy = CanonicalizationPolicy {
+            strip_tracking_params: true,
+            sort_query_params: true,
+            normalize_percent_encoding: true,
+            collapse_index_html: true,
+            lowercase_path: true,
+            ..Default::default()
+        };
+        for url in [
+            "https://example.com/A/%7E%41/index.htm?b=%41&a&utm_medium=x",
+            "https://example.com/;jsessionid=1?",
+            "https://example.com/%E2%82%AC/x/",
+        ] {
+            let once = canon(url, &policy);
+            assert_eq!(canon(&once, &policy), once, "{url}");
+        }
+    }
+
+    #[test]
+    fn test_tracking_variants_share_a_frontier_slot() {
+        let seeds = vec!["https://example.com/".to_string()];
+        let policy = CanonicalizationPolicy {
+            strip_tracking_params: true,
+            sort_query_params: true,
+            ..Default::default()
+        };
+        let mut frontier = Frontier::new(&seeds, 3, policy);
+        let _ = frontier.next();
+        frontier.add_discovered(
+            &[
+                "https://example.com/p?b=2&a=1".to_string(),
+                "https://example.com/p?a=1&b=2&utm_campaign=spring".to_string(),
+                "https://example.com/p/?fbclid=abc&a=1&b=2".to_string(),
+            ],
+            1,
+        );
+        assert_eq!(frontier.pending_count(), 1);
+        assert_eq!(frontier.next().unwrap().0, "https://example.com/p?a=1&b=2");
+    }
+
+    #[test]
+    fn test_trap_urls_are_skipped_but_listed_urls_are_not_capped() {
+        let seeds = vec!["https://example.com/".to_string()];
+        let mut frontier = Frontier::new(&seeds, 5, Default::default()).with_trap_detection(
+            TrapDetector::new(crate::models::TrapDetectionConfig {
+                max_urls_per_template: 2,
+                ..Default::default()
+            }),
+        );
+        let _ = frontier.next();
+
+        for id in 1..=3 {
+            frontier.add_listed(&format!("https://example.com/p/{id}"), 80);
+        }
+        let links: Vec<String> = (4..=8)
+            .map(|id| format!("https://example.com/p/{id}"))
+            .chain(["https://example.com/a/b/a/b/a/b".to_string()])
+            .collect();
+        frontier.add_discovered(&links, 1);
+        frontier.add_discovered(&links, 2);
+
+        // 3 listed + 2 linked under the cap.
+        assert_eq!(frontier.pending_count(), 5);
+        let report = frontier.traps().report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].pattern, "example.com/p/{n}");
+        assert_eq!(report[0].skipped_count, 3);
+        assert_eq!(
+            report[1].kind,
+            crate::models::CrawlTrapKind::RepeatedSegments
+        );
+    }
 }
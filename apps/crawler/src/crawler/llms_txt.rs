@@ -0,0 +1,527 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use url::Url;
+
+use super::egress::EgressPolicy;
+use super::fetcher::RateLimitedFetcher;
+use super::markdown::estimate_tokens;
+use crate::models::{
+    LlmsFullTxt, LlmsTxtBrokenLink, LlmsTxtReport, LlmsTxtSection, DEFAULT_USER_AGENT,
+};
+
+/// Bytes of llms.txt we parse. The format is an index, so anything larger is
+/// almost certainly not one.
+const MAX_LLMS_TXT_BYTES: usize = 1024 * 1024;
+
+/// Bytes of llms-full.txt we count before giving up on reaching the end.
+const MAX_LLMS_FULL_TXT_BYTES: usize = 50 * 1024 * 1024;
+
+/// Leading bytes of llms-full.txt kept to tell a text file from an HTML page.
+const SNIFF_BYTES: usize = 1024;
+
+/// Same-site links fetched to check they resolve; the rest are left unchecked.
+const MAX_CHECKED_LINKS: usize = 20;
+
+/// A markdown list link: `- [title](url): description`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmsTxtLink {
+    pub title: String,
+    /// Resolved against the llms.txt URL.
+    pub url: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmsTxtDocumentSection {
+    pub title: String,
+    pub links: Vec<LlmsTxtLink>,
+}
+
+/// An llms.txt file parsed into the structure the proposal defines: an H1
+/// title, an optional blockquote summary, free-form details, then H2 sections
+/// of link lists.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LlmsTxtDocument {
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub sections: Vec<LlmsTxtDocumentSection>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl LlmsTxtDocument {
+    pub fn links(&self) -> impl Iterator<Item = &LlmsTxtLink> {
+        self.sections.iter().flat_map(|s| s.links.iter())
+    }
+}
+
+/// Parse and validate llms.txt content. Relative link URLs resolve against
+/// `base`.
+pub fn parse(content: &str, base: &Url) -> LlmsTxtDocument {
+    let mut doc = LlmsTxtDocument::default();
+    let mut seen_content = false;
+    let mut in_fence = false;
+    // The summary is the blockquote directly under the title.
+    let mut summary_open = false;
+
+    let issue = |list: &mut Vec<String>, code: &str| {
+        if !list.iter().any(|c| c == code) {
+            list.push(code.to_string());
+        }
+    };
+
+    for raw in content.trim_start_matches('\u{feff}').lines() {
+        let line = raw.trim_end();
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            seen_content = true;
+            summary_open = false;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if line.trim().is_empty() {
+            // A blank line after the summary's first line ends it.
+            if doc.summary.is_some() {
+                summary_open = false;
+            }
+            continue;
+        }
+
+        if let Some(title) = line.strip_prefix("# ") {
+            if doc.title.is_some() {
+                issue(&mut doc.errors, "multiple_titles");
+            } else {
+                if seen_content {
+                    issue(&mut doc.errors, "title_not_first");
+                }
+                doc.title = Some(title.trim().to_string());
+                summary_open = doc.sections.is_empty();
+            }
+            seen_content = true;
+            continue;
+        }
+        seen_content = true;
+
+        if let Some(title) = line.strip_prefix("## ") {
+            doc.sections.push(LlmsTxtDocumentSection {
+                title: title.trim().to_string(),
+                links: Vec::new(),
+            });
+            summary_open = false;
+            continue;
+        }
+
+        if summary_open {
+            if let Some(quote) = line.strip_prefix('>') {
+                let quote = quote.trim();
+                doc.summary = Some(match doc.summary.take() {
+                    Some(prev) if !quote.is_empty() => format!("{} {}", prev, quote),
+                    Some(prev) => prev,
+                    None => quote.to_string(),
+                });
+                continue;
+            }
+        }
+        summary_open = false;
+
+        // List items only carry meaning inside an H2 section; before the first
+        // one they're free-form details.
+        let Some(section) = doc.sections.last_mut() else {
+            continue;
+        };
+        let trimmed = line.trim_start();
+        let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker))
+        else {
+            continue;
+        };
+        match parse_link_item(item) {
+            Some((title, href, description)) => match base.join(href) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {
+                    section.links.push(LlmsTxtLink {
+                        title,
+                        url: url.to_string(),
+                        description,
+                    });
+                }
+                _ => issue(&mut doc.errors, "invalid_link_url"),
+            },
+            None => issue(&mut doc.warnings, "malformed_link_item"),
+        }
+    }
+
+    if doc.title.is_none() {
+        issue(&mut doc.errors, "missing_title");
+    }
+    if doc.summary.is_none() {
+        issue(&mut doc.warnings, "missing_summary");
+    }
+    if doc.sections.iter().any(|s| s.links.is_empty()) {
+        issue(&mut doc.warnings, "empty_section");
+    }
+    if doc.links().next().is_none() {
+        issue(&mut doc.warnings, "no_links");
+    }
+    doc
+}
+
+/// Split `[title](url): description` into its parts.
+fn parse_link_item(item: &str) -> Option<(String, &str, Option<String>)> {
+    let rest = item.trim().strip_prefix('[')?;
+    let (title, rest) = rest.split_once("](")?;
+    let (href, rest) = rest.split_once(')')?;
+    let href = href.trim();
+    if title.trim().is_empty() || href.is_empty() {
+        return None;
+    }
+    let rest = rest.trim();
+    let description = match rest.strip_prefix(':') {
+        Some(desc) if !desc.trim().is_empty() => Some(desc.trim().to_string()),
+        Some(_) => None,
+        None if rest.is_empty() => None,
+        // Trailing text without the `:` separator.
+        None => return None,
+    };
+    Some((title.trim().to_string(), href, description))
+}
+
+/// Whether a 200 response is a stand-in rather than a text file: an empty
+/// body, or an HTML page from a soft 404 or catch-all route.
+pub fn is_placeholder(content_type: Option<&str>, body: &str) -> bool {
+    let body = body.trim_start_matches('\u{feff}').trim_start();
+    if body.trim_end().is_empty() {
+        return true;
+    }
+    if content_type.is_some_and(|ct| ct.to_ascii_lowercase().contains("text/html")) {
+        return true;
+    }
+    let head: String = body
+        .chars()
+        .take(16)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    head.starts_with("<!doctype html") || head.starts_with("<html")
+}
+
+/// Fetch, parse and validate /llms.txt for the domain, checking its same-site
+/// links through the crawl's fetcher. `None` when the file isn't served.
+pub async fn audit_llms_txt(
+    domain: &str,
+    egress: &EgressPolicy,
+    fetcher: &RateLimitedFetcher,
+) -> Option<LlmsTxtReport> {
+    audit_url(&format!("https://{}/llms.txt", domain), egress, fetcher).await
+}
+
+/// `audit_llms_txt` for an explicit llms.txt URL.
+pub async fn audit_url(
+    url: &str,
+    egress: &EgressPolicy,
+    fetcher: &RateLimitedFetcher,
+) -> Option<LlmsTxtReport> {
+    let file = fetch_text(url, egress, MAX_LLMS_TXT_BYTES, MAX_LLMS_TXT_BYTES).await?;
+    let body = String::from_utf8_lossy(&file.body);
+    let base = Url::parse(&file.url).ok()?;
+
+    let mut report = LlmsTxtReport {
+        url: file.url.clone(),
+        size_bytes: file.size_bytes,
+        token_estimate: estimate_tokens(file.size_bytes),
+        is_placeholder: false,
+        is_valid: false,
+        title: None,
+        summary: None,
+        sections: Vec::new(),
+        link_count: 0,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        external_link_count: 0,
+        links_checked: 0,
+        broken_links: Vec::new(),
+    };
+
+    if is_placeholder(file.content_type.as_deref(), &body) {
+        report.is_placeholder = true;
+        report.errors.push(
+            if body.trim().is_empty() {
+                "empty"
+            } else {
+                "html_content"
+            }
+            .to_string(),
+        );
+        return Some(report);
+    }
+
+    let doc = parse(&body, &base);
+    report.is_valid = doc.errors.is_empty();
+    report.sections = doc
+        .sections
+        .iter()
+        .map(|s| LlmsTxtSection {
+            title: s.title.clone(),
+            link_count: s.links.len() as u32,
+            optional: s.title.eq_ignore_ascii_case("optional"),
+        })
+        .collect();
+    report.link_count = doc.links().count() as u32;
+
+    let (internal, external): (Vec<&LlmsTxtLink>, Vec<&LlmsTxtLink>) =
+        doc.links().partition(|link| same_site(&base, &link.url));
+    report.external_link_count = external.len() as u32;
+
+    let mut seen = HashSet::new();
+    let to_check: Vec<&str> = internal
+        .iter()
+        .map(|l| l.url.as_str())
+        .filter(|u| seen.insert(*u))
+        .take(MAX_CHECKED_LINKS)
+        .collect();
+    let results = futures::future::join_all(to_check.iter().map(|link| fetcher.fetch(link))).await;
+    report.links_checked = to_check.len() as u32;
+    report.broken_links = to_check
+        .iter()
+        .zip(results)
+        .filter_map(|(link, result)| match result {
+            Ok(resp) if resp.status_code < 400 => None,
+            Ok(resp) => Some(LlmsTxtBrokenLink {
+                url: link.to_string(),
+                status_code: Some(resp.status_code),
+                error: None,
+            }),
+            Err(e) => Some(LlmsTxtBrokenLink {
+                url: link.to_string(),
+                status_code: None,
+                error: Some(e.kind().to_string()),
+            }),
+        })
+        .collect();
+
+    report.title = doc.title;
+    report.summary = doc.summary;
+    report.errors = doc.errors;
+    report.warnings = doc.warnings;
+    Some(report)
+}
+
+/// Size up /llms-full.txt for the domain. `None` when it isn't served or is a
+/// placeholder page.
+pub async fn fetch_llms_full_txt(domain: &str, egress: &EgressPolicy) -> Option<LlmsFullTxt> {
+    fetch_llms_full_txt_url(&format!("https://{}/llms-full.txt", domain), egress).await
+}
+
+/// `fetch_llms_full_txt` for an explicit URL.
+pub async fn fetch_llms_full_txt_url(url: &str, egress: &EgressPolicy) -> Option<LlmsFullTxt> {
+    let file = fetch_text(url, egress, SNIFF_BYTES, MAX_LLMS_FULL_TXT_BYTES).await?;
+    if is_placeholder(
+        file.content_type.as_deref(),
+        &String::from_utf8_lossy(&file.body),
+    ) {
+        return None;
+    }
+    Some(LlmsFullTxt {
+        url: file.url,
+        size_bytes: file.size_bytes,
+        token_estimate: estimate_tokens(file.size_bytes),
+        truncated: file.truncated,
+    })
+}
+
+/// Hosts match, ignoring a leading `www.` on either side.
+fn same_site(base: &Url, link: &str) -> bool {
+    let strip = |h: &str| h.trim_start_matches("www.").to_ascii_lowercase();
+    match (base.host_str(), Url::parse(link).ok()) {
+        (Some(base_host), Some(link)) => link.host_str().map(strip) == Some(strip(base_host)),
+        _ => false,
+    }
+}
+
+struct TextFile {
+    /// Final URL after redirects.
+    url: String,
+    content_type: Option<String>,
+    /// The first `keep_bytes` of the body.
+    body: Vec<u8>,
+    size_bytes: u64,
+    truncated: bool,
+}
+
+/// GET a text file, keeping its first `keep_bytes` and counting up to
+/// `max_bytes`. `None` on any non-2xx or transport error.
+async fn fetch_text(
+    url: &str,
+    egress: &EgressPolicy,
+    keep_bytes: usize,
+    max_bytes: usize,
+) -> Option<TextFile> {
+    egress.check_url(url).ok()?;
+    let client = egress
+        .client_builder()
+        .timeout(Duration::from_secs(30))
+        .user_agent(DEFAULT_USER_AGENT)
+        .build()
+        .ok()?;
+
+    let mut response = client.get(url).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let final_url = response.url().to_string();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let mut body = Vec::new();
+    let mut size_bytes: u64 = 0;
+    let mut truncated = false;
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                size_bytes += chunk.len() as u64;
+                let room = keep_bytes.saturating_sub(body.len());
+                body.extend_from_slice(&chunk[..room.min(chunk.len())]);
+                if size_bytes >= max_bytes as u64 {
+                    truncated = true;
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                tracing::debug!(url = %url, error = %e, "Body read failed; keeping what arrived");
+                truncated = true;
+                break;
+            }
+        }
+    }
+
+    Some(TextFile {
+        url: final_url,
+        content_type,
+        body,
+        size_bytes,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+# FastHTML
+
+> FastHTML is a python library which brings together Starlette, Uvicorn,
+> HTMX, and fastcore's `FT` FastTags into a library for creating server-rendered hypermedia applications.
+
+Important notes:
+
+- Although parts of its API are inspired by FastAPI, it is *not* compatible with FastAPI syntax
+
+## Docs
+
+- [FastHTML quick start](https://fastht.ml/docs/tutorials/quickstart_for_web_devs.html.md): A brief overview of many FastHTML features
+- [HTMX reference](/docs/htmx.md): Brief description of all HTMX attributes
+
+## Optional
+
+- [Starlette full documentation](https://gist.githubusercontent.com/starlette.md)
+";
+
+    fn base() -> Url {
+        Url::parse("https://fastht.ml/llms.txt").unwrap()
+    }
+
+    #[test]
+    fn test_parses_the_proposal_example() {
+        let doc = parse(SAMPLE, &base());
+        assert_eq!(doc.title.as_deref(), Some("FastHTML"));
+        assert!(doc.summary.as_deref().unwrap().starts_with(
+            "FastHTML is a python library which brings together Starlette, Uvicorn, HTMX"
+        ));
+        assert_eq!(doc.sections.len(), 2);
+        assert_eq!(doc.sections[0].title, "Docs");
+        assert_eq!(doc.sections[0].links.len(), 2);
+        assert_eq!(
+            doc.sections[0].links[1].url,
+            "https://fastht.ml/docs/htmx.md"
+        );
+        assert_eq!(
+            doc.sections[0].links[1].description.as_deref(),
+            Some("Brief description of all HTMX attributes")
+        );
+        assert_eq!(doc.sections[1].links[0].description, None);
+        // The list item in the details block isn't a link and isn't flagged.
+        assert!(doc.errors.is_empty(), "{:?}", doc.errors);
+        assert!(doc.warnings.is_empty(), "{:?}", doc.warnings);
+    }
+
+    #[test]
+    fn test_flags_structural_errors() {
+        let doc = parse("Intro text\n# Title\n# Another\n## Docs\n", &base());
+        assert_eq!(doc.errors, vec!["title_not_first", "multiple_titles"]);
+        assert!(doc.warnings.contains(&"empty_section".to_string()));
+        assert!(doc.warnings.contains(&"no_links".to_string()));
+        assert!(doc.warnings.contains(&"missing_summary".to_string()));
+
+        let doc = parse("## Docs\n- [a](/a)\n", &base());
+        assert_eq!(doc.errors, vec!["missing_title"]);
+    }
+
+    #[test]
+    fn test_flags_bad_link_items() {
+        let doc = parse(
+            "# T\n> s\n## Docs\n- just text\n- [bad](mailto:x@example.com)\n- [ok](/ok)\n",
+            &base(),
+        );
+        assert_eq!(doc.errors, vec!["invalid_link_url"]);
+        assert_eq!(doc.warnings, vec!["malformed_link_item"]);
+        assert_eq!(doc.links().count(), 1);
+    }
+
+    #[test]
+    fn test_ignores_headings_inside_code_fences() {
+        let doc = parse(
+            "# T\n> s\n```\n# not a title\n```\n## Docs\n- [a](/a)\n",
+            &base(),
+        );
+        assert!(doc.errors.is_empty(), "{:?}", doc.errors);
+    }
+
+    #[test]
+    fn test_detects_placeholders() {
+        assert!(is_placeholder(Some("text/plain"), "  \n"));
+        assert!(is_placeholder(Some("text/html; charset=utf-8"), "# Title"));
+        assert!(is_placeholder(None, "<!DOCTYPE html><html>404</html>"));
+        assert!(is_placeholder(Some("text/plain"), "\n<html><body>"));
+        assert!(!is_placeholder(Some("text/markdown"), "# Title\n"));
+        assert!(!is_placeholder(None, "# Title\n"));
+    }
+
+    #[test]
+    fn test_parse_link_item() {
+        assert_eq!(
+            parse_link_item("[Docs](/docs.md): The docs"),
+            Some(("Docs".to_string(), "/docs.md", Some("The docs".to_string())))
+        );
+        assert_eq!(
+            parse_link_item("[Docs](/docs.md)"),
+            Some(("Docs".to_string(), "/docs.md", None))
+        );
+        assert_eq!(parse_link_item("[Docs](/docs.md) trailing"), None);
+        assert_eq!(parse_link_item("Docs: /docs.md"), None);
+        assert_eq!(parse_link_item("[](/docs.md)"), None);
+    }
+
+    #[test]
+    fn test_same_site_ignores_www() {
+        let base = Url::parse("https://www.example.com/llms.txt").unwrap();
+        assert!(same_site(&base, "https://example.com/a.md"));
+        assert!(same_site(&base, "https://WWW.example.com/a.md"));
+        assert!(!same_site(&base, "https://docs.example.com/a.md"));
+    }
+}
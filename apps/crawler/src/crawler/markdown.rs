@@ -0,0 +1,484 @@
+use scraper::{node::Element, ElementRef, Html, Node};
+use url::Url;
+
+use super::readability::{is_page_chrome, main_content_root};
+
+/// Elements dropped with their contents: hidden, interactive or embedded
+/// content that reads as noise once flattened to text.
+const SKIPPED_TAGS: [&str; 12] = [
+    "script", "style", "noscript", "template", "form", "button", "select", "textarea", "svg",
+    "canvas", "iframe", "object",
+];
+
+/// Elements that start a new block; anything else is read as inline text.
+const BLOCK_TAGS: [&str; 28] = [
+    "address",
+    "article",
+    "blockquote",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "header",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// Rough bytes-per-token ratio for English text, what the common
+/// tokenizers average.
+const BYTES_PER_TOKEN: u64 = 4;
+
+/// Rough LLM token count for `size_bytes` of UTF-8 text, good enough to size
+/// a page or file against a context window. Counting bytes rather than
+/// characters works for files that are only measured, not kept, and
+/// undercounts multi-byte scripts less.
+pub fn estimate_tokens(size_bytes: u64) -> u64 {
+    size_bytes.div_ceil(BYTES_PER_TOKEN)
+}
+
+/// A top-level block of the main-content Markdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub markdown: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    /// `<h1>`–`<h6>`, with the level.
+    Heading(u8),
+    Paragraph,
+    List,
+    Table,
+    Code,
+    Quote,
+    Rule,
+}
+
+/// The page's main content as Markdown: headings, paragraphs, lists, tables,
+/// quotes, code blocks and links, with site chrome (`<nav>`, page-level
+/// `<header>`/`<footer>`, `<aside>` and their ARIA roles), scripts and forms
+/// left out. Links and images are made absolute against `base`. Empty when
+/// the page has no text.
+pub fn main_content_markdown(document: &Html, base: Option<&Url>) -> String {
+    render(&main_content_blocks(document, base))
+}
+
+/// `main_content_markdown` as its blocks, in document order.
+pub fn main_content_blocks(document: &Html, base: Option<&Url>) -> Vec<Block> {
+    let Some(root) = main_content_root(document) else {
+        return Vec::new();
+    };
+    let mut writer = Writer {
+        base,
+        blocks: Vec::new(),
+    };
+    writer.container(root);
+    writer.blocks
+}
+
+/// Blocks joined into a Markdown document.
+pub fn render(blocks: &[Block]) -> String {
+    if blocks.is_empty() {
+        return String::new();
+    }
+    blocks
+        .iter()
+        .map(|b| b.markdown.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+        + "\n"
+}
+
+/// Whether `el` and its contents are left out of the Markdown.
+fn is_skipped(el: &ElementRef) -> bool {
+    let e = el.value();
+    SKIPPED_TAGS.contains(&e.name())
+        || e.attr("hidden").is_some()
+        || e.attr("aria-hidden") == Some("true")
+        || is_page_chrome(el)
+}
+
+fn is_block(e: &Element) -> bool {
+    BLOCK_TAGS.contains(&e.name())
+}
+
+/// Append a text node with its line breaks flattened; only `<br>` breaks a
+/// line in the output.
+fn push_text(out: &mut String, text: &str) {
+    out.extend(
+        text.chars()
+            .map(|c| if c.is_whitespace() { ' ' } else { c }),
+    );
+}
+
+/// Collapse whitespace runs within each line and drop blank lines.
+fn tidy(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+struct Writer<'a> {
+    base: Option<&'a Url>,
+    blocks: Vec<Block>,
+}
+
+impl Writer<'_> {
+    fn push(&mut self, kind: BlockKind, markdown: String) {
+        self.blocks.push(Block { kind, markdown });
+    }
+
+    fn push_paragraph(&mut self, inline: &mut String) {
+        let text = tidy(inline);
+        if !text.is_empty() {
+            self.push(BlockKind::Paragraph, text);
+        }
+        inline.clear();
+    }
+
+    /// Children of a block container: runs of inline content become
+    /// paragraphs, block children are written in turn.
+    fn container(&mut self, el: ElementRef) {
+        let mut inline = String::new();
+        for child in el.children() {
+            match child.value() {
+                Node::Text(text) => push_text(&mut inline, text),
+                Node::Element(e) => {
+                    let Some(child) = ElementRef::wrap(child) else {
+                        continue;
+                    };
+                    if is_skipped(&child) {
+                        continue;
+                    }
+                    if is_block(e) {
+                        self.push_paragraph(&mut inline);
+                        self.block(child);
+                    } else {
+                        inline.push_str(&self.inline(child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.push_paragraph(&mut inline);
+    }
+
+    fn block(&mut self, el: ElementRef) {
+        match el.value().name() {
+            name @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+                let level = name[1..].parse::<u8>().unwrap_or(1);
+                let text = tidy(&self.inline(el)).replace('\n', " ");
+                if !text.is_empty() {
+                    let markdown = format!("{} {}", "#".repeat(level as usize), text);
+                    self.push(BlockKind::Heading(level), markdown);
+                }
+            }
+            "p" | "dt" | "figcaption" => {
+                let mut text = self.inline(el);
+                self.push_paragraph(&mut text);
+            }
+            "ul" | "ol" => {
+                let list = self.list(el, 0);
+                if !list.is_empty() {
+                    self.push(BlockKind::List, list);
+                }
+            }
+            "table" => {
+                if let Some(table) = self.table(el) {
+                    self.push(BlockKind::Table, table);
+                }
+            }
+            "pre" => {
+                let code = el.text().collect::<String>();
+                let code = code.trim_matches('\n');
+                if !code.trim().is_empty() {
+                    self.push(BlockKind::Code, format!("```\n{}\n```", code));
+                }
+            }
+            "blockquote" => {
+                let mut quote = Writer {
+                    base: self.base,
+                    blocks: Vec::new(),
+                };
+                quote.container(el);
+                if !quote.blocks.is_empty() {
+                    let body = render(&quote.blocks);
+                    self.push(
+                        BlockKind::Quote,
+                        body.lines()
+                            .map(|l| {
+                                if l.is_empty() {
+                                    ">".into()
+                                } else {
+                                    format!("> {}", l)
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    );
+                }
+            }
+            "hr" => self.push(BlockKind::Rule, "---".to_string()),
+            _ => self.container(el),
+        }
+    }
+
+    /// Inline Markdown for `el` and its descendants. Block elements met here
+    /// (a `<div>` inside a link, say) contribute their text only.
+    fn inline(&self, el: ElementRef) -> String {
+        let e = el.value();
+        let children = || {
+            let mut out = String::new();
+            for child in el.children() {
+                match child.value() {
+                    Node::Text(text) => push_text(&mut out, text),
+                    Node::Element(_) => {
+                        if let Some(child) = ElementRef::wrap(child).filter(|c| !is_skipped(c)) {
+                            out.push_str(&self.inline(child));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            out
+        };
+        // Emphasis markers must hug the text, so spacing moves outside.
+        let wrap = |marker: &str, text: String| {
+            let trimmed = text.trim();
+            if trimmed.is_empty() {
+                return text;
+            }
+            let lead = if text.starts_with(char::is_whitespace) {
+                " "
+            } else {
+                ""
+            };
+            let trail = if text.ends_with(char::is_whitespace) {
+                " "
+            } else {
+                ""
+            };
+            format!("{lead}{marker}{trimmed}{marker}{trail}")
+        };
+        match e.name() {
+            "br" => "\n".to_string(),
+            "strong" | "b" => wrap("**", children()),
+            "em" | "i" => wrap("_", children()),
+            "code" => wrap("`", children()),
+            "a" => {
+                let text = children();
+                match e.attr("href").and_then(|h| self.link_target(h)) {
+                    Some(href) if !text.trim().is_empty() => {
+                        let label = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                        format!("[{}]({})", label, href)
+                    }
+                    _ => text,
+                }
+            }
+            "img" => match e.attr("src").and_then(|s| self.link_target(s)) {
+                Some(src) => {
+                    let alt = e
+                        .attr("alt")
+                        .unwrap_or_default()
+                        .trim()
+                        .replace(['[', ']'], "");
+                    format!("![{}]({})", alt, src)
+                }
+                None => String::new(),
+            },
+            name if is_block(e) && name != "li" => format!(" {} ", children()),
+            _ => children(),
+        }
+    }
+
+    /// An absolute URL for a link or image, or `None` for in-page anchors and
+    /// `javascript:` links.
+    fn link_target(&self, raw: &str) -> Option<String> {
+        let raw = raw.trim();
+        if raw.is_empty() || raw.starts_with('#') || raw.starts_with("javascript:") {
+            return None;
+        }
+        Some(match self.base.and_then(|b| b.join(raw).ok()) {
+            Some(url) => url.to_string(),
+            None => raw.to_string(),
+        })
+    }
+
+    fn list(&self, el: ElementRef, depth: usize) -> String {
+        let ordered = el.value().name() == "ol";
+        let start = el
+            .value()
+            .attr("start")
+            .and_then(|s| s.trim().parse::<u32>().ok())
+            .unwrap_or(1);
+        let indent = "  ".repeat(depth);
+        let mut lines = Vec::new();
+        let items = el
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|c| c.value().name() == "li" && !is_skipped(c));
+        for (i, item) in items.enumerate() {
+            let mut text = String::new();
+            let mut nested = Vec::new();
+            for child in item.children() {
+                match child.value() {
+                    Node::Text(t) => push_text(&mut text, t),
+                    Node::Element(e) => {
+                        let Some(child) = ElementRef::wrap(child).filter(|c| !is_skipped(c)) else {
+                            continue;
+                        };
+                        if matches!(e.name(), "ul" | "ol") {
+                            nested.push(self.list(child, depth + 1));
+                        } else {
+                            text.push_str(&self.inline(child));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if text.is_empty() && nested.iter().all(String::is_empty) {
+                continue;
+            }
+            let marker = if ordered {
+                format!("{}.", start as usize + i)
+            } else {
+                "-".to_string()
+            };
+            lines.push(
+                format!("{}{} {}", indent, marker, text)
+                    .trim_end()
+                    .to_string(),
+            );
+            lines.extend(nested.into_iter().filter(|n| !n.is_empty()));
+        }
+        lines.join("\n")
+    }
+
+    /// A GitHub-flavoured table; the first row is the header. `None` for a
+    /// table without cells.
+    fn table(&self, el: ElementRef) -> Option<String> {
+        let rows: Vec<Vec<String>> = el
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .filter(|r| r.value().name() == "tr")
+            .map(|row| {
+                row.children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|c| matches!(c.value().name(), "td" | "th"))
+                    .map(|cell| {
+                        self.inline(cell)
+                            .split_whitespace()
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                            .replace('|', "\\|")
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|cells| !cells.is_empty())
+            .collect();
+        let width = rows.iter().map(Vec::len).max()?;
+        let line = |cells: &[String]| {
+            let mut cells = cells.to_vec();
+            cells.resize(width, String::new());
+            format!("| {} |", cells.join(" | "))
+        };
+        let mut out = vec![line(&rows[0]), line(&vec!["---".to_string(); width])];
+        out.extend(rows[1..].iter().map(|r| line(r)));
+        Some(out.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markdown(html: &str) -> String {
+        let base = Url::parse("https://example.com/docs/page").unwrap();
+        main_content_markdown(&Html::parse_document(html), Some(&base))
+    }
+
+    #[test]
+    fn test_main_content_to_markdown() {
+        let md = markdown(
+            r#"<html><body>
+            <header><a href="/">Home</a></header>
+            <nav><ul><li>Menu</li></ul></nav>
+            <main>
+                <h1>Install   guide</h1>
+                <p>Run the <code>setup</code> script, then read
+                   <a href="../faq">the <b>FAQ</b></a>.</p>
+                <ol start="3"><li>First</li><li>Second<ul><li>Nested</li></ul></li></ol>
+                <table>
+                    <tr><th>Plan</th><th>Price</th></tr>
+                    <tr><td>Pro</td><td>$10 | mo</td></tr>
+                </table>
+                <blockquote><p>Quoted</p></blockquote>
+                <pre>fn main() {}
+</pre>
+                <script>track()</script>
+                <form><button>Subscribe</button></form>
+            </main>
+            <footer>Copyright</footer>
+            </body></html>"#,
+        );
+        assert_eq!(
+            md,
+            "# Install guide\n\n\
+             Run the `setup` script, then read [the **FAQ**](https://example.com/faq).\n\n\
+             3. First\n4. Second\n  - Nested\n\n\
+             | Plan | Price |\n| --- | --- |\n| Pro | $10 \\| mo |\n\n\
+             > Quoted\n\n\
+             ```\nfn main() {}\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_article_header_is_kept_and_chrome_roles_dropped() {
+        let md = markdown(
+            r#"<body>
+                <div role="navigation">Skip me</div>
+                <article>
+                    <header><h2>Post title</h2></header>
+                    Loose text<br>next line
+                    <img src="/a.png" alt="Chart">
+                    <aside>Related</aside>
+                </article>
+            </body>"#,
+        );
+        assert_eq!(
+            md,
+            "## Post title\n\nLoose text\nnext line ![Chart](https://example.com/a.png)\n"
+        );
+        assert_eq!(markdown("<body><nav>Only chrome</nav></body>"), "");
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(0), 0);
+        assert_eq!(estimate_tokens(1), 1);
+        assert_eq!(estimate_tokens(4000), 1000);
+        assert_eq!(estimate_tokens("naïve".len() as u64), 2);
+    }
+}
@@ -0,0 +1,284 @@
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use url::Url;
+
+/// Top-level items read per page.
+pub(super) const MAX_ITEMS: usize = 100;
+
+/// Nesting followed below a top-level item.
+pub(super) const MAX_DEPTH: usize = 8;
+
+/// A type or property name without its schema.org namespace:
+/// `https://schema.org/Product` and `schema:Product` both give `Product`.
+/// Terms from other vocabularies are kept whole.
+pub(super) fn short_name(term: &str) -> String {
+    ["https://schema.org/", "http://schema.org/", "schema:"]
+        .iter()
+        .find_map(|prefix| term.strip_prefix(prefix))
+        .unwrap_or(term)
+        .to_string()
+}
+
+/// An element's text with whitespace runs collapsed.
+pub(super) fn text_value(el: &ElementRef) -> String {
+    el.text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `raw` resolved against the page URL.
+pub(super) fn resolve(raw: &str, base: Option<&Url>) -> String {
+    let raw = raw.trim();
+    base.and_then(|b| b.join(raw).ok())
+        .map(|u| u.to_string())
+        .unwrap_or_else(|| raw.to_string())
+}
+
+/// Set a property, turning it into an array when it repeats.
+pub(super) fn add_property(node: &mut Map<String, Value>, name: String, value: Value) {
+    match node.get_mut(&name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            node.insert(name, value);
+        }
+    }
+}
+
+/// `@type` from a space-separated type list, as a string or an array.
+pub(super) fn type_value(types: &str) -> Option<Value> {
+    let mut types: Vec<Value> = types
+        .split_whitespace()
+        .map(|t| Value::String(short_name(t)))
+        .collect();
+    match types.len() {
+        0 => None,
+        1 => types.pop(),
+        _ => Some(Value::Array(types)),
+    }
+}
+
+/// Elements `itemref` can point at, looked up by id.
+struct Refs<'a> {
+    /// The first element carrying each id, as `getElementById` finds it.
+    ids: HashMap<&'a str, ElementRef<'a>>,
+    /// Ids already expanded under the current top-level item, so a shared
+    /// or cyclic reference is read at most once.
+    expanded: HashSet<&'a str>,
+}
+
+/// Every top-level Microdata item on the page (an `itemscope` that isn't
+/// itself the value of an `itemprop`), in document order, as JSON-LD-shaped
+/// nodes that can sit alongside the page's JSON-LD.
+pub fn extract(document: &Html, base: Option<&Url>) -> Vec<Value> {
+    let sel = Selector::parse("[itemscope]").unwrap();
+    let mut refs = Refs {
+        ids: HashMap::new(),
+        expanded: HashSet::new(),
+    };
+    for el in document
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+    {
+        if let Some(id) = el.value().id() {
+            refs.ids.entry(id).or_insert(el);
+        }
+    }
+    document
+        .select(&sel)
+        .filter(|el| el.value().attr("itemprop").is_none())
+        .take(MAX_ITEMS)
+        .map(|el| {
+            refs.expanded.clear();
+            let mut node = Map::new();
+            node.insert("@context".into(), "https://schema.org".into());
+            node.extend(item(&mut refs, el, base, 0));
+            Value::Object(node)
+        })
+        .collect()
+}
+
+fn item<'a>(
+    refs: &mut Refs<'a>,
+    el: ElementRef<'a>,
+    base: Option<&Url>,
+    depth: usize,
+) -> Map<String, Value> {
+    let mut node = Map::new();
+    if let Some(types) = el.value().attr("itemtype").and_then(type_value) {
+        node.insert("@type".into(), types);
+    }
+    if let Some(id) = el.value().attr("itemid") {
+        node.insert("@id".into(), resolve(id, base).into());
+    }
+
+    // `itemref` pulls in properties from elements elsewhere in the page.
+    // Targets are claimed before the children are read, so an item that
+    // references an element outranks the items nested inside it.
+    let mut targets = Vec::new();
+    for id in el
+        .value()
+        .attr("itemref")
+        .unwrap_or_default()
+        .split_whitespace()
+    {
+        if let Some((&id, &target)) = refs.ids.get_key_value(id) {
+            if refs.expanded.insert(id) {
+                targets.push(target);
+            }
+        }
+    }
+    let mut properties = Vec::new();
+    for child in el.children().filter_map(ElementRef::wrap) {
+        visit(refs, child, base, depth, &mut properties);
+    }
+    for target in targets {
+        visit(refs, target, base, depth, &mut properties);
+    }
+    for (name, value) in properties {
+        add_property(&mut node, name, value);
+    }
+    node
+}
+
+/// Collect the properties `el` and its descendants give the current item,
+/// without descending into nested items.
+fn visit<'a>(
+    refs: &mut Refs<'a>,
+    el: ElementRef<'a>,
+    base: Option<&Url>,
+    depth: usize,
+    out: &mut Vec<(String, Value)>,
+) {
+    let is_scope = el.value().attr("itemscope").is_some();
+    if let Some(names) = el.value().attr("itemprop") {
+        let value = if is_scope {
+            (depth < MAX_DEPTH).then(|| Value::Object(item(refs, el, base, depth + 1)))
+        } else {
+            Some(Value::String(property_value(&el, base)))
+        };
+        if let Some(value) = value {
+            for name in names.split_whitespace() {
+                out.push((short_name(name), value.clone()));
+            }
+        }
+    }
+    if !is_scope {
+        for child in el.children().filter_map(ElementRef::wrap) {
+            visit(refs, child, base, depth, out);
+        }
+    }
+}
+
+/// A non-item property's value, read from the attribute the element type
+/// carries it in. `content` is honoured on any element, as search engines do.
+fn property_value(el: &ElementRef, base: Option<&Url>) -> String {
+    let e = el.value();
+    if let Some(content) = e.attr("content") {
+        return content.trim().to_string();
+    }
+    let url_attr = match e.name() {
+        "audio" | "embed" | "iframe" | "img" | "source" | "track" | "video" => Some("src"),
+        "a" | "area" | "link" => Some("href"),
+        "object" => Some("data"),
+        _ => None,
+    };
+    if let Some(attr) = url_attr {
+        return e.attr(attr).map(|u| resolve(u, base)).unwrap_or_default();
+    }
+    match e.name() {
+        "data" | "meter" => e.attr("value").unwrap_or_default().trim().to_string(),
+        "time" => e
+            .attr("datetime")
+            .map(|d| d.trim().to_string())
+            .unwrap_or_else(|| text_value(el)),
+        _ => text_value(el),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn extract_from(html: &str) -> Vec<Value> {
+        let base = Url::parse("https://example.com/p/widget").unwrap();
+        extract(&Html::parse_document(html), Some(&base))
+    }
+
+    #[test]
+    fn test_nested_items_and_value_attributes() {
+        let nodes = extract_from(
+            r##"<div itemscope itemtype="https://schema.org/Product" itemid="#w">
+                <h1 itemprop="name"> Blue
+                    Widget </h1>
+                <img itemprop="image" src="/img/w.png">
+                <div itemprop="offers" itemscope itemtype="http://schema.org/Offer">
+                    <span itemprop="priceCurrency" content="USD">$</span>
+                    <span itemprop="price">19.99</span>
+                    <link itemprop="availability" href="https://schema.org/InStock">
+                </div>
+                <span itemprop="color">blue</span><span itemprop="color">navy</span>
+            </div>"##,
+        );
+        assert_eq!(
+            nodes,
+            vec![json!({
+                "@context": "https://schema.org",
+                "@type": "Product",
+                "@id": "https://example.com/p/widget#w",
+                "name": "Blue Widget",
+                "image": "https://example.com/img/w.png",
+                "offers": {
+                    "@type": "Offer",
+                    "priceCurrency": "USD",
+                    "price": "19.99",
+                    "availability": "https://schema.org/InStock"
+                },
+                "color": ["blue", "navy"]
+            })]
+        );
+    }
+
+    #[test]
+    fn test_itemref_and_separate_items() {
+        let nodes = extract_from(
+            r#"<div itemscope itemtype="https://schema.org/Event" itemref="when">
+                <span itemprop="name">Launch</span>
+            </div>
+            <p id="when"><time itemprop="startDate" datetime="2025-05-01">May 1</time></p>
+            <div itemscope itemtype="https://schema.org/Organization">
+                <span itemprop="name">Acme</span>
+            </div>"#,
+        );
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0]["startDate"], "2025-05-01");
+        assert_eq!(nodes[1]["@type"], "Organization");
+        assert!(extract_from("<p>No items</p>").is_empty());
+    }
+
+    #[test]
+    fn test_itemref_targets_are_expanded_once() {
+        let nodes = extract_from(
+            r#"<div itemscope itemtype="https://schema.org/Event" itemref="when when">
+                <span itemprop="name">Launch</span>
+                <div itemprop="location" itemscope itemtype="https://schema.org/Place"
+                    itemref="when"></div>
+            </div>
+            <p id="when"><time itemprop="startDate" datetime="2025-05-01">May 1</time></p>
+            <div itemscope itemtype="https://schema.org/Event" itemref="when missing"></div>"#,
+        );
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0]["startDate"], "2025-05-01");
+        assert_eq!(nodes[0]["location"], json!({ "@type": "Place" }));
+        // Each top-level item can still read a shared element.
+        assert_eq!(nodes[1]["startDate"], "2025-05-01");
+    }
+}
@@ -1,19 +1,32 @@
+pub mod ai_crawlers;
 pub mod checkpoint;
 pub mod circuit_breaker;
+pub mod egress;
 pub mod extractor;
 pub mod fetcher;
 pub mod frontier;
+pub mod llms_txt;
+pub mod markdown;
+pub mod microdata;
+pub mod near_duplicates;
 pub mod parser;
+pub mod passages;
+pub mod rdfa;
 pub mod readability;
 pub mod robots;
+pub mod schema_validator;
+pub mod scope;
 pub mod security;
+pub mod simhash;
 pub mod sitemap;
+pub mod traps;
+pub mod url_rules;
 
 pub use fetcher::RateLimitedFetcher;
 pub use parser::Parser;
-pub use robots::RobotsChecker;
+pub use robots::{HostRobots, RobotsChecker};
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use url::Url;
 
@@ -21,13 +34,15 @@ use crate::lighthouse::LighthouseRunner;
 use crate::models::*;
 use crate::renderer::JsRenderer;
 use crate::storage::StorageClient;
+use extractor::Extractors;
+use scope::ScopeMatcher;
 
 /// Flatten a parsed JSON-LD value into individual typed nodes, descending into
 /// `@graph` containers and nested arrays. A common `{ "@context", "@graph": [...] }`
 /// wrapper has no top-level `@type`, so without this its entities would be
 /// invisible to downstream scoring. Mirrors `normalizeSchemaNodes` in the
 /// TypeScript scoring engine.
-fn flatten_schema_nodes(value: serde_json::Value, out: &mut Vec<serde_json::Value>) {
+pub(crate) fn flatten_schema_nodes(value: serde_json::Value, out: &mut Vec<serde_json::Value>) {
     match value {
         serde_json::Value::Array(items) => {
             for item in items {
@@ -62,9 +77,14 @@ pub struct CrawlEngine {
     pub lighthouse: Option<LighthouseRunner>,
     pub renderer: Option<JsRenderer>,
     pub storage: Arc<StorageClient>,
-    pub robots: Option<RobotsChecker>,
+    pub robots: Option<HostRobots>,
     pub config: CrawlConfig,
+    /// The primary host's site context; also used for hosts without their own.
     pub site_context_data: Option<SiteContext>,
+    /// Site contexts of the other hosts discovered before the crawl.
+    pub host_site_contexts: HashMap<String, SiteContext>,
+    pub scope: ScopeMatcher,
+    pub extractors: Extractors,
 }
 
 impl CrawlEngine {
@@ -74,21 +94,74 @@ impl CrawlEngine {
         lighthouse: Option<LighthouseRunner>,
         renderer: Option<JsRenderer>,
         storage: Arc<StorageClient>,
-        robots: Option<RobotsChecker>,
-        config: CrawlConfig,
+        robots: Option<HostRobots>,
+        mut config: CrawlConfig,
         site_context_data: Option<SiteContext>,
     ) -> Self {
+        // Key validators the way the frontier spells URLs, so lookups by the
+        // URL being crawled hit regardless of trailing slashes or fragments.
+        config.previous_validators = std::mem::take(&mut config.previous_validators)
+            .into_iter()
+            .map(|(url, v)| {
+                (
+                    frontier::normalize_url(&url, &config.canonicalization).unwrap_or(url),
+                    v,
+                )
+            })
+            .collect();
+
         CrawlEngine {
             fetcher,
             lighthouse,
             renderer,
             storage,
             robots,
+            scope: ScopeMatcher::new(&config.scope, &config.seed_urls),
+            // Rules were validated when the job was submitted.
+            extractors: Extractors::from_config(&config.custom_extractors).unwrap_or_else(|e| {
+                tracing::warn!(error = %e, "Ignoring invalid custom extractors");
+                Extractors::default()
+            }),
             config,
             site_context_data,
+            host_site_contexts: HashMap::new(),
         }
     }
 
+    /// Give pages on these hosts their own site context instead of the
+    /// primary host's.
+    pub fn with_host_site_contexts(mut self, contexts: HashMap<String, SiteContext>) -> Self {
+        self.host_site_contexts = contexts;
+        self
+    }
+
+    fn site_context_for(&self, url: &str) -> Option<SiteContext> {
+        Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().and_then(|h| self.host_site_contexts.get(h)))
+            .or(self.site_context_data.as_ref())
+            .cloned()
+    }
+
+    /// The robots.txt rule blocking `url`, fetching the host's robots.txt on
+    /// first use and honouring its Crawl-delay from then on.
+    async fn robots_blocking_rule(&self, robots: &HostRobots, url: &str) -> Option<String> {
+        let parsed = Url::parse(url).ok()?;
+        let host = parsed.host_str()?.to_ascii_lowercase();
+        let (checker, fetched) = robots.for_url(&parsed).await;
+        if fetched {
+            if let Some(delay) = checker.crawl_delay(&self.config.user_agent) {
+                tracing::info!(
+                    host = %host,
+                    crawl_delay_s = delay.as_secs_f64(),
+                    "Honouring robots.txt Crawl-delay"
+                );
+                self.fetcher.set_crawl_delay(&host, delay).await;
+            }
+        }
+        checker.blocking_rule(url, &self.config.user_agent)
+    }
+
     /// Crawl a single URL and return the parsed page result.
     pub async fn crawl_page(
         &self,
@@ -96,23 +169,39 @@ impl CrawlEngine {
         job_id: &str,
     ) -> Result<CrawlPageResult, CrawlEngineError> {
         // Check robots.txt
-        if let Some(ref checker) = self.robots {
-            if !checker.is_allowed(url, &self.config.user_agent) {
-                return Err(CrawlEngineError::BlockedByRobots(url.to_string()));
+        if let Some(ref robots) = self.robots {
+            if let Some(rule) = self.robots_blocking_rule(robots, url).await {
+                return Err(CrawlEngineError::BlockedByRobots {
+                    url: url.to_string(),
+                    rule,
+                });
             }
         }
 
         let page_start = std::time::Instant::now();
 
-        // Fetch
-        let fetch_result = self
-            .fetcher
-            .fetch(url)
-            .await
-            .map_err(|e| CrawlEngineError::FetchError(e.to_string()))?;
+        // Fetch — conditionally when the previous crawl left validators
+        let previous = self.config.previous_validators.get(url);
+        let fetch_result = self.fetcher.fetch_conditional(url, previous).await?;
+
+        if let (304, Some(previous)) = (fetch_result.status_code, previous) {
+            return Ok(unchanged_page_result(
+                url,
+                previous,
+                fetch_result,
+                page_start.elapsed().as_millis() as u64,
+            ));
+        }
 
         // Parse
-        let parsed = Parser::parse(&fetch_result.body, &fetch_result.final_url);
+        let parsed = Parser::parse_with(
+            &fetch_result.body,
+            &fetch_result.final_url,
+            parser::ParseOptions {
+                scope: Some(&self.scope),
+                extractors: Some(&self.extractors),
+            },
+        );
 
         // Content hash
         let content_hash = {
@@ -128,6 +217,15 @@ impl CrawlEngine {
         let is_html = is_html_content_type(&fetch_result.headers);
 
         let html_upload_fut = self.storage.upload_html(&html_r2_key, &fetch_result.body);
+        // The Markdown is uploaded alongside, under the same content hash.
+        let markdown_r2_key = (is_html && !parsed.markdown.is_empty())
+            .then(|| format!("crawls/{}/markdown/{}.md.gz", job_id, &content_hash[..16]));
+        let markdown_upload_fut = async {
+            match markdown_r2_key {
+                Some(ref key) => self.storage.upload_markdown(key, &parsed.markdown).await,
+                None => Ok(()),
+            }
+        };
         let lighthouse_fut = async {
             if let Some(ref runner) = self.lighthouse {
                 match runner.run_lighthouse(url).await {
@@ -161,11 +259,21 @@ impl CrawlEngine {
             }
         };
 
-        let (html_result, mut lighthouse_result, rendered_links) =
-            tokio::join!(html_upload_fut, lighthouse_fut, renderer_fut);
+        let (html_result, markdown_result, mut lighthouse_result, rendered_links) = tokio::join!(
+            html_upload_fut,
+            markdown_upload_fut,
+            lighthouse_fut,
+            renderer_fut
+        );
         if let Err(e) = html_result {
             tracing::warn!(url = %url, error = %e, "Failed to upload HTML");
         }
+        if let Err(e) = markdown_result {
+            tracing::warn!(url = %url, error = %e, "Failed to upload Markdown");
+        }
+        let markdown_tokens = markdown_r2_key
+            .as_ref()
+            .map(|_| markdown::estimate_tokens(parsed.markdown.len() as u64) as u32);
 
         // Upload Lighthouse JSON (depends on lighthouse result, so sequential)
         if let Some(ref mut result) = lighthouse_result {
@@ -184,13 +292,28 @@ impl CrawlEngine {
         // Build extracted data. Flatten @graph wrappers and bare arrays into
         // their individual typed nodes so common `{ @context, @graph: [...] }`
         // markup is read as real entities, not a typeless blob.
+        // Microdata and RDFa items follow the JSON-LD nodes.
+        let mut schema_parse_errors = Vec::new();
+        let mut structured_data_sources = Vec::new();
         let structured_data: Option<Vec<serde_json::Value>> = if self.config.extract_schema {
             let mut nodes: Vec<serde_json::Value> = Vec::new();
-            for s in &parsed.schema_json_ld {
-                if let Ok(value) = serde_json::from_str::<serde_json::Value>(s) {
-                    flatten_schema_nodes(value, &mut nodes);
+            for (i, s) in parsed.schema_json_ld.iter().enumerate() {
+                match serde_json::from_str::<serde_json::Value>(s) {
+                    Ok(value) => flatten_schema_nodes(value, &mut nodes),
+                    Err(e) => schema_parse_errors.push(SchemaParseError {
+                        block_index: i as u32,
+                        message: e.to_string(),
+                    }),
                 }
             }
+            structured_data_sources.resize(nodes.len(), StructuredDataSource::JsonLd);
+            for (source, items) in [
+                (StructuredDataSource::Microdata, parsed.microdata),
+                (StructuredDataSource::Rdfa, parsed.rdfa),
+            ] {
+                structured_data_sources.extend(std::iter::repeat_n(source, items.len()));
+                nodes.extend(items);
+            }
             if nodes.is_empty() {
                 None
             } else {
@@ -213,6 +336,16 @@ impl CrawlEngine {
                 }
             }
         }
+        let schema_validation: Vec<SchemaNodeReport> = structured_data
+            .as_deref()
+            .unwrap_or(&[])
+            .iter()
+            .zip(&structured_data_sources)
+            .map(|(node, source)| SchemaNodeReport {
+                source: *source,
+                ..schema_validator::validate_node(node)
+            })
+            .collect();
         let has_faq_schema = schema_types.iter().any(|t| t == "FAQPage");
         let has_howto_schema = schema_types.iter().any(|t| t == "HowTo");
         let has_breadcrumb_schema = schema_types.iter().any(|t| t == "BreadcrumbList");
@@ -231,12 +364,20 @@ impl CrawlEngine {
             &parsed.external_link_details,
             rendered_links.as_deref(),
             &fetch_result.final_url,
+            Some(&self.scope),
+        );
+        let merged_internal_details = merge_internal_link_details(
+            &parsed.internal_link_details,
+            &merged_internal,
+            rendered_links.as_deref(),
         );
 
         let timing_ms = page_start.elapsed().as_millis() as u64;
 
-        // Detect cross-domain redirect: original URL vs final URL
-        let cross_domain = is_cross_domain_redirect(url, &fetch_result.final_url);
+        // Detect cross-domain redirect: original URL vs final URL. Redirects
+        // between hosts in the crawl scope (e.g. to `blog.`) stay on the site.
+        let cross_domain = is_cross_domain_redirect(url, &fetch_result.final_url)
+            && !self.scope.contains(&fetch_result.final_url);
         if cross_domain {
             tracing::info!(
                 original = %url,
@@ -246,11 +387,17 @@ impl CrawlEngine {
         }
 
         // If cross-domain redirect, clear links to prevent frontier pollution
-        let (result_internal, result_external, result_external_details) = if cross_domain {
-            (vec![], vec![], vec![])
-        } else {
-            (merged_internal, merged_external, merged_external_details)
-        };
+        let (result_internal, result_internal_details, result_external, result_external_details) =
+            if cross_domain {
+                (vec![], vec![], vec![], vec![])
+            } else {
+                (
+                    merged_internal,
+                    merged_internal_details,
+                    merged_external,
+                    merged_external_details,
+                )
+            };
 
         Ok(CrawlPageResult {
             url: if cross_domain {
@@ -263,8 +410,13 @@ impl CrawlEngine {
             meta_description: parsed.meta_description,
             canonical_url: parsed.canonical_url,
             word_count: parsed.word_count,
+            content_fingerprint: parsed.content_fingerprint.map(|f| format!("{:016x}", f)),
+            near_duplicate_of: None,
+            near_duplicate_cluster: None,
             content_hash,
             html_r2_key,
+            markdown_r2_key,
+            markdown_tokens,
             extracted: ExtractedData {
                 h1: parsed.headings.h1,
                 h2: parsed.headings.h2,
@@ -274,6 +426,7 @@ impl CrawlEngine {
                 h6: parsed.headings.h6,
                 schema_types,
                 internal_links: result_internal,
+                internal_link_details: result_internal_details,
                 external_links: result_external,
                 external_link_details: result_external_details,
                 images_without_alt: parsed.images_without_alt,
@@ -281,6 +434,7 @@ impl CrawlEngine {
                 robots_directives: parsed.robots_directives,
                 og_tags,
                 structured_data,
+                structured_data_sources,
                 flesch_score: parsed.flesch_score,
                 flesch_classification: parsed.flesch_classification,
                 avg_sentence_length: parsed.avg_sentence_length,
@@ -300,6 +454,10 @@ impl CrawlEngine {
                 has_faq_schema,
                 has_howto_schema,
                 has_breadcrumb_schema,
+                custom_extractions: parsed.custom_extractions,
+                schema_validation,
+                schema_parse_errors,
+                passages: parsed.passages,
             },
             lighthouse: lighthouse_result,
             js_rendered_link_count,
@@ -307,13 +465,14 @@ impl CrawlEngine {
             etag: fetch_result.headers.get("etag").cloned(),
             last_modified: fetch_result.headers.get("last-modified").cloned(),
             redirect_chain: fetch_result.redirect_chain,
-            site_context: self.site_context_data.clone(),
+            site_context: self.site_context_for(&fetch_result.final_url),
             is_cross_domain_redirect: cross_domain,
             redirect_url: if cross_domain {
                 Some(fetch_result.final_url)
             } else {
                 None
             },
+            unchanged: false,
         })
     }
 
@@ -325,12 +484,64 @@ impl CrawlEngine {
     }
 }
 
+/// Lightweight result for a page the server reported as `304 Not Modified`:
+/// no parse, upload or Lighthouse run; the previous crawl's stored HTML and
+/// link list stand in for the page.
+fn unchanged_page_result(
+    url: &str,
+    previous: &PreviousValidators,
+    fetch_result: fetcher::FetchResult,
+    timing_ms: u64,
+) -> CrawlPageResult {
+    // Servers may send fresh validators with the 304; otherwise keep the old ones.
+    let etag = fetch_result
+        .headers
+        .get("etag")
+        .cloned()
+        .or_else(|| previous.etag.clone());
+    let last_modified = fetch_result
+        .headers
+        .get("last-modified")
+        .cloned()
+        .or_else(|| previous.last_modified.clone());
+
+    CrawlPageResult {
+        url: url.to_string(),
+        status_code: fetch_result.status_code,
+        title: None,
+        meta_description: None,
+        canonical_url: None,
+        word_count: 0,
+        content_hash: previous.content_hash.clone().unwrap_or_default(),
+        content_fingerprint: previous.content_fingerprint.clone(),
+        near_duplicate_of: None,
+        near_duplicate_cluster: None,
+        html_r2_key: previous.html_r2_key.clone(),
+        markdown_r2_key: previous.markdown_r2_key.clone(),
+        markdown_tokens: previous.markdown_tokens,
+        extracted: ExtractedData {
+            internal_links: previous.internal_links.clone(),
+            ..Default::default()
+        },
+        lighthouse: None,
+        js_rendered_link_count: None,
+        site_context: None,
+        timing_ms,
+        etag,
+        last_modified,
+        redirect_chain: fetch_result.redirect_chain,
+        is_cross_domain_redirect: false,
+        redirect_url: None,
+        unchanged: true,
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum CrawlEngineError {
-    #[error("URL blocked by robots.txt: {0}")]
-    BlockedByRobots(String),
+    #[error("URL blocked by robots.txt: {url} ({rule})")]
+    BlockedByRobots { url: String, rule: String },
     #[error("Fetch error: {0}")]
-    FetchError(String),
+    FetchError(#[from] fetcher::FetchError),
     #[error("Parse error: {0}")]
     ParseError(String),
 }
@@ -382,6 +593,7 @@ pub fn merge_links(
     static_external_details: &[ExtractedLink],
     rendered: Option<&[crate::renderer::RenderedLink]>,
     page_url: &str,
+    scope: Option<&ScopeMatcher>,
 ) -> (Vec<String>, Vec<String>, Vec<ExtractedLink>) {
     let rendered = match rendered {
         Some(links) if !links.is_empty() => links,
@@ -425,8 +637,9 @@ pub fn merge_links(
         }
 
         let link_host = parsed_url.host_str().map(|h| h.to_string());
-        let is_internal = match (&page_host, &link_host) {
-            (Some(ph), Some(lh)) => ph == lh,
+        let is_internal = match (scope, &page_host, &link_host) {
+            (Some(scope), _, Some(lh)) => scope.contains_host(lh),
+            (None, Some(ph), Some(lh)) => ph == lh,
             _ => false,
         };
 
@@ -446,6 +659,7 @@ pub fn merge_links(
                     anchor_text: link.anchor_text.clone(),
                     rel: link.rel.clone(),
                     is_external: true,
+                    placement: None,
                 });
             }
         }
@@ -454,11 +668,73 @@ pub fn merge_links(
     (merged_internal, merged_external, merged_external_details)
 }
 
+/// Internal link details for the merged internal URLs: the static ones, plus
+/// one entry per URL only the JS renderer found (placement unknown).
+fn merge_internal_link_details(
+    static_details: &[ExtractedLink],
+    merged_internal: &[String],
+    rendered: Option<&[crate::renderer::RenderedLink]>,
+) -> Vec<ExtractedLink> {
+    let static_urls: HashSet<&str> = static_details.iter().map(|l| l.url.as_str()).collect();
+    let mut details = static_details.to_vec();
+    for url in merged_internal {
+        if static_urls.contains(url.as_str()) {
+            continue;
+        }
+        let link = rendered.unwrap_or_default().iter().find(|l| l.url == *url);
+        details.push(ExtractedLink {
+            url: url.clone(),
+            anchor_text: link.map(|l| l.anchor_text.clone()).unwrap_or_default(),
+            rel: link.map(|l| l.rel.clone()).unwrap_or_default(),
+            is_external: false,
+            placement: None,
+        });
+    }
+    details
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::renderer::RenderedLink;
 
+    #[test]
+    fn unchanged_result_reuses_previous_crawl_data() {
+        let previous = PreviousValidators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: Some("Mon, 05 Oct 2026 10:00:00 GMT".to_string()),
+            html_r2_key: "crawls/old-job/html/abcdef0123456789.html.gz".to_string(),
+            markdown_r2_key: Some("crawls/old-job/markdown/abcdef0123456789.md.gz".to_string()),
+            markdown_tokens: Some(420),
+            content_hash: Some("abcdef0123456789".to_string()),
+            content_fingerprint: Some("00ff00ff00ff00ff".to_string()),
+            internal_links: vec!["https://example.com/about".to_string()],
+        };
+        let fetch_result = fetcher::FetchResult {
+            status_code: 304,
+            body: String::new(),
+            headers: [("etag".to_string(), "\"v2\"".to_string())].into(),
+            final_url: "https://example.com/".to_string(),
+            redirect_chain: vec![],
+        };
+
+        let page = unchanged_page_result("https://example.com/", &previous, fetch_result, 12);
+        assert!(page.unchanged);
+        assert_eq!(page.status_code, 304);
+        assert_eq!(page.html_r2_key, previous.html_r2_key);
+        assert_eq!(page.markdown_r2_key, previous.markdown_r2_key);
+        assert_eq!(page.markdown_tokens, Some(420));
+        assert_eq!(page.content_hash, "abcdef0123456789");
+        assert_eq!(
+            page.content_fingerprint.as_deref(),
+            Some("00ff00ff00ff00ff")
+        );
+        assert_eq!(page.extracted.internal_links, previous.internal_links);
+        // Fresh validators from the 304 win; missing ones fall back to the old.
+        assert_eq!(page.etag.as_deref(), Some("\"v2\""));
+        assert_eq!(page.last_modified, previous.last_modified);
+    }
+
     fn node_types(nodes: &[serde_json::Value]) -> Vec<String> {
         nodes
             .iter()
@@ -594,6 +870,7 @@ mod tests {
             anchor_text: "B".to_string(),
             rel: "nofollow".to_string(),
             is_external: true,
+            placement: None,
         }];
 
         let (mi, me, md) = merge_links(
@@ -602,6 +879,7 @@ mod tests {
             &details,
             None,
             "https://example.com/page",
+            None,
         );
         assert_eq!(mi, internal);
         assert_eq!(me, external);
@@ -627,6 +905,7 @@ mod tests {
             &details,
             Some(&rendered),
             "https://example.com/page",
+            None,
         );
         assert_eq!(mi.len(), 1);
         assert_eq!(me.len(), 0);
@@ -650,6 +929,7 @@ mod tests {
             &details,
             Some(&rendered),
             "https://example.com/page",
+            None,
         );
         assert_eq!(mi.len(), 0);
         assert_eq!(me, vec!["https://other.com/new".to_string()]);
@@ -683,6 +963,7 @@ mod tests {
             &details,
             Some(&rendered),
             "https://example.com/page",
+            None,
         );
         assert_eq!(mi.len(), 2);
         assert!(mi.contains(&"https://example.com/a".to_string()));
@@ -724,6 +1005,7 @@ mod tests {
             &details,
             Some(&rendered),
             "https://example.com/page",
+            None,
         );
         assert_eq!(mi.len(), 1);
         assert_eq!(mi[0], "https://example.com/valid");
@@ -738,6 +1020,7 @@ mod tests {
             anchor_text: "Static anchor".to_string(),
             rel: "nofollow".to_string(),
             is_external: true,
+            placement: None,
         }];
 
         // Rendered has the same URL but different anchor/rel
@@ -753,6 +1036,7 @@ mod tests {
             &details,
             Some(&rendered),
             "https://example.com/page",
+            None,
         );
         // External URL list shouldn't duplicate
         assert_eq!(me.len(), 1);
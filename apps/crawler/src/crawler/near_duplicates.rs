@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::{CrawlPageResult, NearDuplicateCluster};
+
+/// URLs listed per cluster in the report; `page_count` carries the total.
+const MAX_CLUSTER_URLS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Fingerprinted {
+    url: String,
+    fingerprint: u64,
+    cluster: Option<u32>,
+}
+
+/// Every fingerprinted page of the crawl so far, grouped into clusters of
+/// near-duplicates as pages arrive. Pages join the cluster of the closest
+/// earlier page within the threshold, so a cluster's first page is its
+/// representative. Checkpointed with the frontier.
+///
+/// Candidates are found through band buckets rather than a scan of every
+/// page: with `max_distance + 1` bands, two fingerprints at most
+/// `max_distance` bits apart agree exactly on at least one band. The buckets
+/// are rebuilt from `pages` after a restore instead of being checkpointed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NearDuplicateIndex {
+    pages: Vec<Fingerprinted>,
+    /// Cluster id -> representative URL. Ids are indexes.
+    representatives: Vec<String>,
+    /// (band, band bits) -> indexes into `pages`.
+    #[serde(skip)]
+    buckets: HashMap<(u32, u64), Vec<usize>>,
+    /// Band count `buckets` was built for; `0` before the first build.
+    #[serde(skip)]
+    bands: u32,
+}
+
+/// The bucket keys of `fingerprint` split into `bands` contiguous bit ranges.
+fn band_keys(fingerprint: u64, bands: u32) -> impl Iterator<Item = (u32, u64)> {
+    (0..bands).map(move |band| {
+        let start = band * 64 / bands;
+        let end = (band + 1) * 64 / bands;
+        let mask = if end - start == 64 {
+            u64::MAX
+        } else {
+            (1u64 << (end - start)) - 1
+        };
+        (band, (fingerprint >> start) & mask)
+    })
+}
+
+impl NearDuplicateIndex {
+    /// Fingerprint `page` against the pages seen so far and fill in
+    /// `near_duplicate_of` and `near_duplicate_cluster` when it matches one
+    /// within `max_distance` differing bits.
+    pub fn assign(&mut self, page: &mut CrawlPageResult, max_distance: u32) {
+        let Some(fingerprint) = page
+            .content_fingerprint
+            .as_deref()
+            .and_then(|f| u64::from_str_radix(f, 16).ok())
+        else {
+            return;
+        };
+
+        let bands = (max_distance + 1).min(64);
+        if self.bands != bands {
+            self.rebuild_buckets(bands);
+        }
+        let mut candidates: Vec<usize> = band_keys(fingerprint, bands)
+            .filter_map(|key| self.buckets.get(&key))
+            .flatten()
+            .copied()
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        // Earliest page wins a tie, as it would in a scan in crawl order.
+        let closest = candidates
+            .into_iter()
+            .filter(|&i| self.pages[i].url != page.url)
+            .map(|i| ((self.pages[i].fingerprint ^ fingerprint).count_ones(), i))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min()
+            .map(|(_, i)| i);
+        let cluster = closest.map(|i| match self.pages[i].cluster {
+            Some(id) => id,
+            None => {
+                let id = self.representatives.len() as u32;
+                self.representatives.push(self.pages[i].url.clone());
+                self.pages[i].cluster = Some(id);
+                id
+            }
+        });
+
+        if let Some(id) = cluster {
+            page.near_duplicate_of = Some(self.representatives[id as usize].clone());
+            page.near_duplicate_cluster = Some(id);
+        }
+        self.insert_buckets(fingerprint, self.pages.len());
+        self.pages.push(Fingerprinted {
+            url: page.url.clone(),
+            fingerprint,
+            cluster,
+        });
+    }
+
+    /// A copy for a checkpoint: the pages and clusters, without the buckets,
+    /// which are rebuilt on restore.
+    pub fn snapshot(&self) -> Self {
+        NearDuplicateIndex {
+            pages: self.pages.clone(),
+            representatives: self.representatives.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn rebuild_buckets(&mut self, bands: u32) {
+        self.bands = bands;
+        self.buckets.clear();
+        for i in 0..self.pages.len() {
+            self.insert_buckets(self.pages[i].fingerprint, i);
+        }
+    }
+
+    fn insert_buckets(&mut self, fingerprint: u64, index: usize) {
+        for key in band_keys(fingerprint, self.bands) {
+            self.buckets.entry(key).or_default().push(index);
+        }
+    }
+
+    /// All clusters, in the order they formed.
+    pub fn clusters(&self) -> Vec<NearDuplicateCluster> {
+        let mut clusters: Vec<NearDuplicateCluster> = self
+            .representatives
+            .iter()
+            .enumerate()
+            .map(|(id, url)| NearDuplicateCluster {
+                id: id as u32,
+                representative_url: url.clone(),
+                page_count: 0,
+                urls: Vec::new(),
+            })
+            .collect();
+        for page in &self.pages {
+            if let Some(id) = page.cluster {
+                let cluster = &mut clusters[id as usize];
+                cluster.page_count += 1;
+                if cluster.urls.len() < MAX_CLUSTER_URLS {
+                    cluster.urls.push(page.url.clone());
+                }
+            }
+        }
+        clusters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, fingerprint: u64) -> CrawlPageResult {
+        CrawlPageResult {
+            url: url.to_string(),
+            status_code: 200,
+            content_fingerprint: Some(format!("{:016x}", fingerprint)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_clusters_form_around_the_first_page() {
+        let mut index = NearDuplicateIndex::default();
+        let base = 0xdead_beef_0000_0000u64;
+
+        let mut first = page("https://example.com/a", base);
+        index.assign(&mut first, 3);
+        assert_eq!(first.near_duplicate_of, None);
+
+        let mut other = page("https://example.com/other", !base);
+        index.assign(&mut other, 3);
+        assert_eq!(other.near_duplicate_cluster, None);
+
+        let mut second = page("https://example.com/b", base ^ 0b11);
+        index.assign(&mut second, 3);
+        assert_eq!(
+            second.near_duplicate_of.as_deref(),
+            Some("https://example.com/a")
+        );
+        assert_eq!(second.near_duplicate_cluster, Some(0));
+
+        // Four bits from `a` but one from `b`: joins the same cluster.
+        let mut third = page("https://example.com/c", base ^ 0b1111);
+        index.assign(&mut third, 3);
+        assert_eq!(third.near_duplicate_cluster, Some(0));
+        assert_eq!(
+            third.near_duplicate_of.as_deref(),
+            Some("https://example.com/a")
+        );
+
+        let clusters = index.clusters();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].page_count, 3);
+        assert_eq!(
+            clusters[0].urls,
+            vec![
+                "https://example.com/a",
+                "https://example.com/b",
+                "https://example.com/c"
+            ]
+        );
+    }
+
+    /// The same location page for another city differs in about 6 of 64
+    /// bits (0.906 similarity); the default threshold must cluster it.
+    #[test]
+    fn test_default_threshold_clusters_city_variants() {
+        let config: crate::models::CrawlConfig = serde_json::from_value(serde_json::json!({
+            "seed_urls": [], "max_pages": 1, "max_depth": 1
+        }))
+        .unwrap();
+        let max_distance = crate::crawler::simhash::max_distance(config.near_duplicate_threshold);
+
+        let mut index = NearDuplicateIndex::default();
+        let base = 0x0123_4567_89ab_cdefu64;
+        index.assign(
+            &mut page("https://example.com/us/austin", base),
+            max_distance,
+        );
+        let mut variant = page("https://example.com/us/dallas", base ^ 0b11_1111);
+        index.assign(&mut variant, max_distance);
+        assert_eq!(
+            variant.near_duplicate_of.as_deref(),
+            Some("https://example.com/us/austin")
+        );
+    }
+
+    /// A restored index rebuilds its buckets and still finds matches.
+    #[test]
+    fn test_restored_index_finds_earlier_pages() {
+        let mut index = NearDuplicateIndex::default();
+        let base = 0xfeed_0000_0000_beefu64;
+        index.assign(&mut page("https://example.com/a", base), 6);
+        // Differing bits spread over several bands.
+        let spread = base ^ (1 | 1 << 20 | 1 << 40 | 1 << 63);
+
+        let mut restored: NearDuplicateIndex =
+            serde_json::from_str(&serde_json::to_string(&index).unwrap()).unwrap();
+        let mut near = page("https://example.com/b", spread);
+        restored.assign(&mut near, 6);
+        assert_eq!(near.near_duplicate_cluster, Some(0));
+    }
+
+    #[test]
+    fn test_threshold_and_missing_fingerprints() {
+        let mut index = NearDuplicateIndex::default();
+        index.assign(&mut page("https://example.com/a", 0), 0);
+        let mut near = page("https://example.com/b", 1);
+        index.assign(&mut near, 0);
+        assert_eq!(near.near_duplicate_cluster, None);
+
+        let mut thin = page("https://example.com/thin", 0);
+        thin.content_fingerprint = None;
+        index.assign(&mut thin, 64);
+        assert_eq!(thin.near_duplicate_cluster, None);
+        assert!(index.clusters().is_empty());
+    }
+}
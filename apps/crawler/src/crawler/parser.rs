@@ -1,8 +1,12 @@
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Selector};
 use std::collections::HashMap;
 use url::Url;
 
-use crate::models::{ExtractedLink, HreflangAlternate};
+use super::extractor::Extractors;
+use super::scope::ScopeMatcher;
+use crate::models::{
+    ContentPassage, ExtractedLink, ExtractorResult, HreflangAlternate, LinkPlacement,
+};
 
 /// Detect web-analytics / tag-manager tools present in the page HTML.
 /// Returns short tool keys (e.g. "ga4", "gtm") used by the SEO/analytics audit.
@@ -53,11 +57,15 @@ pub struct ParsedPage {
     pub canonical_url: Option<String>,
     pub headings: Headings,
     pub internal_links: Vec<String>,
+    pub internal_link_details: Vec<ExtractedLink>,
     pub external_links: Vec<String>,
     pub external_link_details: Vec<ExtractedLink>,
     pub total_images: u32,
     pub images_without_alt: u32,
     pub schema_json_ld: Vec<String>,
+    /// Microdata and RDFa items as JSON-LD-shaped nodes.
+    pub microdata: Vec<serde_json::Value>,
+    pub rdfa: Vec<serde_json::Value>,
     pub og_tags: HashMap<String, String>,
     pub robots_directives: Vec<String>,
     pub has_robots_meta: bool,
@@ -80,7 +88,12 @@ pub struct ParsedPage {
     pub hreflang_urls: Vec<String>,
     pub hreflang: Vec<HreflangAlternate>,
     pub analytics_tools: Vec<String>,
-    pub custom_extractions: Vec<super::extractor::ExtractorResult>,
+    pub custom_extractions: Vec<ExtractorResult>,
+    /// SimHash of the main-content text; `None` for thin pages.
+    pub content_fingerprint: Option<u64>,
+    /// Main content as Markdown; empty when the page has no text.
+    pub markdown: String,
+    pub passages: Vec<ContentPassage>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -95,9 +108,58 @@ pub struct Headings {
 
 pub struct Parser;
 
+/// Per-crawl settings `Parser::parse_with` applies on top of `parse`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions<'a> {
+    /// Hosts whose links are internal; `None` means `base_url`'s host only.
+    pub scope: Option<&'a ScopeMatcher>,
+    /// Custom extraction rules to run.
+    pub extractors: Option<&'a Extractors>,
+}
+
+/// `<a href>` links split by host, as bare URLs and with details.
+#[derive(Default)]
+struct Links {
+    internal: Vec<String>,
+    internal_details: Vec<ExtractedLink>,
+    external: Vec<String>,
+    external_details: Vec<ExtractedLink>,
+}
+
+/// The page region `el` sits in. The nearest chrome ancestor wins, except
+/// that a `<header>`/`<footer>` inside `<main>` or `<article>` is part of the
+/// content.
+fn link_placement(el: &ElementRef) -> LinkPlacement {
+    let mut ancestors = el.ancestors().filter_map(|n| n.value().as_element());
+    while let Some(e) = ancestors.next() {
+        let placement = match (e.name(), e.attr("role")) {
+            ("nav", _) | (_, Some("navigation")) => LinkPlacement::Nav,
+            ("header", _) | (_, Some("banner")) => LinkPlacement::Header,
+            ("footer", _) | (_, Some("contentinfo")) => LinkPlacement::Footer,
+            ("aside", _) | (_, Some("complementary")) => LinkPlacement::Aside,
+            ("main" | "article", _) | (_, Some("main")) => return LinkPlacement::Content,
+            _ => continue,
+        };
+        let in_content = matches!(e.name(), "header" | "footer")
+            && ancestors.any(|a| matches!(a.name(), "main" | "article"));
+        return if in_content {
+            LinkPlacement::Content
+        } else {
+            placement
+        };
+    }
+    LinkPlacement::Content
+}
+
 impl Parser {
-    /// Parse an HTML document and extract all SEO-relevant data.
+    /// Parse an HTML document and extract all SEO-relevant data. Links to
+    /// any host other than `base_url`'s are external.
     pub fn parse(html_content: &str, base_url: &str) -> ParsedPage {
+        Self::parse_with(html_content, base_url, ParseOptions::default())
+    }
+
+    /// `parse` with a crawl's scope and custom extractors.
+    pub fn parse_with(html_content: &str, base_url: &str, options: ParseOptions) -> ParsedPage {
         let document = Html::parse_document(html_content);
         let base = Url::parse(base_url).ok();
 
@@ -105,10 +167,16 @@ impl Parser {
         let meta_description = Self::extract_meta_description(&document);
         let canonical_url = Self::extract_canonical(&document);
         let headings = Self::extract_headings(&document);
-        let (internal_links, external_links, external_link_details) =
-            Self::extract_links(&document, &base);
+        let Links {
+            internal: internal_links,
+            internal_details: internal_link_details,
+            external: external_links,
+            external_details: external_link_details,
+        } = Self::extract_links(&document, &base, options.scope);
         let (total_images, images_without_alt) = Self::extract_image_stats(&document);
         let schema_json_ld = Self::extract_json_ld(&document);
+        let microdata = super::microdata::extract(&document, base.as_ref());
+        let rdfa = super::rdfa::extract(&document, base.as_ref());
         let og_tags = Self::extract_og_tags(&document);
         let (has_robots_meta, robots_directives) = Self::extract_robots_meta(&document);
         let word_count = Self::compute_word_count(&document);
@@ -138,22 +206,35 @@ impl Parser {
 
         // Analytics / tag-manager detection
         let analytics_tools = detect_analytics_tools(html_content);
+        let custom_extractions = options
+            .extractors
+            .map(|e| e.run(&document, html_content, &schema_json_ld))
+            .unwrap_or_default();
 
         // Human-Readiness metrics
         let text_content = Self::get_all_text(&document);
         let (variance, transitions) = Self::analyze_human_readiness(&text_content);
 
+        let content_fingerprint =
+            super::simhash::fingerprint(&super::readability::main_content_text(&document));
+        let blocks = super::markdown::main_content_blocks(&document, base.as_ref());
+        let markdown = super::markdown::render(&blocks);
+        let passages = super::passages::split(&blocks);
+
         ParsedPage {
             title,
             meta_description,
             canonical_url,
             headings,
             internal_links,
+            internal_link_details,
             external_links,
             external_link_details,
             total_images,
             images_without_alt,
             schema_json_ld,
+            microdata,
+            rdfa,
             og_tags,
             robots_directives,
             has_robots_meta,
@@ -174,7 +255,10 @@ impl Parser {
             hreflang_urls,
             hreflang,
             analytics_tools,
-            custom_extractions: vec![],
+            custom_extractions,
+            content_fingerprint,
+            markdown,
+            passages,
         }
     }
 
@@ -296,14 +380,9 @@ impl Parser {
         headings
     }
 
-    fn extract_links(
-        document: &Html,
-        base: &Option<Url>,
-    ) -> (Vec<String>, Vec<String>, Vec<ExtractedLink>) {
+    fn extract_links(document: &Html, base: &Option<Url>, scope: Option<&ScopeMatcher>) -> Links {
         let sel = Selector::parse("a[href]").unwrap();
-        let mut internal = Vec::new();
-        let mut external = Vec::new();
-        let mut external_details = Vec::new();
+        let mut links = Links::default();
 
         let base_host = base
             .as_ref()
@@ -324,34 +403,41 @@ impl Parser {
                     let link_host = resolved_url.host_str().map(|h| h.to_lowercase());
                     let url_str = resolved_url.to_string();
 
-                    if link_host == base_host {
-                        internal.push(url_str);
+                    // Capture anchor text (trimmed, max 500 chars)
+                    let anchor_text = el
+                        .text()
+                        .collect::<String>()
+                        .trim()
+                        .chars()
+                        .take(500)
+                        .collect::<String>();
+
+                    // Capture rel attribute
+                    let rel = el.value().attr("rel").unwrap_or("").to_string();
+
+                    let is_external = match (scope, &link_host) {
+                        (Some(scope), Some(host)) => !scope.contains_host(host),
+                        _ => link_host != base_host,
+                    };
+                    let details = ExtractedLink {
+                        url: url_str.clone(),
+                        anchor_text,
+                        rel,
+                        is_external,
+                        placement: Some(link_placement(&el)),
+                    };
+                    if is_external {
+                        links.external_details.push(details);
+                        links.external.push(url_str);
                     } else {
-                        // Capture anchor text (trimmed, max 500 chars)
-                        let anchor_text = el
-                            .text()
-                            .collect::<String>()
-                            .trim()
-                            .chars()
-                            .take(500)
-                            .collect::<String>();
-
-                        // Capture rel attribute
-                        let rel = el.value().attr("rel").unwrap_or("").to_string();
-
-                        external_details.push(ExtractedLink {
-                            url: url_str.clone(),
-                            anchor_text,
-                            rel,
-                            is_external: true,
-                        });
-                        external.push(url_str);
+                        links.internal_details.push(details);
+                        links.internal.push(url_str);
                     }
                 }
             }
         }
 
-        (internal, external, external_details)
+        links
     }
 
     fn extract_image_stats(document: &Html) -> (u32, u32) {
@@ -585,6 +671,76 @@ mod tests {
         assert_eq!(plain.rel, "");
     }
 
+    #[test]
+    fn test_internal_link_details_and_placement() {
+        let html = r#"<!DOCTYPE html>
+<html><body>
+    <header><a href="/">Logo</a><nav><a href="/pricing">Pricing</a></nav></header>
+    <main>
+        <article>
+            <header><a href="/authors/ann">Ann</a></header>
+            <p>See <a href="/guide" rel="nofollow">the guide</a>.</p>
+            <aside><a href="/related">Related</a></aside>
+        </article>
+    </main>
+    <div role="contentinfo"><a href="/terms">Terms</a></div>
+    <footer><a href="/privacy">Privacy</a></footer>
+</body></html>"#;
+        let page = Parser::parse(html, "https://example.com");
+        let placements: Vec<(&str, Option<LinkPlacement>)> = page
+            .internal_link_details
+            .iter()
+            .map(|l| (l.anchor_text.as_str(), l.placement))
+            .collect();
+        assert_eq!(
+            placements,
+            vec![
+                ("Logo", Some(LinkPlacement::Header)),
+                ("Pricing", Some(LinkPlacement::Nav)),
+                ("Ann", Some(LinkPlacement::Content)),
+                ("the guide", Some(LinkPlacement::Content)),
+                ("Related", Some(LinkPlacement::Aside)),
+                ("Terms", Some(LinkPlacement::Footer)),
+                ("Privacy", Some(LinkPlacement::Footer)),
+            ]
+        );
+        assert_eq!(page.internal_link_details[3].rel, "nofollow");
+        assert!(page.internal_link_details.iter().all(|l| !l.is_external));
+        assert_eq!(page.internal_links.len(), page.internal_link_details.len());
+    }
+
+    #[test]
+    fn test_links_to_in_scope_hosts_are_internal() {
+        let html = r#"<html><body>
+            <a href="https://example.com/pricing">Pricing</a>
+            <a href="https://blog.example.com/post">Blog</a>
+            <a href="https://other.org/">Other</a>
+        </body></html>"#;
+        let page = Parser::parse(html, "https://www.example.com/");
+        assert!(page.internal_links.is_empty());
+
+        let scope = ScopeMatcher::new(
+            &crate::models::CrawlScope::Domain,
+            &["https://www.example.com/".to_string()],
+        );
+        let page = Parser::parse_with(
+            html,
+            "https://www.example.com/",
+            ParseOptions {
+                scope: Some(&scope),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            page.internal_links,
+            vec![
+                "https://example.com/pricing",
+                "https://blog.example.com/post"
+            ]
+        );
+        assert_eq!(page.external_links, vec!["https://other.org/"]);
+    }
+
     #[test]
     fn test_images() {
         let page = Parser::parse(TEST_HTML, "https://example.com/test");
@@ -0,0 +1,343 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use super::markdown::{estimate_tokens, Block, BlockKind};
+use crate::models::ContentPassage;
+
+/// A section's blocks are grouped into passages of up to this many words,
+/// about the span answer engines quote. A single longer block stays whole.
+const MAX_PASSAGE_WORDS: u32 = 200;
+
+/// Below this a passage is too short to stand on its own.
+const MIN_SELF_CONTAINED_WORDS: u32 = 25;
+
+/// Passages reported per page.
+const MAX_PASSAGES: usize = 200;
+
+/// Characters of text kept in `ContentPassage::excerpt`.
+const EXCERPT_CHARS: usize = 160;
+
+/// Words that, opening a passage, point back at something before it.
+const DANGLING_OPENERS: [&str; 16] = [
+    "this",
+    "that",
+    "these",
+    "those",
+    "it",
+    "its",
+    "they",
+    "them",
+    "he",
+    "she",
+    "such",
+    "also",
+    "however",
+    "therefore",
+    "additionally",
+    "furthermore",
+];
+
+/// Phrases that lean on text outside the passage.
+const OUTSIDE_REFERENCES: [&str; 8] = [
+    "as mentioned",
+    "as noted",
+    "as discussed",
+    "see above",
+    "see below",
+    "mentioned above",
+    "described above",
+    "previous section",
+];
+
+/// Subjects that make "X is a ..." a remark rather than a definition.
+const PRONOUN_SUBJECTS: [&str; 12] = [
+    "this", "that", "it", "there", "these", "those", "they", "he", "she", "we", "you", "here",
+];
+
+fn markup_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(?m)!?\[([^\]]*)\]\([^)]*\)|^\s*(?:[-*]|\d+\.)\s+|^\s*>\s?|^\|?(?:\s*-{3,}\s*\|)+\s*$|^```.*$|\*\*|`",
+        )
+        .expect("valid markup regex")
+    })
+}
+
+/// A number with a currency, a percent sign or a unit.
+fn measure_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(?i)[$€£¥]\s?\d|\d\s?(?:%|percent\b)|\b\d+(?:[.,]\d+)*\s?(?:k|m|bn|x|million|billion|thousand|ms|seconds?|minutes?|hours?|days?|weeks?|months?|years?|kb|mb|gb|tb|km|kg|lbs?)\b",
+        )
+        .expect("valid measure regex")
+    })
+}
+
+/// A bare number, with any thousands separators or decimals.
+fn quantity_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\b\d+(?:[.,]\d+)*\b").expect("valid quantity regex"))
+}
+
+fn definition_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^([A-Z][^,;:]{0,60}?)\s+(?:(?:is|are)\s+(?:a|an|the|one of)\s|refers to\s|is defined as\s|means\s|is known as\s)",
+        )
+        .expect("valid definition regex")
+    })
+}
+
+/// Text of a Markdown block without link targets, list, quote and code
+/// markers, bold/code markup or table rules.
+fn plain_text(markdown: &str) -> String {
+    markup_re()
+        .replace_all(markdown, "$1")
+        .replace('|', " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn sentences(text: &str) -> impl Iterator<Item = &str> {
+    text.split_inclusive(['.', '!', '?'])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+/// A sentence of the form "X is a ...", "X refers to ..." or "X means ...",
+/// where X isn't a pronoun. "is"/"are" need a determiner after them, so
+/// "Shipping is free" describes rather than defines.
+fn has_definition(text: &str) -> bool {
+    sentences(text).any(|sentence| {
+        definition_re().captures(sentence).is_some_and(|c| {
+            let subject = c[1].split_whitespace().next().unwrap_or_default();
+            !PRONOUN_SUBJECTS.contains(&subject.to_lowercase().as_str())
+        })
+    })
+}
+
+/// A figure worth citing: a number with a currency, percent sign or unit,
+/// or a quantity of several digits. Single digits ("H2", "Web 2.0", "step
+/// 3") and years don't count.
+fn has_statistic(text: &str) -> bool {
+    measure_re().is_match(text)
+        || quantity_re().find_iter(text).any(|m| {
+            let n = m.as_str();
+            let is_year = n.len() == 4 && (n.starts_with("19") || n.starts_with("20"));
+            n.contains(',') || (!n.contains('.') && n.len() >= 2 && !is_year)
+        })
+}
+
+fn is_self_contained(text: &str, word_count: u32) -> bool {
+    let opener = text
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    let lower = text.to_lowercase();
+    word_count >= MIN_SELF_CONTAINED_WORDS
+        && !DANGLING_OPENERS.contains(&opener.as_str())
+        && !OUTSIDE_REFERENCES.iter().any(|p| lower.contains(p))
+}
+
+/// Builds passages from a run of blocks under one heading path.
+struct Section {
+    heading_path: Vec<(u8, String)>,
+    blocks: Vec<(BlockKind, String)>,
+    words: u32,
+}
+
+impl Section {
+    fn flush(&mut self, out: &mut Vec<ContentPassage>) {
+        if self.blocks.is_empty() {
+            return;
+        }
+        let text = self
+            .blocks
+            .iter()
+            .map(|(_, t)| t.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let prose = self
+            .blocks
+            .iter()
+            .filter(|(kind, _)| matches!(kind, BlockKind::Paragraph | BlockKind::Quote))
+            .map(|(_, t)| t.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let has = |kind: BlockKind| self.blocks.iter().any(|(k, _)| *k == kind);
+        out.push(ContentPassage {
+            heading_path: self.heading_path.iter().map(|(_, h)| h.clone()).collect(),
+            excerpt: text.chars().take(EXCERPT_CHARS).collect(),
+            word_count: self.words,
+            token_count: estimate_tokens(text.len() as u64) as u32,
+            has_definition: has_definition(&prose),
+            has_statistic: has_statistic(&text),
+            has_list: has(BlockKind::List),
+            has_table: has(BlockKind::Table),
+            is_self_contained: is_self_contained(&text, self.words),
+        });
+        self.blocks.clear();
+        self.words = 0;
+    }
+}
+
+/// Split the main content into passages: a heading starts a new one, and
+/// within a section consecutive blocks share a passage up to
+/// `MAX_PASSAGE_WORDS`. Each carries its heading path (H1 > H2 > H3 ...).
+pub fn split(blocks: &[Block]) -> Vec<ContentPassage> {
+    let mut out = Vec::new();
+    let mut section = Section {
+        heading_path: Vec::new(),
+        blocks: Vec::new(),
+        words: 0,
+    };
+    for block in blocks {
+        if out.len() >= MAX_PASSAGES {
+            break;
+        }
+        match block.kind {
+            BlockKind::Heading(level) => {
+                section.flush(&mut out);
+                section.heading_path.retain(|(l, _)| *l < level);
+                let heading = plain_text(block.markdown.trim_start_matches('#'));
+                section.heading_path.push((level, heading));
+            }
+            BlockKind::Rule => section.flush(&mut out),
+            kind => {
+                let text = plain_text(&block.markdown);
+                let words = text.split_whitespace().count() as u32;
+                if words == 0 {
+                    continue;
+                }
+                if section.words > 0 && section.words + words > MAX_PASSAGE_WORDS {
+                    section.flush(&mut out);
+                }
+                section.blocks.push((kind, text));
+                section.words += words;
+            }
+        }
+    }
+    section.flush(&mut out);
+    out.truncate(MAX_PASSAGES);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::markdown::main_content_blocks;
+    use scraper::Html;
+
+    fn passages(html: &str) -> Vec<ContentPassage> {
+        split(&main_content_blocks(&Html::parse_document(html), None))
+    }
+
+    const FILLER: &str = "Crawlers read every page of a site and record what they find, \
+        from titles and headings to links, so that later analysis can score each page \
+        against the others.";
+
+    #[test]
+    fn test_passages_follow_heading_path() {
+        let html = format!(
+            r#"<main>
+                <h1>Guide</h1>
+                <p>Intro text.</p>
+                <h2>Basics</h2>
+                <p>A sitemap is a file that lists the URLs of a site. {FILLER}</p>
+                <h3>Formats</h3>
+                <ul><li>XML</li><li>Text</li></ul>
+                <h2>Numbers</h2>
+                <table><tr><th>Pages</th></tr><tr><td>1,200</td></tr></table>
+                <p>This shows 40% growth.</p>
+            </main>"#
+        );
+        let p = passages(&html);
+        let paths: Vec<Vec<&str>> = p
+            .iter()
+            .map(|p| p.heading_path.iter().map(String::as_str).collect())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                vec!["Guide"],
+                vec!["Guide", "Basics"],
+                vec!["Guide", "Basics", "Formats"],
+                vec!["Guide", "Numbers"],
+            ]
+        );
+
+        assert!(!p[0].is_self_contained);
+        assert!(p[1].has_definition);
+        assert!(p[1].is_self_contained);
+        assert!(!p[1].has_statistic);
+        assert!(p[2].has_list);
+        assert!(p[3].has_table && p[3].has_statistic);
+        assert!(!p[3].has_definition);
+        assert!(p[1].word_count > 30);
+        assert!(p[1].token_count > 0);
+        assert!(p[1].excerpt.starts_with("A sitemap is a file"));
+    }
+
+    #[test]
+    fn test_long_sections_split_at_paragraphs() {
+        let paragraph = format!("<p>{FILLER} {FILLER} {FILLER}</p>");
+        let html = format!("<main><h2>Long</h2>{}</main>", paragraph.repeat(5));
+        let p = passages(&html);
+        assert!(p.len() > 1);
+        assert!(p.iter().all(|p| p.word_count <= MAX_PASSAGE_WORDS));
+        assert!(p.iter().all(|p| p.heading_path == vec!["Long"]));
+    }
+
+    #[test]
+    fn test_definitions_and_dangling_openers() {
+        assert!(has_definition(
+            "Crawl budget refers to the pages a bot will fetch."
+        ));
+        assert!(has_definition(
+            "A canonical URL is the preferred version of a page."
+        ));
+        assert!(has_definition(
+            "Crawl delay is defined as seconds between requests."
+        ));
+        assert!(!has_definition("This is a great tool."));
+        assert!(!has_definition("Shipping is free on orders over $50."));
+        assert!(!has_definition("Paris is beautiful in spring."));
+        assert!(!has_definition("Our team is growing."));
+        assert!(!is_self_contained(
+            &format!("However, {FILLER}"),
+            MIN_SELF_CONTAINED_WORDS
+        ));
+        assert!(!is_self_contained(
+            &format!("{FILLER} As mentioned, it helps."),
+            MIN_SELF_CONTAINED_WORDS
+        ));
+    }
+
+    #[test]
+    fn test_statistics_need_a_unit_or_a_real_quantity() {
+        for text in [
+            "Traffic grew 40% last year.",
+            "Plans start at $5 a month.",
+            "The site has 1,200 pages.",
+            "Pages load 3x faster.",
+            "We serve 350 customers.",
+            "Revenue reached 2.5 million.",
+        ] {
+            assert!(has_statistic(text), "{text}");
+        }
+        for text in [
+            "Use one H2 per section.",
+            "Web 2.0 changed publishing.",
+            "What changed in 2024",
+            "Step 3: submit the sitemap.",
+        ] {
+            assert!(!has_statistic(text), "{text}");
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{Map, Value};
+use url::Url;
+
+use super::microdata::{add_property, resolve, text_value, type_value, MAX_DEPTH, MAX_ITEMS};
+
+/// Every top-level RDFa Lite resource on the page (a `typeof` that isn't
+/// itself the value of a `property`), in document order, as JSON-LD-shaped
+/// nodes. Prefixed terms from vocabularies other than schema.org are kept
+/// as-is.
+pub fn extract(document: &Html, base: Option<&Url>) -> Vec<Value> {
+    let sel = Selector::parse("[typeof]").unwrap();
+    document
+        .select(&sel)
+        .filter(|el| el.value().attr("property").is_none())
+        .take(MAX_ITEMS)
+        .map(|el| {
+            let vocab = std::iter::once(el)
+                .chain(el.ancestors().filter_map(ElementRef::wrap))
+                .find_map(|e| e.value().attr("vocab"))
+                .map(|v| v.trim().trim_end_matches('/'))
+                .filter(|v| !v.is_empty() && !v.ends_with("schema.org"))
+                .unwrap_or("https://schema.org");
+            let mut node = Map::new();
+            node.insert("@context".into(), vocab.into());
+            node.extend(resource(el, base, 0));
+            Value::Object(node)
+        })
+        .collect()
+}
+
+fn resource(el: ElementRef, base: Option<&Url>, depth: usize) -> Map<String, Value> {
+    let mut node = Map::new();
+    if let Some(types) = el.value().attr("typeof").and_then(type_value) {
+        node.insert("@type".into(), types);
+    }
+    if let Some(id) = el
+        .value()
+        .attr("resource")
+        .or_else(|| el.value().attr("about"))
+    {
+        node.insert("@id".into(), resolve(id, base).into());
+    }
+    let mut properties = Vec::new();
+    for child in el.children().filter_map(ElementRef::wrap) {
+        visit(child, base, depth, &mut properties);
+    }
+    for (name, value) in properties {
+        add_property(&mut node, name, value);
+    }
+    node
+}
+
+/// Collect the properties `el` and its descendants give the current
+/// resource, without descending into nested `typeof` resources.
+fn visit(el: ElementRef, base: Option<&Url>, depth: usize, out: &mut Vec<(String, Value)>) {
+    let is_resource = el.value().attr("typeof").is_some();
+    if let Some(names) = el.value().attr("property") {
+        let value = if is_resource {
+            (depth < MAX_DEPTH).then(|| Value::Object(resource(el, base, depth + 1)))
+        } else {
+            Some(Value::String(property_value(&el, base)))
+        };
+        if let Some(value) = value {
+            for name in names.split_whitespace() {
+                out.push((super::microdata::short_name(name), value.clone()));
+            }
+        }
+    }
+    if !is_resource {
+        for child in el.children().filter_map(ElementRef::wrap) {
+            visit(child, base, depth, out);
+        }
+    }
+}
+
+/// `content` wins, then a link target (`resource`, `href`, `src`), then a
+/// `<time datetime>`, then the element's text.
+fn property_value(el: &ElementRef, base: Option<&Url>) -> String {
+    let e = el.value();
+    if let Some(content) = e.attr("content") {
+        return content.trim().to_string();
+    }
+    if let Some(url) = e.attr("resource").or_else(|| match e.name() {
+        "a" | "area" | "link" => e.attr("href"),
+        "img" | "audio" | "video" | "source" | "iframe" | "embed" => e.attr("src"),
+        _ => None,
+    }) {
+        return resolve(url, base);
+    }
+    match (e.name(), e.attr("datetime")) {
+        ("time", Some(datetime)) => datetime.trim().to_string(),
+        _ => text_value(el),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rdfa_lite_resources() {
+        let base = Url::parse("https://example.com/blog/post").unwrap();
+        let html = r##"<html><head>
+                <meta property="og:title" content="Not an RDFa resource">
+            </head><body vocab="https://schema.org/">
+            <article typeof="BlogPosting" resource="#post">
+                <h1 property="headline">Hello</h1>
+                <time property="datePublished" datetime="2025-02-03">Feb 3</time>
+                <div property="author" typeof="Person">
+                    <a property="url" href="/about"><span property="name">Ann</span></a>
+                </div>
+                <img property="schema:image" src="cover.png">
+            </article>
+            <div typeof="schema:Organization"><span property="name">Acme</span></div>
+        </body></html>"##;
+        let nodes = extract(&Html::parse_document(html), Some(&base));
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(
+            nodes[0],
+            json!({
+                "@context": "https://schema.org",
+                "@type": "BlogPosting",
+                "@id": "https://example.com/blog/post#post",
+                "headline": "Hello",
+                "datePublished": "2025-02-03",
+                "author": {
+                    "@type": "Person",
+                    "url": "https://example.com/about",
+                    "name": "Ann"
+                },
+                "image": "https://example.com/blog/cover.png"
+            })
+        );
+        assert_eq!(nodes[1]["@type"], "Organization");
+    }
+}
@@ -34,7 +34,10 @@ pub struct TextHtmlRatio {
 // ─── Domain Logic ───────────────────────────────────────────────────
 
 /// Site-chrome tags whose paragraphs are boilerplate, not main content.
-const CHROME_TAGS: [&str; 4] = ["nav", "header", "footer", "aside"];
+pub(crate) const CHROME_TAGS: [&str; 4] = ["nav", "header", "footer", "aside"];
+
+/// ARIA landmarks that mark site chrome, like the `CHROME_TAGS`.
+const CHROME_ROLES: [&str; 4] = ["navigation", "banner", "contentinfo", "complementary"];
 
 /// Minimum words the cleaned main-content sample must yield before we trust it
 /// over the full all-`<p>` text. Below this a stripped page (chrome-only, or a
@@ -129,6 +132,55 @@ pub fn compute_text_html_ratio(document: &Html, raw_html: &str) -> TextHtmlRatio
     }
 }
 
+/// All visible text of the main-content region (first `<main>`/`<article>`,
+/// else `<body>`), minus site chrome and `<script>`/`<style>`/`<noscript>`/
+/// `<template>` contents. Unlike the Flesch sample this keeps headings, lists
+/// and tables, so it fingerprints what the page is actually about.
+pub fn main_content_text(document: &Html) -> String {
+    const HIDDEN_TAGS: [&str; 4] = ["script", "style", "noscript", "template"];
+    let Some(root) = main_content_root(document) else {
+        return String::new();
+    };
+
+    root.descendants()
+        .filter_map(|node| {
+            let text = node.value().as_text()?;
+            let skipped = node
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .any(|a| HIDDEN_TAGS.contains(&a.value().name()) || is_page_chrome(&a));
+            (!skipped).then_some(&**text)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether `el` is site chrome left out of the main content: a chrome tag or
+/// ARIA landmark. A `<header>`/`<footer>` inside an article is part of it (its
+/// title, byline or notes), as in `link_placement`.
+pub(crate) fn is_page_chrome(el: &ElementRef) -> bool {
+    let e = el.value();
+    let chrome = CHROME_TAGS.contains(&e.name())
+        || e.attr("role").is_some_and(|r| CHROME_ROLES.contains(&r));
+    chrome
+        && !(matches!(e.name(), "header" | "footer")
+            && el.ancestors().any(|a| {
+                a.value()
+                    .as_element()
+                    .is_some_and(|a| matches!(a.name(), "main" | "article"))
+            }))
+}
+
+/// The main-content region: the first `<main>`/`<article>`, else `<body>`.
+pub(crate) fn main_content_root(document: &Html) -> Option<ElementRef<'_>> {
+    let region_sel = Selector::parse("main, article").expect("valid region selector");
+    let body_sel = Selector::parse("body").expect("valid body selector");
+    document
+        .select(&region_sel)
+        .next()
+        .or_else(|| document.select(&body_sel).next())
+}
+
 /// Byte length of HTML with `<script>`, `<style>` blocks and comments removed,
 /// used as the text-to-HTML ratio denominator.
 fn content_html_length(raw_html: &str) -> usize {
@@ -158,15 +210,8 @@ fn all_paragraph_text(document: &Html) -> String {
 /// nested under site chrome (`<nav>`/`<header>`/`<footer>`/`<aside>`, wherever it
 /// sits — including in-content chrome inside the region).
 fn main_content_paragraph_text(document: &Html) -> String {
-    let region_sel = Selector::parse("main, article").expect("valid region selector");
-    let body_sel = Selector::parse("body").expect("valid body selector");
     let p_sel = Selector::parse("p").expect("valid p selector");
-
-    let Some(root) = document
-        .select(&region_sel)
-        .next()
-        .or_else(|| document.select(&body_sel).next())
-    else {
+    let Some(root) = main_content_root(document) else {
         return String::new();
     };
 
@@ -442,4 +487,18 @@ mod tests {
         assert_eq!(count_word_syllables("hello"), 2);
         assert_eq!(count_word_syllables("beautiful"), 3);
     }
+
+    #[test]
+    fn test_main_content_text_skips_chrome_and_scripts() {
+        let html = Html::parse_document(
+            "<html><body><nav>Home About</nav><main><header><h1>Clinic</h1></header>\
+             <script>var csrf = 'x';</script><ul><li>Open daily</li></ul>\
+             <aside>Related</aside><div role=\"navigation\">Next</div>\
+             <footer>Reviewed</footer></main><footer>Copyright</footer></body></html>",
+        );
+        let text = main_content_text(&html);
+        let words: Vec<&str> = text.split_whitespace().collect();
+        // An article's own header and footer stay, as in the Markdown.
+        assert_eq!(words, vec!["Clinic", "Open", "daily", "Reviewed"]);
+    }
 }
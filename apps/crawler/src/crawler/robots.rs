@@ -1,7 +1,10 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use thiserror::Error;
+use tokio::sync::{Mutex, OnceCell};
 use url::Url;
 
+use super::egress::EgressPolicy;
 use crate::models::DEFAULT_USER_AGENT;
 
 #[derive(Error, Debug)]
@@ -12,130 +15,252 @@ pub enum RobotsError {
     UrlError(String),
 }
 
-/// Known AI bot user agents to check in robots.txt.
+/// AI bots whose root-level block feeds `SiteContext.ai_crawlers_blocked` and
+/// with it the `AI_CRAWLER_BLOCKED` score factor. The full catalog, with vendor
+/// and purpose, is `ai_crawlers::AI_CRAWLERS`.
 pub const AI_BOT_USER_AGENTS: &[&str] = &["GPTBot", "ClaudeBot", "PerplexityBot", "GoogleOther"];
 
+/// Bytes of robots.txt we parse (RFC 9309 §2.5 requires at least 500 KiB);
+/// anything past the last complete line within the limit is ignored.
+const MAX_ROBOTS_BYTES: usize = 500 * 1024;
+
+/// Consecutive redirects followed for robots.txt (RFC 9309 §2.3.1.2). Past
+/// that the file is treated as unavailable.
+const MAX_ROBOTS_REDIRECTS: usize = 5;
+
 /// A single parsed robots.txt rule (allow or disallow).
 #[derive(Debug, Clone)]
 struct RobotsRule {
-    path: String,
+    /// Percent-normalized path pattern; may contain `*` and a trailing `$`.
+    pattern: String,
     allow: bool,
 }
 
-/// Parsed robots.txt rules for a single domain.
+/// The rules and crawl delay of every group naming one product token. Repeated
+/// groups for the same token are merged (RFC 9309 §2.2.1).
+#[derive(Debug, Clone, Default)]
+struct RobotsGroup {
+    rules: Vec<RobotsRule>,
+    /// `Crawl-delay` in seconds. Not part of RFC 9309, but widely used.
+    crawl_delay: Option<f64>,
+}
+
+/// Parsed robots.txt rules for a single domain, matched per RFC 9309.
 pub struct RobotsChecker {
-    /// Map from lowercase user-agent to list of allow/disallow rules.
-    rules: HashMap<String, Vec<RobotsRule>>,
+    /// Map from lowercase product token (or `*`) to its merged group.
+    groups: HashMap<String, RobotsGroup>,
     /// Sitemaps discovered in robots.txt
     pub sitemaps: Vec<String>,
     /// Whether we successfully fetched and parsed the robots.txt.
     pub loaded: bool,
+    /// robots.txt was unreachable (5xx, 429 or a network error), so
+    /// everything is disallowed (RFC 9309 §2.3.1.4). That says nothing about
+    /// which bots the site means to block, so bot reports skip it.
+    pub unreachable: bool,
 }
 
 impl RobotsChecker {
-    /// Fetch and parse robots.txt for the given domain.
-    pub async fn new(domain: &str) -> Result<Self, RobotsError> {
-        let robots_url = format!("https://{}/robots.txt", domain);
-        let client = reqwest::Client::builder()
+    /// Fetch and parse the robots.txt of the site `site_url` is on, keeping its
+    /// scheme and port. A host the egress policy refuses is treated like one
+    /// without robots.txt.
+    pub async fn new(site_url: &str, egress: &EgressPolicy) -> Result<Self, RobotsError> {
+        let robots_url = Url::parse(site_url)
+            .and_then(|u| u.join("/robots.txt"))
+            .map_err(|e| RobotsError::UrlError(format!("{site_url}: {e}")))?;
+        Self::fetch(robots_url.as_str(), egress).await
+    }
+
+    /// Fetch and parse the robots.txt at `robots_url`, mapping the outcome per
+    /// RFC 9309 §2.3.1: a 2xx is parsed, a 4xx (or too many redirects) allows
+    /// everything, and a 5xx, 429 or network error disallows everything.
+    pub async fn fetch(robots_url: &str, egress: &EgressPolicy) -> Result<Self, RobotsError> {
+        let client = egress
+            .client_builder_with_redirects(MAX_ROBOTS_REDIRECTS)
             .timeout(std::time::Duration::from_secs(10))
             .user_agent(DEFAULT_USER_AGENT)
             .build()?;
 
-        let response = match client.get(&robots_url).send().await {
-            Ok(resp) if resp.status().is_success() => resp,
-            Ok(_) => {
-                // No robots.txt or error — everything is allowed
-                return Ok(RobotsChecker {
-                    rules: HashMap::new(),
-                    sitemaps: Vec::new(),
-                    loaded: false,
-                });
-            }
-            Err(_) => {
-                return Ok(RobotsChecker {
-                    rules: HashMap::new(),
-                    sitemaps: Vec::new(),
-                    loaded: false,
-                });
+        if egress.check_url(robots_url).is_err() {
+            return Ok(Self::not_loaded());
+        }
+
+        let mut response = match client.get(robots_url).send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                // Our policy refusing the host says nothing about the site.
+                if super::egress::find_egress_error(&e).is_some() {
+                    return Ok(Self::not_loaded());
+                }
+                tracing::warn!(url = %robots_url, error = %e, "robots.txt unreachable");
+                return Ok(Self::unreachable());
             }
         };
 
-        let body = response.text().await.unwrap_or_default();
-        let (rules, sitemaps) = Self::parse_robots_txt(&body);
+        let status = response.status();
+        if status.as_u16() == 429 || status.is_server_error() {
+            tracing::warn!(url = %robots_url, status = %status, "robots.txt unreachable");
+            return Ok(Self::unreachable());
+        }
+        if !status.is_success() {
+            // 4xx, or a redirect we stopped following — no robots.txt.
+            return Ok(Self::not_loaded());
+        }
 
-        Ok(RobotsChecker {
-            rules,
-            sitemaps,
-            loaded: true,
-        })
+        let mut body: Vec<u8> = Vec::new();
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    body.extend_from_slice(&chunk);
+                    if body.len() > MAX_ROBOTS_BYTES {
+                        break;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::warn!(url = %robots_url, error = %e, "robots.txt body unreadable");
+                    return Ok(Self::unreachable());
+                }
+            }
+        }
+        if body.len() > MAX_ROBOTS_BYTES {
+            body.truncate(MAX_ROBOTS_BYTES);
+            // Drop the partial last line; a cut-off pattern would match too much.
+            let end = body.iter().rposition(|b| *b == b'\n').unwrap_or(0);
+            body.truncate(end);
+        }
+
+        Ok(Self::parse_robots_txt(&String::from_utf8_lossy(&body)))
     }
 
     /// Create a RobotsChecker from raw robots.txt content (useful for testing).
     pub fn from_content(content: &str) -> Self {
-        let (rules, sitemaps) = Self::parse_robots_txt(content);
+        Self::parse_robots_txt(content)
+    }
+
+    /// No robots.txt (missing or refused) — everything is allowed.
+    fn not_loaded() -> Self {
         RobotsChecker {
-            rules,
-            sitemaps,
-            loaded: true,
+            groups: HashMap::new(),
+            sitemaps: Vec::new(),
+            loaded: false,
+            unreachable: false,
         }
     }
 
+    /// robots.txt couldn't be fetched — everything is disallowed.
+    fn unreachable() -> Self {
+        let group = RobotsGroup {
+            rules: vec![RobotsRule {
+                pattern: "/".to_string(),
+                allow: false,
+            }],
+            crawl_delay: None,
+        };
+        RobotsChecker {
+            groups: HashMap::from([("*".to_string(), group)]),
+            sitemaps: Vec::new(),
+            loaded: false,
+            unreachable: true,
+        }
+    }
+
+    /// The group that applies to `user_agent`: the one naming its product
+    /// token, else the `*` group. A matching group with no rules still wins,
+    /// and then allows everything.
+    fn group_for(&self, user_agent: &str) -> Option<&RobotsGroup> {
+        let token = product_token(user_agent);
+        self.groups.get(&token).or_else(|| self.groups.get("*"))
+    }
+
+    /// Whether a group in robots.txt names `user_agent`'s product token, as
+    /// opposed to it falling back to the `*` group.
+    pub fn names_agent(&self, user_agent: &str) -> bool {
+        self.groups.contains_key(&product_token(user_agent))
+    }
+
+    /// The `Crawl-delay` that applies to `user_agent`: its own group's value,
+    /// else the wildcard group's. A value too large for a `Duration` saturates;
+    /// the fetcher caps it anyway.
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<std::time::Duration> {
+        let token = product_token(user_agent);
+        [token.as_str(), "*"]
+            .iter()
+            .find_map(|agent| self.groups.get(*agent).and_then(|g| g.crawl_delay))
+            .map(|secs| {
+                std::time::Duration::try_from_secs_f64(secs).unwrap_or(std::time::Duration::MAX)
+            })
+    }
+
     /// Check if the given URL is allowed for the specified user agent.
     /// Implements longest-match precedence per the robots.txt spec:
     /// the most specific (longest) matching rule wins regardless of allow/disallow order.
     pub fn is_allowed(&self, url: &str, user_agent: &str) -> bool {
+        self.deciding_rule(url, user_agent)
+            .map(|r| r.allow)
+            .unwrap_or(true)
+    }
+
+    /// The rule that blocks `url` for `user_agent`, rendered as it appears in
+    /// robots.txt (e.g. `Disallow: /private`), or `None` when it is allowed.
+    pub fn blocking_rule(&self, url: &str, user_agent: &str) -> Option<String> {
+        let rule = self.deciding_rule(url, user_agent).filter(|r| !r.allow)?;
+        if self.unreachable {
+            Some(format!(
+                "Disallow: {} (robots.txt unreachable)",
+                rule.pattern
+            ))
+        } else {
+            Some(format!("Disallow: {}", rule.pattern))
+        }
+    }
+
+    /// The rule that decides whether `url` is allowed for `user_agent`, or
+    /// `None` when no rule matches (default allow).
+    fn deciding_rule(&self, url: &str, user_agent: &str) -> Option<&RobotsRule> {
         // Accept either a full absolute URL or a bare path. A bare path like "/"
         // fails `Url::parse` (relative URL without a base); previously that hit the
         // default-allow branch, which silently made bot-blocking checks a no-op.
+        // Rules match against the path plus query (RFC 9309 §2.2.2).
         let path = match Url::parse(url) {
-            Ok(u) => u.path().to_string(),
+            Ok(u) => match u.query() {
+                Some(q) => format!("{}?{}", u.path(), q),
+                None => u.path().to_string(),
+            },
             Err(_) if url.starts_with('/') => url.to_string(),
-            Err(_) => return true,
+            Err(_) => return None,
         };
+        let path = normalize_percent_encoding(&path, true);
 
-        let ua_lower = user_agent.to_lowercase();
-
-        // Check specific user-agent rules first, then fall back to wildcard.
-        // Stop at the first agent that has any matching rules.
-        let agents_to_check = [ua_lower.as_str(), "*"];
-
-        for agent in &agents_to_check {
-            if let Some(rules) = self.rules.get(*agent) {
-                // Collect every rule whose path is a prefix of the request path.
-                // (Empty-path rules never match here; an empty `Disallow:` means
-                // "allow all" and is handled by the default-allow fallthrough below.)
-                let matched = rules
-                    .iter()
-                    .filter(|r| !r.path.is_empty() && path.starts_with(r.path.as_str()));
-
-                if let Some(max_len) = matched.clone().map(|r| r.path.len()).max() {
-                    // Longest-match precedence selects the most specific rules. On
-                    // an equal-length tie between an Allow and a Disallow, the
-                    // robots.txt spec says the least-restrictive rule wins: Allow
-                    // beats Disallow regardless of their order in the file
-                    // (https://developers.google.com/search/docs/crawling-indexing/robots/robots_txt#order-of-precedence-for-rules).
-                    // So a URL is blocked only when EVERY longest-matching rule is
-                    // a Disallow — if any is an Allow, it is allowed. Using
-                    // `max_by_key(len).allow` instead would return whichever tied
-                    // rule appears last in the file, falsely flagging a site that
-                    // lists `Allow: /` before a same-length `Disallow: /`.
-                    return matched
-                        .filter(|r| r.path.len() == max_len)
-                        .any(|r| r.allow);
-                }
-
-                // If this agent has rules but none matched, check for an empty Disallow
-                // (which means "allow everything for this agent").
-                // We only stop here if the agent actually had rules defined.
-                if !rules.is_empty() {
-                    // No rule matched — allowed by default for this agent block.
-                    return true;
-                }
-            }
+        // robots.txt itself is always allowed (RFC 9309 §2.2.2).
+        if path == "/robots.txt" {
+            return None;
         }
 
-        // No rules at all — default allow.
-        true
+        let group = self.group_for(user_agent)?;
+
+        // Empty patterns never match: an empty `Disallow:` means "allow all".
+        let matched = group
+            .rules
+            .iter()
+            .filter(|r| !r.pattern.is_empty() && pattern_matches(&r.pattern, &path));
+
+        // Longest-match precedence selects the most specific rules, measured in
+        // octets of the pattern. On an equal-length tie between an Allow and a
+        // Disallow, the least-restrictive rule wins: Allow beats Disallow
+        // regardless of their order in the file (RFC 9309 §2.2.2;
+        // https://developers.google.com/search/docs/crawling-indexing/robots/robots_txt#order-of-precedence-for-rules).
+        // So a URL is blocked only when EVERY longest-matching rule is a
+        // Disallow — if any is an Allow, it is allowed. Using
+        // `max_by_key(len).allow` instead would return whichever tied rule
+        // appears last in the file, falsely flagging a site that lists
+        // `Allow: /` before a same-length `Disallow: /`.
+        let max_len = matched.clone().map(|r| r.pattern.len()).max()?;
+        let longest: Vec<&RobotsRule> = matched.filter(|r| r.pattern.len() == max_len).collect();
+        longest
+            .iter()
+            .find(|r| r.allow)
+            .or(longest.first())
+            .copied()
     }
 
     /// Check which AI bots are blocked for a given URL.
@@ -147,83 +272,236 @@ impl RobotsChecker {
             .collect()
     }
 
-    /// Parse robots.txt content into a map of user-agent -> rules (allow/disallow), and list of sitemaps.
-    fn parse_robots_txt(content: &str) -> (HashMap<String, Vec<RobotsRule>>, Vec<String>) {
-        let mut rules: HashMap<String, Vec<RobotsRule>> = HashMap::new();
+    /// Parse robots.txt content into groups keyed by product token, and the
+    /// list of sitemaps.
+    ///
+    /// Grouping follows RFC 9309 §2.1: consecutive `user-agent` lines open one
+    /// group, and the group's rules follow until the next `user-agent` line
+    /// that comes after a rule. Blank lines don't end a group. Rules before any
+    /// `user-agent` line belong to no group and are ignored.
+    fn parse_robots_txt(content: &str) -> Self {
+        let mut groups: HashMap<String, RobotsGroup> = HashMap::new();
         let mut sitemaps: Vec<String> = Vec::new();
         let mut current_agents: Vec<String> = Vec::new();
+        // Set once the current group has a rule, so the next `user-agent`
+        // line starts a new group instead of joining this one.
+        let mut in_rules = false;
 
-        for line in content.lines() {
-            let line = line.trim();
+        // A UTF-8 byte order mark MAY precede the first line.
+        let content = content.strip_prefix('\u{feff}').unwrap_or(content);
 
-            // Strip inline comments
-            let line = if let Some(idx) = line.find('#') {
-                line[..idx].trim()
-            } else {
-                line
+        for line in content.lines() {
+            // Strip comments
+            let line = match line.find('#') {
+                Some(idx) => &line[..idx],
+                None => line,
             };
-
+            let line = line.trim();
             if line.is_empty() {
-                // Empty line resets current user-agent context
-                current_agents.clear();
                 continue;
             }
 
-            if let Some((key, value)) = line.split_once(':') {
-                let key = key.trim().to_lowercase();
-                let value = value.trim();
-
-                match key.as_str() {
-                    "user-agent" => {
-                        let ua = value.to_lowercase();
-                        current_agents.push(ua);
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        current_agents.clear();
+                        in_rules = false;
                     }
-                    "allow" => {
-                        for agent in &current_agents {
-                            rules.entry(agent.clone()).or_default().push(RobotsRule {
-                                path: value.to_string(),
-                                allow: true,
-                            });
-                        }
+                    let token = if value == "*" {
+                        "*".to_string()
+                    } else {
+                        product_token(value)
+                    };
+                    if !token.is_empty() {
+                        groups.entry(token.clone()).or_default();
+                        current_agents.push(token);
                     }
-                    "disallow" => {
-                        for agent in &current_agents {
-                            rules.entry(agent.clone()).or_default().push(RobotsRule {
-                                path: value.to_string(),
-                                allow: false,
-                            });
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    let rule = RobotsRule {
+                        pattern: normalize_percent_encoding(value, false),
+                        allow: key == "allow",
+                    };
+                    for agent in &current_agents {
+                        if let Some(group) = groups.get_mut(agent) {
+                            group.rules.push(rule.clone());
                         }
                     }
-                    "sitemap" => {
-                        if !value.is_empty() {
-                            sitemaps.push(value.to_string());
+                }
+                "crawl-delay" => {
+                    in_rules = true;
+                    // Seconds, possibly fractional. Unparseable, negative or
+                    // non-finite values are ignored rather than guessed at.
+                    if let Ok(secs) = value.parse::<f64>() {
+                        if secs.is_finite() && secs >= 0.0 {
+                            for agent in &current_agents {
+                                if let Some(group) = groups.get_mut(agent) {
+                                    group.crawl_delay = Some(secs);
+                                }
+                            }
                         }
                     }
-                    _ => {
-                        // Crawl-delay, etc. — ignored
-                    }
+                }
+                "sitemap" if !value.is_empty() => {
+                    // Not a group member; applies to the whole file.
+                    sitemaps.push(value.to_string());
+                }
+                _ => {
+                    // Host, Clean-param, etc. — ignored
                 }
             }
         }
 
-        (rules, sitemaps)
+        RobotsChecker {
+            groups,
+            sitemaps,
+            loaded: true,
+            unreachable: false,
+        }
     }
 }
 
-/// Fetch /llms.txt from a domain. Returns the content if found (HTTP 200).
-pub async fn fetch_llms_txt(domain: &str) -> Option<String> {
-    let url = format!("https://{}/llms.txt", domain);
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .user_agent(DEFAULT_USER_AGENT)
-        .build()
-        .ok()?;
-
-    let response = client.get(&url).send().await.ok()?;
-    if response.status().is_success() {
-        response.text().await.ok()
+/// The product token of a user-agent string, lowercased: its leading run of
+/// letters, `_` and `-` (RFC 9309 §2.2.1). `"LLMRankBot/1.0 (+https://...)"`
+/// becomes `"llmrankbot"`; `"*"` has no product token.
+fn product_token(user_agent: &str) -> String {
+    user_agent
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic() || *c == '_' || *c == '-')
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Bring a path (or rule pattern) to the form RFC 9309 §2.2.2 compares in:
+/// non-ASCII bytes, spaces and controls are percent-encoded, escapes of
+/// unreserved characters are decoded, and the remaining escapes get uppercase
+/// hex. So `/%62%61%7A` and `/baz` compare equal, as do `/ツ` and `/%E3%83%84`.
+///
+/// For a URL path (`escape_specials`), literal `*` and `$` are encoded too, so
+/// a pattern can match them only as `%2A` / `%24` (RFC 9309 §2.2.3).
+fn normalize_percent_encoding(path: &str, escape_specials: bool) -> String {
+    let bytes = path.as_bytes();
+    let mut out = String::with_capacity(path.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            let hex = &path[i + 1..i + 3];
+            let decoded = u8::from_str_radix(hex, 16).expect("two hex digits");
+            if decoded.is_ascii_alphanumeric() || b"-._~".contains(&decoded) {
+                out.push(decoded as char);
+            } else {
+                out.push('%');
+                out.push_str(&hex.to_ascii_uppercase());
+            }
+            i += 3;
+            continue;
+        }
+        if b.is_ascii_graphic() && !(escape_specials && (b == b'*' || b == b'$')) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Match a normalized path against a rule pattern: `*` matches any run of
+/// characters, a trailing `$` anchors the end, and otherwise the pattern is a
+/// prefix match.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    // `split` always yields at least one part.
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let literals: Vec<&str> = parts.collect();
+    let Some((last, middle)) = literals.split_last() else {
+        // No `*`: a plain prefix, or an exact match when anchored.
+        return !anchored || rest.is_empty();
+    };
+    for literal in middle {
+        match rest.find(literal) {
+            Some(pos) => rest = &rest[pos + literal.len()..],
+            None => return false,
+        }
+    }
+    if anchored {
+        // Leftmost matching of the middle parts leaves the most room for the
+        // anchored tail, so checking the suffix here is enough.
+        rest.ends_with(last)
     } else {
-        None
+        rest.contains(last)
+    }
+}
+
+/// robots.txt per host. Hosts known before the crawl are inserted up front;
+/// any other in-scope host has its robots.txt fetched the first time one of
+/// its URLs comes up.
+pub struct HostRobots {
+    egress: EgressPolicy,
+    /// One cell per host, so workers that need the same host's robots.txt
+    /// at once wait on a single fetch.
+    checkers: Mutex<HashMap<String, Arc<OnceCell<Arc<RobotsChecker>>>>>,
+}
+
+impl HostRobots {
+    pub fn new(egress: EgressPolicy) -> Self {
+        HostRobots {
+            egress,
+            checkers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn insert(&mut self, host: &str, checker: RobotsChecker) {
+        self.checkers.get_mut().insert(
+            host.to_ascii_lowercase(),
+            Arc::new(OnceCell::new_with(Some(Arc::new(checker)))),
+        );
+    }
+
+    /// The checker for the host `url` is on, and whether this call fetched it.
+    pub async fn for_url(&self, url: &Url) -> (Arc<RobotsChecker>, bool) {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let cell = self
+            .checkers
+            .lock()
+            .await
+            .entry(host.clone())
+            .or_default()
+            .clone();
+        let mut fetched = false;
+        let checker = cell
+            .get_or_init(|| async {
+                fetched = true;
+                let checker = RobotsChecker::new(url.as_str(), &self.egress)
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::warn!(host = %host, error = %e, "robots.txt fetch failed");
+                        RobotsChecker::not_loaded()
+                    });
+                Arc::new(checker)
+            })
+            .await;
+        (checker.clone(), fetched)
     }
 }
 
@@ -265,6 +543,21 @@ Disallow: /search
         assert!(!checker.is_allowed("https://example.com/any/page", "GPTBot"));
     }
 
+    #[test]
+    fn test_blocking_rule_reports_matched_disallow() {
+        let checker = RobotsChecker::from_content(SAMPLE_ROBOTS);
+        assert_eq!(
+            checker
+                .blocking_rule("https://example.com/private/data", "MyBot")
+                .as_deref(),
+            Some("Disallow: /private/")
+        );
+        assert_eq!(
+            checker.blocking_rule("https://example.com/public", "MyBot"),
+            None
+        );
+    }
+
     #[test]
     fn test_claudebot_blocked() {
         let checker = RobotsChecker::from_content(SAMPLE_ROBOTS);
@@ -505,4 +798,131 @@ Disallow: /
         assert!(!checker.is_allowed("https://example.com/", "GPTBot"));
         assert!(checker.blocked_bots("/").contains(&"GPTBot".to_string()));
     }
+
+    #[test]
+    fn test_crawl_delay_per_user_agent_group() {
+        let content = "User-agent: *\nCrawl-delay: 2\n\nUser-agent: SlowBot\nCrawl-delay: 0.5\nDisallow: /x\n\nUser-agent: BadBot\nCrawl-delay: soon\n";
+        let checker = RobotsChecker::from_content(content);
+        assert_eq!(
+            checker.crawl_delay("SlowBot"),
+            Some(std::time::Duration::from_millis(500))
+        );
+        // No group of its own (or an unparseable value): the wildcard applies.
+        assert_eq!(
+            checker.crawl_delay("OtherBot"),
+            Some(std::time::Duration::from_secs(2))
+        );
+        assert_eq!(
+            checker.crawl_delay("BadBot"),
+            Some(std::time::Duration::from_secs(2))
+        );
+        assert_eq!(RobotsChecker::from_content("").crawl_delay("x"), None);
+    }
+
+    #[test]
+    fn test_huge_crawl_delay_saturates_instead_of_panicking() {
+        let checker = RobotsChecker::from_content("User-agent: *\nCrawl-delay: 1e20\n");
+        assert_eq!(
+            checker.crawl_delay("AnyBot"),
+            Some(std::time::Duration::MAX)
+        );
+    }
+
+    #[test]
+    fn test_pattern_matches_wildcards_and_anchors() {
+        assert!(pattern_matches("/fish", "/fish.html"));
+        assert!(!pattern_matches("/fish", "/Fish.asp"));
+        assert!(pattern_matches("/fish*", "/fishheads/yummy.html"));
+        assert!(pattern_matches("/*.php", "/folder/filename.php?parameters"));
+        assert!(!pattern_matches("/*.php", "/windows.PHP"));
+        assert!(pattern_matches("/*.php$", "/folder/filename.php"));
+        assert!(!pattern_matches("/*.php$", "/filename.php?parameters"));
+        assert!(!pattern_matches("/*.php$", "/filename.php5"));
+        assert!(pattern_matches(
+            "/fish*.php",
+            "/fishheads/catfish.php?parameters"
+        ));
+        assert!(!pattern_matches("/fish*.php", "/Fish.PHP"));
+        assert!(pattern_matches("/this/path/exactly$", "/this/path/exactly"));
+        assert!(!pattern_matches(
+            "/this/path/exactly$",
+            "/this/path/exactly/not"
+        ));
+        assert!(pattern_matches("*.gif$", "/images/a.gif"));
+        assert!(pattern_matches("/*", "/"));
+        assert!(pattern_matches("/a*b*c$", "/abxbc"));
+    }
+
+    #[test]
+    fn test_normalize_percent_encoding() {
+        assert_eq!(
+            normalize_percent_encoding("/foo/bar?baz=quz", true),
+            "/foo/bar?baz=quz"
+        );
+        assert_eq!(
+            normalize_percent_encoding("/foo/bar/ツ", false),
+            "/foo/bar/%E3%83%84"
+        );
+        assert_eq!(
+            normalize_percent_encoding("/foo/bar/%e3%83%84", true),
+            "/foo/bar/%E3%83%84"
+        );
+        assert_eq!(
+            normalize_percent_encoding("/foo/bar/%62%61%7A", true),
+            "/foo/bar/baz"
+        );
+        // Reserved characters stay escaped; a stray `%` is left alone.
+        assert_eq!(normalize_percent_encoding("/a%2fb%", true), "/a%2Fb%");
+        assert_eq!(normalize_percent_encoding("/a*b$", true), "/a%2Ab%24");
+        assert_eq!(normalize_percent_encoding("/a*b$", false), "/a*b$");
+    }
+
+    #[test]
+    fn test_product_token() {
+        assert_eq!(
+            product_token("LLMRankBot/1.0 (+https://llmrank.app/bot)"),
+            "llmrankbot"
+        );
+        assert_eq!(product_token("Google-Extended"), "google-extended");
+        assert_eq!(product_token("*"), "");
+    }
+
+    #[tokio::test]
+    async fn test_host_robots_serves_inserted_checkers() {
+        let mut robots = HostRobots::new(EgressPolicy::default());
+        robots.insert(
+            "Docs.Example.com",
+            RobotsChecker::from_content(SAMPLE_ROBOTS),
+        );
+        let url = Url::parse("https://docs.example.com/private/x").unwrap();
+        let (checker, fetched) = robots.for_url(&url).await;
+        assert!(!fetched);
+        assert!(checker.loaded);
+    }
+
+    #[tokio::test]
+    async fn test_host_robots_fetches_each_host_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let site = axum::Router::new().route(
+            "/robots.txt",
+            axum::routing::get(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                SAMPLE_ROBOTS
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, site).await.unwrap() });
+
+        let robots = HostRobots::new(EgressPolicy::new(vec!["127.0.0.1".parse().unwrap()]));
+        let url = Url::parse(&format!("http://{addr}/private/x")).unwrap();
+        let results = futures::future::join_all((0..8).map(|_| robots.for_url(&url))).await;
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        assert_eq!(results.iter().filter(|(_, fetched)| *fetched).count(), 1);
+        assert!(results.iter().all(|(checker, _)| checker.loaded));
+    }
 }
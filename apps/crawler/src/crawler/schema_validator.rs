@@ -0,0 +1,477 @@
+use serde_json::{Map, Value};
+
+use crate::models::{SchemaIssue, SchemaIssueKind, SchemaNodeReport, StructuredDataSource};
+
+/// Nesting followed below a top-level node; deeper entities go unchecked.
+const MAX_DEPTH: usize = 8;
+
+/// Property expectations for a schema.org type. Each entry is met by any one
+/// of its properties, e.g. a Product needs `offers`, `review` or
+/// `aggregateRating`.
+struct TypeRules {
+    required: &'static [&'static [&'static str]],
+    recommended: &'static [&'static [&'static str]],
+}
+
+const ARTICLE: TypeRules = TypeRules {
+    required: &[&["headline"]],
+    recommended: &[
+        &["author"],
+        &["datePublished"],
+        &["dateModified"],
+        &["image"],
+        &["publisher"],
+    ],
+};
+
+const PRODUCT: TypeRules = TypeRules {
+    required: &[&["name"], &["offers", "review", "aggregateRating"]],
+    recommended: &[&["image"], &["description"], &["brand"], &["sku"]],
+};
+
+const OFFER: TypeRules = TypeRules {
+    required: &[
+        &["price", "priceSpecification"],
+        &["priceCurrency", "priceSpecification"],
+    ],
+    recommended: &[&["availability"], &["url"]],
+};
+
+const AGGREGATE_OFFER: TypeRules = TypeRules {
+    required: &[&["lowPrice"], &["priceCurrency"]],
+    recommended: &[&["highPrice"], &["offerCount"]],
+};
+
+const FAQ_PAGE: TypeRules = TypeRules {
+    required: &[&["mainEntity"]],
+    recommended: &[],
+};
+
+const QUESTION: TypeRules = TypeRules {
+    required: &[&["name"], &["acceptedAnswer"]],
+    recommended: &[],
+};
+
+const ANSWER: TypeRules = TypeRules {
+    required: &[&["text"]],
+    recommended: &[],
+};
+
+const HOW_TO: TypeRules = TypeRules {
+    required: &[&["name"], &["step"]],
+    recommended: &[&["image"], &["totalTime"], &["supply"], &["tool"]],
+};
+
+const HOW_TO_STEP: TypeRules = TypeRules {
+    required: &[&["text", "itemListElement"]],
+    recommended: &[&["name"], &["url"], &["image"]],
+};
+
+const LOCAL_BUSINESS: TypeRules = TypeRules {
+    required: &[&["name"], &["address"]],
+    recommended: &[
+        &["telephone"],
+        &["url"],
+        &["geo"],
+        &["openingHoursSpecification", "openingHours"],
+        &["priceRange"],
+        &["image"],
+    ],
+};
+
+const ORGANIZATION: TypeRules = TypeRules {
+    required: &[&["name"]],
+    recommended: &[&["url"], &["logo"], &["sameAs"]],
+};
+
+const BREADCRUMB_LIST: TypeRules = TypeRules {
+    required: &[&["itemListElement"]],
+    recommended: &[],
+};
+
+const LIST_ITEM: TypeRules = TypeRules {
+    required: &[&["position"], &["name", "item"]],
+    recommended: &[],
+};
+
+const REVIEW: TypeRules = TypeRules {
+    required: &[&["author"], &["reviewRating"]],
+    recommended: &[&["itemReviewed"], &["datePublished"]],
+};
+
+const RATING: TypeRules = TypeRules {
+    required: &[&["ratingValue"]],
+    recommended: &[&["bestRating"]],
+};
+
+const AGGREGATE_RATING: TypeRules = TypeRules {
+    required: &[&["ratingValue"], &["ratingCount", "reviewCount"]],
+    recommended: &[&["bestRating"]],
+};
+
+const EVENT: TypeRules = TypeRules {
+    required: &[&["name"], &["startDate"], &["location"]],
+    recommended: &[
+        &["endDate"],
+        &["eventStatus"],
+        &["description"],
+        &["image"],
+        &["offers"],
+        &["organizer"],
+        &["performer"],
+    ],
+};
+
+const PLACE: TypeRules = TypeRules {
+    required: &[&["address"]],
+    recommended: &[&["name"]],
+};
+
+const VIRTUAL_LOCATION: TypeRules = TypeRules {
+    required: &[&["url"]],
+    recommended: &[],
+};
+
+/// Rules for a type or one of its common subtypes; `None` for types we don't
+/// validate.
+fn rules_for(schema_type: &str) -> Option<&'static TypeRules> {
+    Some(match schema_type {
+        "Article" | "NewsArticle" | "BlogPosting" | "TechArticle" | "ScholarlyArticle"
+        | "Report" => &ARTICLE,
+        "Product" => &PRODUCT,
+        "Offer" => &OFFER,
+        "AggregateOffer" => &AGGREGATE_OFFER,
+        "FAQPage" => &FAQ_PAGE,
+        "Question" => &QUESTION,
+        "Answer" => &ANSWER,
+        "HowTo" => &HOW_TO,
+        "HowToStep" => &HOW_TO_STEP,
+        "LocalBusiness"
+        | "Restaurant"
+        | "Store"
+        | "Hotel"
+        | "Dentist"
+        | "MedicalClinic"
+        | "AutoDealer"
+        | "LegalService"
+        | "RealEstateAgent"
+        | "HomeAndConstructionBusiness"
+        | "FoodEstablishment"
+        | "ProfessionalService" => &LOCAL_BUSINESS,
+        "Organization" | "Corporation" | "NGO" | "EducationalOrganization" => &ORGANIZATION,
+        "BreadcrumbList" => &BREADCRUMB_LIST,
+        "ListItem" => &LIST_ITEM,
+        "Review" => &REVIEW,
+        "Rating" => &RATING,
+        "AggregateRating" => &AGGREGATE_RATING,
+        "Event" | "MusicEvent" | "BusinessEvent" | "SportsEvent" | "EducationEvent"
+        | "TheaterEvent" | "Festival" => &EVENT,
+        "Place" => &PLACE,
+        "VirtualLocation" => &VIRTUAL_LOCATION,
+        _ => return None,
+    })
+}
+
+/// `@type` values of a node, with `schema:` and `https://schema.org/`
+/// prefixes removed.
+fn node_types(node: &Map<String, Value>) -> Vec<String> {
+    let short = |t: &str| {
+        let t = t.rsplit('/').next().unwrap_or(t);
+        t.rsplit(':').next().unwrap_or(t).to_string()
+    };
+    match node.get("@type") {
+        Some(Value::String(t)) => vec![short(t)],
+        Some(Value::Array(items)) => items.iter().filter_map(|t| t.as_str()).map(short).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether a property carries something: not null, blank or an empty list.
+fn is_present(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::String(s)) => !s.trim().is_empty(),
+        Some(Value::Array(items)) => items.iter().any(|v| is_present(Some(v))),
+        Some(Value::Object(map)) => !map.is_empty(),
+        Some(_) => true,
+    }
+}
+
+fn is_numeric(value: &Value) -> bool {
+    match value {
+        Value::Number(_) => true,
+        Value::String(s) => s.trim().parse::<f64>().is_ok(),
+        _ => false,
+    }
+}
+
+/// ISO 8601 date, optionally followed by a time: `2024-05-01` or
+/// `2024-05-01T09:00:00+02:00`.
+fn is_iso_date(value: &str) -> bool {
+    let b = value.trim().as_bytes();
+    let digits = |r: std::ops::Range<usize>| b[r].iter().all(u8::is_ascii_digit);
+    if b.len() < 10 || !digits(0..4) || b[4] != b'-' || !digits(5..7) || b[7] != b'-' {
+        return false;
+    }
+    if !digits(8..10) || (b.len() > 10 && b[10] != b'T' && b[10] != b' ') {
+        return false;
+    }
+    let month = (b[5] - b'0') * 10 + (b[6] - b'0');
+    let day = (b[8] - b'0') * 10 + (b[9] - b'0');
+    (1..=12).contains(&month) && (1..=31).contains(&day)
+}
+
+/// Why a property's value is unusable, for the properties whose format we
+/// check.
+fn invalid_value(property: &str, value: &Value) -> Option<&'static str> {
+    // Null and blank values are reported as missing, not invalid.
+    let values: Vec<&Value> = match value {
+        Value::Array(items) => items.iter().collect(),
+        v => vec![v],
+    }
+    .into_iter()
+    .filter(|v| is_present(Some(v)))
+    .collect();
+    match property {
+        "price" | "lowPrice" | "highPrice" | "ratingValue" | "bestRating" | "worstRating"
+        | "ratingCount" | "reviewCount" => values
+            .iter()
+            .any(|v| !is_numeric(v))
+            .then_some("should be a number, without currency symbols or separators"),
+        "position" => values
+            .iter()
+            .any(|v| match v {
+                Value::Number(n) => n.as_u64().is_none(),
+                Value::String(s) => s.trim().parse::<u64>().is_err(),
+                _ => true,
+            })
+            .then_some("should be a whole number"),
+        "datePublished" | "dateModified" | "startDate" | "endDate" | "priceValidUntil" => values
+            .iter()
+            .any(|v| !v.as_str().is_some_and(is_iso_date))
+            .then_some("should be an ISO 8601 date"),
+        _ => None,
+    }
+}
+
+struct Validation {
+    errors: Vec<SchemaIssue>,
+    warnings: Vec<SchemaIssue>,
+    nested_types: Vec<String>,
+}
+
+impl Validation {
+    fn push(list: &mut Vec<SchemaIssue>, property: String, kind: SchemaIssueKind, message: String) {
+        if !list
+            .iter()
+            .any(|i| i.property == property && i.kind == kind)
+        {
+            list.push(SchemaIssue {
+                property,
+                kind,
+                message,
+            });
+        }
+    }
+
+    fn check(&mut self, node: &Map<String, Value>, types: &[String], path: &str, depth: usize) {
+        let at = |property: &str| {
+            if path.is_empty() {
+                property.to_string()
+            } else {
+                format!("{}.{}", path, property)
+            }
+        };
+
+        for schema_type in types {
+            let Some(rules) = rules_for(schema_type) else {
+                continue;
+            };
+            for (group, severity) in rules
+                .required
+                .iter()
+                .map(|g| (g, true))
+                .chain(rules.recommended.iter().map(|g| (g, false)))
+            {
+                if group.iter().any(|p| is_present(node.get(*p))) {
+                    continue;
+                }
+                let names = group
+                    .iter()
+                    .map(|p| format!("`{}`", p))
+                    .collect::<Vec<_>>()
+                    .join(" or ");
+                let (list, level) = if severity {
+                    (&mut self.errors, "required")
+                } else {
+                    (&mut self.warnings, "recommended")
+                };
+                Self::push(
+                    list,
+                    at(&group.join("|")),
+                    SchemaIssueKind::Missing,
+                    format!("{} is {} for {}", names, level, schema_type),
+                );
+            }
+        }
+
+        for (property, value) in node {
+            if property.starts_with('@') {
+                continue;
+            }
+            if let Some(reason) = invalid_value(property, value) {
+                Self::push(
+                    &mut self.errors,
+                    at(property),
+                    SchemaIssueKind::Invalid,
+                    format!("`{}` {}", property, reason),
+                );
+            }
+            if depth >= MAX_DEPTH {
+                continue;
+            }
+            let children: Vec<&Map<String, Value>> = match value {
+                Value::Object(map) => vec![map],
+                Value::Array(items) => items.iter().filter_map(|v| v.as_object()).collect(),
+                _ => Vec::new(),
+            };
+            for child in children {
+                let child_types = node_types(child);
+                for t in &child_types {
+                    if !self.nested_types.contains(t) {
+                        self.nested_types.push(t.clone());
+                    }
+                }
+                self.check(child, &child_types, &at(property), depth + 1);
+            }
+        }
+    }
+}
+
+/// Check one flattened structured data node (see `flatten_schema_nodes`)
+/// and the entities nested in it. A nested `{ "@id": ... }` reference has no
+/// type and so is not held to any rules.
+pub fn validate_node(node: &Value) -> SchemaNodeReport {
+    let empty = Map::new();
+    let map = node.as_object().unwrap_or(&empty);
+    let types = node_types(map);
+    let mut validation = Validation {
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        nested_types: Vec::new(),
+    };
+    if types.is_empty() {
+        validation.errors.push(SchemaIssue {
+            property: "@type".to_string(),
+            kind: SchemaIssueKind::Missing,
+            message: "node has no `@type`".to_string(),
+        });
+    }
+    validation.check(map, &types, "", 0);
+    SchemaNodeReport {
+        source: StructuredDataSource::default(),
+        types,
+        id: map.get("@id").and_then(|v| v.as_str()).map(str::to_string),
+        errors: validation.errors,
+        warnings: validation.warnings,
+        nested_types: validation.nested_types,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn properties(issues: &[SchemaIssue]) -> Vec<&str> {
+        issues.iter().map(|i| i.property.as_str()).collect()
+    }
+
+    #[test]
+    fn test_complete_product_passes() {
+        let report = validate_node(&json!({
+            "@context": "https://schema.org",
+            "@type": "Product",
+            "name": "Widget",
+            "image": "https://example.com/w.png",
+            "description": "A widget",
+            "brand": { "@type": "Brand", "name": "Acme" },
+            "sku": "W-1",
+            "offers": {
+                "@type": "Offer",
+                "price": "19.99",
+                "priceCurrency": "USD",
+                "availability": "https://schema.org/InStock",
+                "url": "https://example.com/w"
+            }
+        }));
+        assert_eq!(report.types, vec!["Product"]);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+        assert_eq!(report.nested_types, vec!["Brand", "Offer"]);
+    }
+
+    #[test]
+    fn test_missing_and_invalid_properties() {
+        let report = validate_node(&json!({
+            "@type": "schema:Product",
+            "name": "Widget",
+            "offers": [{ "@type": "Offer", "price": "$19.99" }]
+        }));
+        assert_eq!(
+            properties(&report.errors),
+            vec!["offers.priceCurrency|priceSpecification", "offers.price"]
+        );
+        assert_eq!(report.errors[1].kind, SchemaIssueKind::Invalid);
+        assert!(properties(&report.warnings).contains(&"image"));
+        assert!(properties(&report.warnings).contains(&"offers.availability"));
+
+        let product = validate_node(&json!({ "@type": "Product", "name": "Widget" }));
+        assert_eq!(
+            properties(&product.errors),
+            vec!["offers|review|aggregateRating"]
+        );
+    }
+
+    #[test]
+    fn test_faq_issues_are_reported_once_per_path() {
+        let report = validate_node(&json!({
+            "@type": "FAQPage",
+            "mainEntity": [
+                { "@type": "Question", "name": "One?" },
+                { "@type": "Question", "name": "Two?" },
+                {
+                    "@type": "Question",
+                    "name": "Three?",
+                    "acceptedAnswer": { "@type": "Answer", "text": " " }
+                }
+            ]
+        }));
+        assert_eq!(
+            properties(&report.errors),
+            vec![
+                "mainEntity.acceptedAnswer",
+                "mainEntity.acceptedAnswer.text"
+            ]
+        );
+        assert_eq!(report.nested_types, vec!["Question", "Answer"]);
+    }
+
+    #[test]
+    fn test_event_dates_and_untyped_nodes() {
+        let report = validate_node(&json!({
+            "@type": ["Event", "Thing"],
+            "name": "Launch",
+            "startDate": "May 1st",
+            "location": { "@type": "Place", "address": "1 Main St" }
+        }));
+        assert_eq!(properties(&report.errors), vec!["startDate"]);
+        assert!(is_iso_date("2024-05-01"));
+        assert!(is_iso_date("2024-05-01T09:00:00+02:00"));
+        assert!(!is_iso_date("2024-13-01"));
+
+        let untyped = validate_node(&json!({ "name": "Nothing" }));
+        assert!(untyped.types.is_empty());
+        assert_eq!(properties(&untyped.errors), vec!["@type"]);
+    }
+}
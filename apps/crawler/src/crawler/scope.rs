@@ -0,0 +1,187 @@
+use std::collections::HashSet;
+use url::Url;
+
+use crate::models::CrawlScope;
+
+/// `host` lowercased, without a trailing dot or a leading `www.`.
+pub fn normalize_host(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    match host.strip_prefix("www.") {
+        Some(rest) => rest.to_string(),
+        None => host,
+    }
+}
+
+/// The domain a host is registered under, per the Public Suffix List
+/// (private suffixes included): `blog.example.com` and `www.example.com`
+/// both give `example.com`, `shop.example.co.uk` gives `example.co.uk` and
+/// `alice.github.io` stays `alice.github.io`. IP addresses, single-label
+/// hosts and bare suffixes are returned as-is.
+pub fn registrable_domain(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[') {
+        return host;
+    }
+    psl::domain_str(&host).map_or_else(|| host.clone(), str::to_string)
+}
+
+/// `CrawlScope` resolved against the seed URLs: answers whether a host or URL
+/// belongs to the site being crawled.
+#[derive(Debug, Clone, Default)]
+pub struct ScopeMatcher {
+    /// Normalized hosts (see `normalize_host`) in scope.
+    hosts: HashSet<String>,
+    /// Registrable domains whose every subdomain is in scope.
+    domains: HashSet<String>,
+}
+
+impl ScopeMatcher {
+    pub fn new(scope: &CrawlScope, seed_urls: &[String]) -> Self {
+        let seed_hosts = seed_urls.iter().filter_map(|u| {
+            Url::parse(u)
+                .ok()
+                .and_then(|u| u.host_str().map(str::to_string))
+        });
+        let mut matcher = ScopeMatcher::default();
+        match scope {
+            CrawlScope::Host => matcher.hosts.extend(seed_hosts.map(|h| normalize_host(&h))),
+            CrawlScope::Domain => matcher
+                .domains
+                .extend(seed_hosts.map(|h| registrable_domain(&h))),
+            CrawlScope::Hosts { hosts } => matcher.hosts.extend(
+                seed_hosts
+                    .chain(hosts.iter().cloned())
+                    .map(|h| normalize_host(&h)),
+            ),
+        }
+        matcher
+    }
+
+    /// A scope of exactly one host, for checks made without a crawl config.
+    pub fn single_host(host: &str) -> Self {
+        ScopeMatcher {
+            hosts: HashSet::from([normalize_host(host)]),
+            domains: HashSet::new(),
+        }
+    }
+
+    pub fn contains_host(&self, host: &str) -> bool {
+        let host = normalize_host(host);
+        self.hosts.contains(&host)
+            || (!self.domains.is_empty() && self.domains.contains(&registrable_domain(&host)))
+    }
+
+    /// Whether `url` is an http(s) URL on an in-scope host.
+    pub fn contains(&self, url: &str) -> bool {
+        Url::parse(url).ok().is_some_and(|u| {
+            matches!(u.scheme(), "http" | "https")
+                && u.host_str().is_some_and(|h| self.contains_host(h))
+        })
+    }
+}
+
+/// The distinct hosts to discover up front (robots.txt, sitemaps, llms.txt),
+/// in seed order: every seed host, then any hosts listed in the scope. Hosts
+/// found later by following links are only known once crawled.
+pub fn known_hosts(scope: &CrawlScope, seed_urls: &[String]) -> Vec<String> {
+    let listed = match scope {
+        CrawlScope::Hosts { hosts } => hosts.as_slice(),
+        _ => &[],
+    };
+    let mut seen = HashSet::new();
+    seed_urls
+        .iter()
+        .filter_map(|u| {
+            Url::parse(u)
+                .ok()
+                .and_then(|u| u.host_str().map(str::to_ascii_lowercase))
+        })
+        .chain(
+            listed
+                .iter()
+                .map(|h| h.trim_end_matches('.').to_ascii_lowercase()),
+        )
+        .filter(|h| !h.is_empty() && seen.insert(normalize_host(h)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeds(urls: &[&str]) -> Vec<String> {
+        urls.iter().map(|u| u.to_string()).collect()
+    }
+
+    #[test]
+    fn test_registrable_domain() {
+        assert_eq!(registrable_domain("blog.example.com"), "example.com");
+        assert_eq!(registrable_domain("Example.com."), "example.com");
+        assert_eq!(
+            registrable_domain("a.b.shop.example.co.uk"),
+            "example.co.uk"
+        );
+        assert_eq!(registrable_domain("example.co.uk"), "example.co.uk");
+        assert_eq!(registrable_domain("localhost"), "localhost");
+        assert_eq!(registrable_domain("10.0.0.1"), "10.0.0.1");
+        assert_eq!(registrable_domain("shop.example.com.au"), "example.com.au");
+        assert_eq!(registrable_domain("www.example.ac.jp"), "example.ac.jp");
+    }
+
+    #[test]
+    fn test_private_suffixes_are_separate_sites() {
+        for host in [
+            "alice.github.io",
+            "my-app.vercel.app",
+            "site.netlify.app",
+            "demo.herokuapp.com",
+            "news.blogspot.com",
+            "bucket.s3.amazonaws.com",
+        ] {
+            assert_eq!(registrable_domain(&format!("www.{host}")), host);
+        }
+        assert_eq!(registrable_domain("github.io"), "github.io");
+
+        let scope = ScopeMatcher::new(&CrawlScope::Domain, &seeds(&["https://alice.github.io/"]));
+        assert!(scope.contains("https://docs.alice.github.io/"));
+        assert!(!scope.contains("https://bob.github.io/"));
+    }
+
+    #[test]
+    fn test_host_scope_treats_www_as_the_same_host() {
+        let scope = ScopeMatcher::new(&CrawlScope::Host, &seeds(&["https://example.com/"]));
+        assert!(scope.contains("https://www.example.com/about"));
+        assert!(scope.contains("http://EXAMPLE.com"));
+        assert!(!scope.contains("https://blog.example.com/"));
+        assert!(!scope.contains("mailto:hi@example.com"));
+    }
+
+    #[test]
+    fn test_domain_scope_covers_subdomains() {
+        let scope = ScopeMatcher::new(&CrawlScope::Domain, &seeds(&["https://www.example.com/"]));
+        assert!(scope.contains("https://blog.example.com/post"));
+        assert!(scope.contains("https://docs.example.com/"));
+        assert!(scope.contains("https://example.com/"));
+        assert!(!scope.contains("https://example.org/"));
+        assert!(!scope.contains("https://notexample.com/"));
+    }
+
+    #[test]
+    fn test_host_list_scope() {
+        let scope = CrawlScope::Hosts {
+            hosts: vec!["docs.example.com".into(), "help.example.net".into()],
+        };
+        let matcher = ScopeMatcher::new(&scope, &seeds(&["https://www.example.com/"]));
+        assert!(matcher.contains("https://example.com/"));
+        assert!(matcher.contains("https://help.example.net/faq"));
+        assert!(!matcher.contains("https://blog.example.com/"));
+
+        assert_eq!(
+            known_hosts(
+                &scope,
+                &seeds(&["https://www.example.com/", "https://example.com/pricing"])
+            ),
+            vec!["www.example.com", "docs.example.com", "help.example.net"]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+/// Words per shingle. Three-word shingles keep word order without making a
+/// single changed word flip too many features.
+const SHINGLE_WORDS: usize = 3;
+
+/// Below this many words a page is too thin to fingerprint; every short
+/// "Page not found" would otherwise cluster together.
+const MIN_WORDS: usize = 20;
+
+/// 64-bit SimHash of `text`'s word shingles, or `None` for thin content.
+/// Near-identical texts get fingerprints a few bits apart, so a timestamp or
+/// CSRF token in the page doesn't make it look unique.
+pub fn fingerprint(text: &str) -> Option<u64> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.len() < MIN_WORDS {
+        return None;
+    }
+
+    let mut weights = [0i32; 64];
+    for shingle in words.windows(SHINGLE_WORDS) {
+        let hash = fnv1a(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+    Some(
+        weights
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| **w > 0)
+            .fold(0u64, |acc, (bit, _)| acc | 1 << bit),
+    )
+}
+
+/// Share of matching bits between two fingerprints, from 0.0 to 1.0.
+pub fn similarity(a: u64, b: u64) -> f64 {
+    1.0 - f64::from((a ^ b).count_ones()) / 64.0
+}
+
+/// Most differing bits two fingerprints may have and still count as
+/// near-duplicates at `threshold` similarity.
+pub fn max_distance(threshold: f64) -> u32 {
+    ((1.0 - threshold.clamp(0.0, 1.0)) * 64.0).floor() as u32
+}
+
+/// FNV-1a over the shingle's words. Stable across builds, unlike
+/// `DefaultHasher`, so fingerprints from different crawls compare.
+fn fnv1a(words: &[String]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            hash ^= u64::from(b' ');
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        for byte in word.bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCATION_PAGE: &str = "Our Springfield clinic offers same-day appointments, \
+        walk-in care and on-site lab testing. Parking is free and the clinic is open \
+        seven days a week from eight in the morning until eight at night. Call ahead \
+        to reserve a time or book online through the patient portal.";
+
+    #[test]
+    fn test_small_edits_stay_close() {
+        let a = fingerprint(&format!("{LOCATION_PAGE} Updated 10:41:07")).unwrap();
+        let b = fingerprint(&format!("{LOCATION_PAGE} Updated 10:42:55")).unwrap();
+        assert!(similarity(a, b) >= 0.9, "{}", similarity(a, b));
+
+        let c = fingerprint(&LOCATION_PAGE.replace("Springfield", "Shelbyville")).unwrap();
+        assert!(similarity(a, c) >= 0.8, "{}", similarity(a, c));
+    }
+
+    #[test]
+    fn test_different_pages_are_far_apart() {
+        let a = fingerprint(LOCATION_PAGE).unwrap();
+        let b = fingerprint(
+            "Rust's ownership model guarantees memory safety without a garbage \
+             collector. Every value has a single owner, borrows are checked at compile \
+             time, and lifetimes describe how long references stay valid across calls.",
+        )
+        .unwrap();
+        assert!(similarity(a, b) < 0.8, "{}", similarity(a, b));
+    }
+
+    #[test]
+    fn test_thin_content_has_no_fingerprint() {
+        assert_eq!(fingerprint("Page not found. Go back home."), None);
+    }
+
+    #[test]
+    fn test_max_distance() {
+        assert_eq!(max_distance(1.0), 0);
+        assert_eq!(max_distance(0.95), 3);
+        assert_eq!(max_distance(0.9), 6);
+    }
+}
@@ -1,177 +1,642 @@
+use flate2::read::GzDecoder;
 use futures::stream::{FuturesUnordered, StreamExt};
-use regex::Regex;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use url::Url;
 
-use crate::models::DEFAULT_USER_AGENT;
+use super::egress::EgressPolicy;
+use crate::models::{SitemapAnalysis, DEFAULT_USER_AGENT};
+
+/// Most `<url>` (or `<sitemap>`) entries one file may list (sitemaps.org).
+pub const MAX_SITEMAP_ENTRIES: usize = 50_000;
+
+/// Largest uncompressed sitemap the protocol allows: 50 MiB.
+pub const MAX_SITEMAP_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Longest `<loc>` the protocol allows.
+const MAX_LOC_LEN: usize = 2048;
+
+/// A `lastmod` older than this marks the URL stale.
+const STALE_AFTER: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+const CHANGEFREQ_VALUES: &[&str] = &[
+    "always", "hourly", "daily", "weekly", "monthly", "yearly", "never",
+];
+
+/// An `xhtml:link rel="alternate"` hreflang entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapAlternate {
+    pub hreflang: String,
+    pub href: String,
+}
+
+/// One `<url>` of a urlset, or one `<sitemap>` of an index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SitemapEntry {
+    pub loc: String,
+    pub lastmod: Option<SystemTime>,
+    pub changefreq: Option<String>,
+    /// 0.0–1.0; the protocol default is 0.5.
+    pub priority: Option<f32>,
+    pub images: u32,
+    pub videos: u32,
+    pub news: bool,
+    pub alternates: Vec<SitemapAlternate>,
+}
+
+impl SitemapEntry {
+    /// Frontier priority for a sitemap URL: 70–90, below seeds (100) and above
+    /// links found while crawling (50). The sitemap's own `priority` spreads
+    /// URLs over 70–80, and a recent `lastmod` adds up to 10 more.
+    pub fn frontier_priority(&self, now: SystemTime) -> u32 {
+        let priority = self.priority.unwrap_or(0.5).clamp(0.0, 1.0);
+        let base = 70 + (priority * 10.0).round() as u32;
+        let recency = match self.lastmod.and_then(|t| now.duration_since(t).ok()) {
+            Some(age) if age <= Duration::from_secs(30 * 24 * 60 * 60) => 10,
+            Some(age) if age <= STALE_AFTER => 5,
+            // Future dates count as fresh.
+            None if self.lastmod.is_some() => 10,
+            _ => 0,
+        };
+        base + recency
+    }
+
+    fn is_stale(&self, now: SystemTime) -> bool {
+        self.lastmod
+            .and_then(|t| now.duration_since(t).ok())
+            .is_some_and(|age| age > STALE_AFTER)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SitemapKind {
+    UrlSet,
+    Index,
+    #[default]
+    Unknown,
+}
+
+/// A single sitemap file, parsed.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedSitemap {
+    pub kind: SitemapKind,
+    pub entries: Vec<SitemapEntry>,
+    /// Protocol violations found in this file.
+    pub issues: Vec<String>,
+}
+
+impl ParsedSitemap {
+    fn issue(&mut self, code: &str) {
+        if !self.issues.iter().any(|c| c == code) {
+            self.issues.push(code.to_string());
+        }
+    }
+}
+
+/// Parse a urlset or sitemapindex, gunzipping it first if it starts with the
+/// gzip magic bytes. The document is streamed, so only the entries are held
+/// in memory; reading stops past `MAX_SITEMAP_BYTES` of uncompressed XML.
+pub fn parse_sitemap(body: &[u8]) -> ParsedSitemap {
+    let limit = MAX_SITEMAP_BYTES + 1;
+    if body.starts_with(&[0x1f, 0x8b]) {
+        parse_xml(BufReader::new(GzDecoder::new(body).take(limit)))
+    } else {
+        parse_xml(body.take(limit))
+    }
+}
+
+fn parse_xml<R: BufRead>(input: R) -> ParsedSitemap {
+    let mut reader = Reader::from_reader(input);
+    reader.config_mut().trim_text(true);
+
+    let mut out = ParsedSitemap::default();
+    let mut buf = Vec::new();
+    // Local names of the open elements, root first.
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut entry: Option<SitemapEntry> = None;
+    let mut text = String::new();
+
+    loop {
+        let event = match reader.read_event_into(&mut buf) {
+            Ok(event) => event,
+            Err(_) => {
+                out.issue(truncated_or_malformed(&reader));
+                break;
+            }
+        };
+        match event {
+            Event::Start(e) => {
+                let name = e.local_name().as_ref().to_vec();
+                open_element(&mut out, &mut entry, &path, &name, &e);
+                path.push(name);
+                text.clear();
+            }
+            // A self-closed `<url/>` has no `<loc>`; only extensions and the
+            // root are meaningful when empty.
+            Event::Empty(e) if path.len() != 1 => {
+                let name = e.local_name().as_ref().to_vec();
+                open_element(&mut out, &mut entry, &path, &name, &e);
+            }
+            Event::Text(t) => match t.unescape() {
+                Ok(s) => text.push_str(&s),
+                Err(_) => out.issue("malformed_xml"),
+            },
+            Event::CData(c) => text.push_str(&String::from_utf8_lossy(&c)),
+            Event::End(_) => {
+                let name = path.pop().unwrap_or_default();
+                // Fields sit directly under `<url>`/`<sitemap>`: root, entry, field.
+                if path.len() == 2 {
+                    if let Some(current) = entry.as_mut() {
+                        close_field(&mut out.issues, current, &name, text.trim());
+                    }
+                } else if path.len() == 1 {
+                    if let Some(done) = entry.take() {
+                        if is_valid_loc(&done.loc) {
+                            out.entries.push(done);
+                        } else {
+                            out.issue(if done.loc.len() > MAX_LOC_LEN {
+                                "loc_too_long"
+                            } else {
+                                "invalid_loc"
+                            });
+                        }
+                    }
+                }
+                text.clear();
+            }
+            Event::Eof => {
+                if out.kind != SitemapKind::Unknown && !path.is_empty() {
+                    out.issue(truncated_or_malformed(&reader));
+                }
+                break;
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if out.kind == SitemapKind::Unknown && !out.issues.iter().any(|i| i == "malformed_xml") {
+        out.issue("unknown_root");
+    }
+    if out.entries.len() > MAX_SITEMAP_ENTRIES {
+        out.issue(match out.kind {
+            SitemapKind::Index => "too_many_sitemaps",
+            _ => "too_many_urls",
+        });
+    }
+    out
+}
+
+/// A document that stops early was either cut off at the size limit or is
+/// broken XML.
+fn truncated_or_malformed<R>(reader: &Reader<R>) -> &'static str {
+    if reader.buffer_position() > MAX_SITEMAP_BYTES {
+        "file_too_large"
+    } else {
+        "malformed_xml"
+    }
+}
+
+fn open_element(
+    out: &mut ParsedSitemap,
+    entry: &mut Option<SitemapEntry>,
+    path: &[Vec<u8>],
+    name: &[u8],
+    element: &BytesStart,
+) {
+    match (path.len(), name) {
+        (0, b"urlset") => out.kind = SitemapKind::UrlSet,
+        (0, b"sitemapindex") => out.kind = SitemapKind::Index,
+        (1, b"url") if out.kind == SitemapKind::UrlSet => *entry = Some(SitemapEntry::default()),
+        (1, b"sitemap") if out.kind == SitemapKind::Index => *entry = Some(SitemapEntry::default()),
+        // Extensions, directly under `<url>`.
+        (2, _) => {
+            let Some(current) = entry.as_mut() else {
+                return;
+            };
+            match name {
+                b"image" => current.images += 1,
+                b"video" => current.videos += 1,
+                b"news" => current.news = true,
+                b"link" => {
+                    if let Some(alt) = alternate(element) {
+                        current.alternates.push(alt);
+                    }
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `<xhtml:link rel="alternate" hreflang="…" href="…"/>`.
+fn alternate(element: &BytesStart) -> Option<SitemapAlternate> {
+    let (mut rel, mut hreflang, mut href) = (None, None, None);
+    for attr in element.attributes().flatten() {
+        let value = attr.unescape_value().ok()?.into_owned();
+        match attr.key.local_name().as_ref() {
+            b"rel" => rel = Some(value),
+            b"hreflang" => hreflang = Some(value),
+            b"href" => href = Some(value),
+            _ => {}
+        }
+    }
+    if !rel?.eq_ignore_ascii_case("alternate") {
+        return None;
+    }
+    Some(SitemapAlternate {
+        hreflang: hreflang?,
+        href: href?,
+    })
+}
+
+fn close_field(issues: &mut Vec<String>, entry: &mut SitemapEntry, name: &[u8], value: &str) {
+    let mut issue = |code: &str| {
+        if !issues.iter().any(|c| c == code) {
+            issues.push(code.to_string());
+        }
+    };
+    match name {
+        b"loc" => entry.loc = value.to_string(),
+        b"lastmod" => match parse_w3c_datetime(value) {
+            Some(t) => entry.lastmod = Some(t),
+            None => issue("invalid_lastmod"),
+        },
+        b"changefreq" => {
+            let freq = value.to_ascii_lowercase();
+            if CHANGEFREQ_VALUES.contains(&freq.as_str()) {
+                entry.changefreq = Some(freq);
+            } else {
+                issue("invalid_changefreq");
+            }
+        }
+        b"priority" => match value.parse::<f32>() {
+            Ok(p) if (0.0..=1.0).contains(&p) => entry.priority = Some(p),
+            _ => issue("invalid_priority"),
+        },
+        _ => {}
+    }
+}
+
+fn is_valid_loc(loc: &str) -> bool {
+    loc.len() <= MAX_LOC_LEN
+        && Url::parse(loc).is_ok_and(|u| matches!(u.scheme(), "http" | "https"))
+}
+
+/// Parse a W3C Datetime (`YYYY`, `YYYY-MM`, `YYYY-MM-DD`, or a full date
+/// with `Thh:mm[:ss[.s]]` and a `Z`/`±hh:mm` offset). A missing offset is
+/// read as UTC, since plenty of sitemaps leave it off.
+pub fn parse_w3c_datetime(value: &str) -> Option<SystemTime> {
+    let value = value.trim();
+    let (date, time) = match value.split_once('T') {
+        Some((d, t)) => (d, Some(t)),
+        None => (value, None),
+    };
+
+    let mut parts = date.split('-');
+    let year_str = parts.next()?;
+    if year_str.len() != 4 {
+        return None;
+    }
+    let year: i64 = year_str.parse().ok()?;
+    let field = |s: Option<&str>| -> Option<Option<u32>> {
+        match s {
+            None => Some(None),
+            Some(s) if s.len() == 2 => s.parse().ok().map(Some),
+            Some(_) => None,
+        }
+    };
+    let month = field(parts.next())?;
+    let day = field(parts.next())?;
+    // A time needs a full date.
+    if parts.next().is_some() || (time.is_some() && day.is_none()) {
+        return None;
+    }
+    let (month, day) = (month.unwrap_or(1), day.unwrap_or(1));
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let mut secs = days_from_civil(year, month, day) * 86_400;
+    if let Some(time) = time {
+        let (clock, offset_secs) = split_offset(time)?;
+        let mut hms = clock.split(':');
+        let hour: i64 = hms.next()?.parse().ok()?;
+        let minute: i64 = hms.next()?.parse().ok()?;
+        let second: f64 = match hms.next() {
+            Some(s) => s.parse().ok()?,
+            None => 0.0,
+        };
+        if hms.next().is_some() || hour > 23 || minute > 59 || !(0.0..61.0).contains(&second) {
+            return None;
+        }
+        secs += hour * 3600 + minute * 60 + second as i64 - offset_secs;
+    }
+
+    u64::try_from(secs)
+        .ok()
+        .map(|s| UNIX_EPOCH + Duration::from_secs(s))
+}
+
+/// Split `hh:mm:ssZ` / `hh:mm+02:00` / `hh:mm-0500` into the clock and the
+/// offset east of UTC in seconds.
+fn split_offset(time: &str) -> Option<(&str, i64)> {
+    if let Some(clock) = time.strip_suffix('Z').or_else(|| time.strip_suffix('z')) {
+        return Some((clock, 0));
+    }
+    let Some(idx) = time.rfind(['+', '-']) else {
+        return Some((time, 0));
+    };
+    let (clock, offset) = time.split_at(idx);
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+    // Checked before slicing: `digits[..2]` would split a multi-byte char.
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = digits[2..].parse().ok()?;
+    Some((clock, sign * (hours * 3600 + minutes * 60)))
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's
+/// `days_from_civil`).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
 
 /// Result of fetching and parsing sitemaps for a domain.
 #[derive(Debug, Clone)]
 pub struct SitemapResult {
-    /// All discovered URLs from the sitemap(s).
-    pub urls: Vec<String>,
+    /// Deduplicated same-domain entries from the sitemap(s).
+    pub entries: Vec<SitemapEntry>,
     /// Total number of URLs found before filtering.
     pub total_count: u32,
+    /// Sitemap files fetched and parsed, indexes included.
+    pub sitemap_count: u32,
+    /// Protocol violations across every parsed file.
+    pub issues: Vec<String>,
+    /// Across all URLs, before domain filtering.
+    urls_with_lastmod: u32,
+    stale_count: u32,
+    image_count: u32,
+    video_count: u32,
+    news_count: u32,
+    alternate_count: u32,
+}
+
+impl SitemapResult {
+    fn empty() -> Self {
+        SitemapResult {
+            entries: Vec::new(),
+            total_count: 0,
+            sitemap_count: 0,
+            issues: Vec::new(),
+            urls_with_lastmod: 0,
+            stale_count: 0,
+            image_count: 0,
+            video_count: 0,
+            news_count: 0,
+            alternate_count: 0,
+        }
+    }
+
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|e| e.loc.as_str())
+    }
+
+    /// The site-level summary the scorer reads. `None` when no sitemap could
+    /// be fetched at all.
+    pub fn analysis(&self) -> Option<SitemapAnalysis> {
+        if self.sitemap_count == 0 {
+            return None;
+        }
+        Some(SitemapAnalysis {
+            is_valid: self.issues.is_empty(),
+            url_count: self.total_count,
+            stale_url_count: self.stale_count,
+            discovered_page_count: self.entries.len() as u32,
+            sitemap_count: self.sitemap_count,
+            urls_with_lastmod: self.urls_with_lastmod,
+            image_count: self.image_count,
+            video_count: self.video_count,
+            news_count: self.news_count,
+            alternate_count: self.alternate_count,
+            issues: self.issues.clone(),
+        })
+    }
+
+    fn record_file(&mut self, parsed: &ParsedSitemap) {
+        self.sitemap_count += 1;
+        for issue in &parsed.issues {
+            if !self.issues.contains(issue) {
+                self.issues.push(issue.clone());
+            }
+        }
+    }
+
+    fn add_urls(&mut self, entries: Vec<SitemapEntry>, now: SystemTime) {
+        for entry in &entries {
+            self.total_count += 1;
+            self.urls_with_lastmod += u32::from(entry.lastmod.is_some());
+            self.stale_count += u32::from(entry.is_stale(now));
+            self.image_count += entry.images;
+            self.video_count += entry.videos;
+            self.news_count += u32::from(entry.news);
+            self.alternate_count += entry.alternates.len() as u32;
+        }
+        self.entries.extend(entries);
+    }
 }
 
 /// Fetch and parse sitemaps from the given URLs (typically from robots.txt).
-/// Returns deduplicated URLs filtered to the same domain as `seed_domain`.
+/// Returns deduplicated entries filtered to the same domain as `seed_domain`.
 ///
-/// Handles both `<urlset>` (standard) and `<sitemapindex>` (index) formats.
-/// For sitemap indexes, fetches up to `max_child_sitemaps` child sitemaps and recurses
-/// up to `max_depth` levels deep into nested sitemap indexes.
+/// Handles both `<urlset>` (standard) and `<sitemapindex>` (index) formats,
+/// plain or gzipped. For sitemap indexes, fetches up to `max_child_sitemaps`
+/// child sitemaps and recurses up to `max_depth` levels deep into nested
+/// sitemap indexes.
 pub async fn fetch_sitemap_urls(
     sitemap_urls: &[String],
     seed_domain: &str,
     max_child_sitemaps: usize,
     max_depth: usize,
+    egress: &EgressPolicy,
 ) -> SitemapResult {
-    let client = match reqwest::Client::builder()
+    let client = match egress
+        .client_builder()
         .timeout(std::time::Duration::from_secs(15))
         .user_agent(DEFAULT_USER_AGENT)
         .build()
     {
-        Ok(c) => c,
-        Err(_) => {
-            return SitemapResult {
-                urls: vec![],
-                total_count: 0,
-            }
-        }
+        Ok(http) => SitemapClient {
+            http,
+            egress: egress.clone(),
+        },
+        Err(_) => return SitemapResult::empty(),
     };
 
-    let mut all_urls: Vec<String> = Vec::new();
-    let loc_re = Regex::new(r"<loc>\s*(.*?)\s*</loc>").expect("valid regex");
+    let now = SystemTime::now();
+    let mut result = SitemapResult::empty();
 
     // Fetch all top-level sitemaps concurrently
     let mut top_futures: FuturesUnordered<_> = sitemap_urls
         .iter()
         .map(|url| {
-            let client = client.clone();
+            let client = &client;
             let url = url.clone();
-            async move { (fetch_xml(&client, &url).await, url) }
+            async move { (client.fetch_sitemap(&url).await, url) }
         })
         .collect();
 
-    while let Some((xml_opt, _sitemap_url)) = top_futures.next().await {
-        let xml = match xml_opt {
-            Some(xml) => xml,
-            None => continue,
+    while let Some((parsed, _sitemap_url)) = top_futures.next().await {
+        let Some(parsed) = parsed else {
+            continue;
         };
-
         fetch_sitemap_recursive(
             &client,
-            &loc_re,
-            &xml,
+            parsed,
             max_child_sitemaps,
             max_depth,
             0,
-            &mut all_urls,
+            now,
+            &mut result,
         )
         .await;
     }
 
-    let total_count = all_urls.len() as u32;
-
     // Filter to same domain and deduplicate
     let seed_domain_lower = seed_domain.to_lowercase();
-    let mut seen = std::collections::HashSet::new();
-    let filtered: Vec<String> = all_urls
+    let mut seen = HashSet::new();
+    let entries = std::mem::take(&mut result.entries);
+    result.entries = entries
         .into_iter()
-        .filter(|url| {
-            Url::parse(url)
+        .filter(|entry| {
+            Url::parse(&entry.loc)
                 .ok()
                 .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
                 .map(|h| h == seed_domain_lower || h == format!("www.{}", seed_domain_lower))
                 .unwrap_or(false)
         })
-        .filter(|url| seen.insert(url.clone()))
+        .filter(|entry| seen.insert(entry.loc.clone()))
         .collect();
 
-    SitemapResult {
-        urls: filtered,
-        total_count,
-    }
+    result
 }
 
-/// Process a single sitemap XML document, recursing into nested sitemap indexes.
+/// Record a parsed sitemap, recursing into nested sitemap indexes.
 /// `current_depth` tracks how many levels deep we are (0 = top level).
 #[async_recursion::async_recursion]
 async fn fetch_sitemap_recursive(
-    client: &reqwest::Client,
-    loc_re: &Regex,
-    xml: &str,
+    client: &SitemapClient,
+    parsed: ParsedSitemap,
     max_child_sitemaps: usize,
     max_depth: usize,
     current_depth: usize,
-    out: &mut Vec<String>,
+    now: SystemTime,
+    out: &mut SitemapResult,
 ) {
-    if xml.contains("<sitemapindex") {
-        // Sitemap index — extract child sitemap URLs and fetch them concurrently
-        let child_urls: Vec<String> = loc_re
-            .captures_iter(xml)
-            .filter_map(|cap| cap.get(1).map(|m| m.as_str().to_string()))
-            .take(max_child_sitemaps)
-            .collect();
-
-        let mut child_futures: FuturesUnordered<_> = child_urls
-            .iter()
-            .map(|url| {
-                let client = client.clone();
-                let url = url.clone();
-                async move { (fetch_xml(&client, &url).await, url) }
-            })
-            .collect();
-
-        while let Some((result, _url)) = child_futures.next().await {
-            if let Some(child_xml) = result {
-                if child_xml.contains("<sitemapindex") && current_depth < max_depth {
-                    // Recurse into nested sitemap index
-                    fetch_sitemap_recursive(
-                        client,
-                        loc_re,
-                        &child_xml,
-                        max_child_sitemaps,
-                        max_depth,
-                        current_depth + 1,
-                        out,
-                    )
-                    .await;
-                } else {
-                    extract_locs(loc_re, &child_xml, out);
-                }
-            }
+    out.record_file(&parsed);
+    if parsed.kind != SitemapKind::Index {
+        out.add_urls(parsed.entries, now);
+        return;
+    }
+
+    // Sitemap index — fetch child sitemaps concurrently
+    let mut child_futures: FuturesUnordered<_> = parsed
+        .entries
+        .iter()
+        .take(max_child_sitemaps)
+        .map(|child| {
+            let url = child.loc.clone();
+            async move { (client.fetch_sitemap(&url).await, url) }
+        })
+        .collect();
+
+    while let Some((result, _url)) = child_futures.next().await {
+        let Some(child) = result else {
+            continue;
+        };
+        if child.kind == SitemapKind::Index && current_depth >= max_depth {
+            // Too deep to follow; its children are skipped.
+            out.record_file(&child);
+            continue;
         }
-    } else {
-        // Standard sitemap — extract URLs directly
-        extract_locs(loc_re, xml, out);
+        fetch_sitemap_recursive(
+            client,
+            child,
+            max_child_sitemaps,
+            max_depth,
+            current_depth + 1,
+            now,
+            out,
+        )
+        .await;
     }
 }
 
-/// Fetch XML content from a URL. Returns None on any error.
-async fn fetch_xml(client: &reqwest::Client, url: &str) -> Option<String> {
-    let resp = match client.get(url).send().await {
-        Ok(r) => r,
-        Err(e) => {
-            tracing::warn!(url = %url, error = %e, "Failed to fetch sitemap");
+/// HTTP client for sitemap fetches. Sitemap `<loc>`s are site-controlled, so
+/// each URL goes through the egress policy before it is requested.
+struct SitemapClient {
+    http: reqwest::Client,
+    egress: EgressPolicy,
+}
+
+impl SitemapClient {
+    /// Fetch and parse a sitemap. Returns None on any fetch error.
+    async fn fetch_sitemap(&self, url: &str) -> Option<ParsedSitemap> {
+        if let Err(e) = self.egress.check_url(url) {
+            tracing::warn!(url = %url, error = %e, "Sitemap URL refused by egress policy");
+            return None;
+        }
+        let mut resp = match self.http.get(url).send().await {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::warn!(url = %url, error = %e, "Failed to fetch sitemap");
+                return None;
+            }
+        };
+        if !resp.status().is_success() {
+            tracing::warn!(url = %url, status = %resp.status(), "Sitemap fetch returned non-200");
             return None;
         }
-    };
-    if !resp.status().is_success() {
-        tracing::warn!(url = %url, status = %resp.status(), "Sitemap fetch returned non-200");
-        return None;
-    }
-    resp.text().await.ok()
-}
 
-/// Extract all `<loc>` values from XML into the output vector.
-fn extract_locs(re: &Regex, xml: &str, out: &mut Vec<String>) {
-    for cap in re.captures_iter(xml) {
-        if let Some(m) = cap.get(1) {
-            let url = m.as_str().trim();
-            if !url.is_empty() {
-                out.push(url.to_string());
+        // A body past the limit is over it uncompressed too; stop reading there.
+        let mut body: Vec<u8> = Vec::new();
+        let mut too_large = false;
+        while let Some(chunk) = resp.chunk().await.ok()? {
+            body.extend_from_slice(&chunk);
+            if body.len() as u64 > MAX_SITEMAP_BYTES {
+                too_large = true;
+                break;
             }
         }
+
+        let mut parsed = parse_sitemap(&body);
+        if too_large {
+            parsed.issues.retain(|i| i != "malformed_xml");
+            parsed.issue("file_too_large");
+        }
+        Some(parsed)
     }
 }
 
@@ -179,17 +644,23 @@ fn extract_locs(re: &Regex, xml: &str, out: &mut Vec<String>) {
 mod tests {
     use super::*;
 
+    fn locs(xml: &str) -> Vec<String> {
+        parse_sitemap(xml.as_bytes())
+            .entries
+            .into_iter()
+            .map(|e| e.loc)
+            .collect()
+    }
+
     #[test]
-    fn test_extract_locs_standard_sitemap() {
-        let re = Regex::new(r"<loc>\s*(.*?)\s*</loc>").unwrap();
+    fn test_parse_standard_sitemap() {
         let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
 <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
   <url><loc>https://example.com/</loc></url>
   <url><loc>https://example.com/about</loc></url>
   <url><loc>https://example.com/blog</loc></url>
 </urlset>"#;
-        let mut urls = Vec::new();
-        extract_locs(&re, xml, &mut urls);
+        let urls = locs(xml);
         assert_eq!(urls.len(), 3);
         assert_eq!(urls[0], "https://example.com/");
         assert_eq!(urls[1], "https://example.com/about");
@@ -197,33 +668,43 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_locs_empty() {
-        let re = Regex::new(r"<loc>\s*(.*?)\s*</loc>").unwrap();
-        let mut urls = Vec::new();
-        extract_locs(&re, "<urlset></urlset>", &mut urls);
-        assert!(urls.is_empty());
+    fn test_parse_empty_urlset() {
+        let parsed = parse_sitemap(b"<urlset></urlset>");
+        assert_eq!(parsed.kind, SitemapKind::UrlSet);
+        assert!(parsed.entries.is_empty());
+        assert!(parsed.issues.is_empty());
     }
 
     #[test]
-    fn test_extract_locs_invalid_xml() {
-        let re = Regex::new(r"<loc>\s*(.*?)\s*</loc>").unwrap();
-        let mut urls = Vec::new();
-        extract_locs(&re, "this is not xml at all", &mut urls);
-        assert!(urls.is_empty());
+    fn test_parse_invalid_xml() {
+        let parsed = parse_sitemap(b"this is not xml at all");
+        assert!(parsed.entries.is_empty());
+        assert_eq!(parsed.issues, vec!["unknown_root"]);
     }
 
     #[test]
-    fn test_extract_locs_with_whitespace() {
-        let re = Regex::new(r"<loc>\s*(.*?)\s*</loc>").unwrap();
+    fn test_parse_loc_with_whitespace() {
         let xml = r#"<urlset>
   <url><loc>
     https://example.com/page
   </loc></url>
 </urlset>"#;
-        let mut urls = Vec::new();
-        extract_locs(&re, xml, &mut urls);
-        assert_eq!(urls.len(), 1);
-        assert_eq!(urls[0], "https://example.com/page");
+        assert_eq!(locs(xml), vec!["https://example.com/page"]);
+    }
+
+    #[test]
+    fn test_parse_entities_and_cdata() {
+        let xml = r#"<urlset>
+  <url><loc>https://example.com/?a=1&amp;b=2</loc></url>
+  <url><loc><![CDATA[https://example.com/?c=3&d=4]]></loc></url>
+</urlset>"#;
+        assert_eq!(
+            locs(xml),
+            vec![
+                "https://example.com/?a=1&b=2",
+                "https://example.com/?c=3&d=4"
+            ]
+        );
     }
 
     #[tokio::test]
@@ -234,20 +715,222 @@ mod tests {
             "example.com",
             5,
             3,
+            &EgressPolicy::default(),
         )
         .await;
         // Should return empty since the URL doesn't exist
-        assert!(result.urls.is_empty());
+        assert!(result.entries.is_empty());
         assert_eq!(result.total_count, 0);
+        assert!(result.analysis().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_follows_index_into_gzipped_child() {
+        use axum::{routing::get, Router};
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let index = format!(
+            "<sitemapindex><sitemap><loc>{base}/pages.xml.gz</loc></sitemap>\
+             <sitemap><loc>{base}/missing.xml</loc></sitemap></sitemapindex>"
+        );
+        let mut gz = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        write!(
+            gz,
+            "<urlset><url><loc>{base}/a</loc><lastmod>2001-01-01</lastmod></url>\
+             <url><loc>{base}/b</loc></url><url><loc>https://other.example/c</loc></url></urlset>"
+        )
+        .unwrap();
+        let pages = gz.finish().unwrap();
+        let app = Router::new()
+            .route("/sitemap.xml", get(move || async move { index }))
+            .route("/pages.xml.gz", get(move || async move { pages }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let result = fetch_sitemap_urls(
+            &[format!("{base}/sitemap.xml")],
+            "127.0.0.1",
+            5,
+            3,
+            &EgressPolicy::new(vec!["127.0.0.1".parse().unwrap()]),
+        )
+        .await;
+        assert_eq!(
+            result.urls().collect::<Vec<_>>(),
+            vec![format!("{base}/a"), format!("{base}/b")]
+        );
+        let analysis = result.analysis().unwrap();
+        assert!(analysis.is_valid);
+        assert_eq!(analysis.sitemap_count, 2);
+        assert_eq!(analysis.url_count, 3);
+        assert_eq!(analysis.discovered_page_count, 2);
+        assert_eq!(analysis.urls_with_lastmod, 1);
+        assert_eq!(analysis.stale_url_count, 1);
     }
 
     #[test]
     fn test_detect_sitemap_index() {
         let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
 <sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
-  <sitemap><loc>https://example.com/sitemap-1.xml</loc></sitemap>
+  <sitemap><loc>https://example.com/sitemap-1.xml</loc><lastmod>2024-01-01</lastmod></sitemap>
   <sitemap><loc>https://example.com/sitemap-2.xml</loc></sitemap>
 </sitemapindex>"#;
-        assert!(xml.contains("<sitemapindex"));
+        let parsed = parse_sitemap(xml.as_bytes());
+        assert_eq!(parsed.kind, SitemapKind::Index);
+        assert_eq!(parsed.entries.len(), 2);
+        assert!(parsed.entries[0].lastmod.is_some());
+    }
+
+    #[test]
+    fn test_parse_fields_and_extensions() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+        xmlns:image="http://www.google.com/schemas/sitemap-image/1.1"
+        xmlns:video="http://www.google.com/schemas/sitemap-video/1.1"
+        xmlns:news="http://www.google.com/schemas/sitemap-news/0.9"
+        xmlns:xhtml="http://www.w3.org/1999/xhtml">
+  <url>
+    <loc>https://example.com/en/</loc>
+    <lastmod>2024-05-01T10:00:00+02:00</lastmod>
+    <changefreq>Weekly</changefreq>
+    <priority>0.8</priority>
+    <image:image><image:loc>https://example.com/a.png</image:loc></image:image>
+    <image:image><image:loc>https://example.com/b.png</image:loc></image:image>
+    <video:video><video:title>Intro</video:title></video:video>
+    <news:news><news:title>Launch</news:title></news:news>
+    <xhtml:link rel="alternate" hreflang="de" href="https://example.com/de/"/>
+    <xhtml:link rel="alternate" hreflang="en" href="https://example.com/en/"/>
+  </url>
+</urlset>"#;
+        let parsed = parse_sitemap(xml.as_bytes());
+        assert!(parsed.issues.is_empty(), "{:?}", parsed.issues);
+        let entry = &parsed.entries[0];
+        // The image's own <loc> must not overwrite the page's.
+        assert_eq!(entry.loc, "https://example.com/en/");
+        assert_eq!(entry.lastmod, parse_w3c_datetime("2024-05-01T08:00:00Z"));
+        assert_eq!(entry.changefreq.as_deref(), Some("weekly"));
+        assert_eq!(entry.priority, Some(0.8));
+        assert_eq!(entry.images, 2);
+        assert_eq!(entry.videos, 1);
+        assert!(entry.news);
+        assert_eq!(
+            entry.alternates[0],
+            SitemapAlternate {
+                hreflang: "de".into(),
+                href: "https://example.com/de/".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_flags_invalid_fields() {
+        let xml = r#"<urlset>
+  <url><loc>/relative</loc></url>
+  <url><loc>https://example.com/a</loc><lastmod>yesterday</lastmod>
+    <priority>2.0</priority><changefreq>sometimes</changefreq></url>
+</urlset>"#;
+        let parsed = parse_sitemap(xml.as_bytes());
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(
+            parsed.issues,
+            vec![
+                "invalid_loc",
+                "invalid_lastmod",
+                "invalid_priority",
+                "invalid_changefreq"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flags_truncated_document() {
+        let parsed = parse_sitemap(b"<urlset><url><loc>https://example.com/</loc></url>");
+        assert_eq!(parsed.issues, vec!["malformed_xml"]);
+    }
+
+    #[test]
+    fn test_parses_gzipped_sitemap() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let mut gz = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(b"<urlset><url><loc>https://example.com/gz</loc></url></urlset>")
+            .unwrap();
+        let body = gz.finish().unwrap();
+        assert_eq!(
+            parse_sitemap(&body).entries[0].loc,
+            "https://example.com/gz"
+        );
+    }
+
+    #[test]
+    fn test_flags_too_many_urls() {
+        let mut xml = String::from("<urlset>");
+        for i in 0..=MAX_SITEMAP_ENTRIES {
+            xml.push_str(&format!("<url><loc>https://example.com/{i}</loc></url>"));
+        }
+        xml.push_str("</urlset>");
+        let parsed = parse_sitemap(xml.as_bytes());
+        assert_eq!(parsed.entries.len(), MAX_SITEMAP_ENTRIES + 1);
+        assert_eq!(parsed.issues, vec!["too_many_urls"]);
+    }
+
+    #[test]
+    fn test_parse_w3c_datetime_forms() {
+        let day = parse_w3c_datetime("2024-03-01").unwrap();
+        let secs = |t: SystemTime| t.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert_eq!(secs(day), 1_709_251_200);
+        assert_eq!(secs(parse_w3c_datetime("2024").unwrap()), 1_704_067_200);
+        assert_eq!(secs(parse_w3c_datetime("2024-03").unwrap()), 1_709_251_200);
+        assert_eq!(
+            secs(parse_w3c_datetime("2024-03-01T01:30:00.5+01:30").unwrap()),
+            1_709_251_200
+        );
+        assert_eq!(
+            secs(parse_w3c_datetime("2024-02-29T23:00-0100").unwrap()),
+            1_709_251_200
+        );
+        assert_eq!(
+            secs(parse_w3c_datetime("2024-03-01T00:00:00").unwrap()),
+            1_709_251_200
+        );
+        for bad in [
+            "2023-02-29",
+            "24-03-01",
+            "2024-13-01",
+            "2024-03-01T25:00Z",
+            "soon",
+            // Four bytes of offset, but not four digits; must not panic.
+            "2024-01-01T10:00+a€",
+        ] {
+            assert!(parse_w3c_datetime(bad).is_none(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_frontier_priority_prefers_fresh_important_urls() {
+        let now = parse_w3c_datetime("2024-06-01").unwrap();
+        let entry = |priority: Option<f32>, lastmod: Option<&str>| SitemapEntry {
+            loc: "https://example.com/".into(),
+            priority,
+            lastmod: lastmod.and_then(parse_w3c_datetime),
+            ..Default::default()
+        };
+        assert_eq!(entry(None, None).frontier_priority(now), 75);
+        assert_eq!(
+            entry(Some(1.0), Some("2024-05-20")).frontier_priority(now),
+            90
+        );
+        assert_eq!(
+            entry(Some(0.0), Some("2024-01-01")).frontier_priority(now),
+            75
+        );
+        assert_eq!(
+            entry(Some(0.5), Some("2020-01-01")).frontier_priority(now),
+            75
+        );
+        assert!(entry(None, Some("2020-01-01")).is_stale(now));
     }
 }
@@ -0,0 +1,337 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use url::Url;
+
+use crate::models::{CrawlTrap, CrawlTrapKind, TrapDetectionConfig};
+
+/// Distinct traps reported per job; later ones still get cut off.
+const MAX_REPORTED_TRAPS: usize = 100;
+
+/// Longest run of segments checked for repetition.
+const MAX_REPEATED_RUN: usize = 4;
+
+/// Counters and findings, kept across checkpoints.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrapState {
+    /// URLs queued per template, for templates with a placeholder or query.
+    #[serde(default)]
+    pub template_counts: HashMap<String, u32>,
+    /// Distinct query strings queued per `host/path`.
+    #[serde(default)]
+    pub query_variant_counts: HashMap<String, u32>,
+    #[serde(default)]
+    pub traps: Vec<CrawlTrap>,
+}
+
+/// Decides whether a newly discovered URL looks like part of a crawler trap.
+/// Admitted URLs are counted towards the per-template and per-path caps;
+/// rejected ones are recorded against the pattern that caught them.
+#[derive(Debug, Clone)]
+pub struct TrapDetector {
+    config: TrapDetectionConfig,
+    state: TrapState,
+}
+
+impl Default for TrapDetector {
+    /// A detector that admits everything.
+    fn default() -> Self {
+        TrapDetector::new(TrapDetectionConfig {
+            enabled: false,
+            ..Default::default()
+        })
+    }
+}
+
+impl TrapDetector {
+    pub fn new(config: TrapDetectionConfig) -> Self {
+        TrapDetector {
+            config,
+            state: TrapState::default(),
+        }
+    }
+
+    /// Resume from a checkpoint's counters and findings.
+    pub fn with_state(mut self, state: TrapState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn state(&self) -> &TrapState {
+        &self.state
+    }
+
+    /// Traps found so far, most costly first.
+    pub fn report(&self) -> Vec<CrawlTrap> {
+        let mut traps = self.state.traps.clone();
+        traps.sort_by(|a, b| {
+            b.skipped_count
+                .cmp(&a.skipped_count)
+                .then_with(|| a.pattern.cmp(&b.pattern))
+        });
+        traps
+    }
+
+    /// Whether `url` (already canonical) may be queued. `listed` URLs come
+    /// from the site's own sitemap: they're still checked for runaway paths
+    /// but neither capped nor counted per template.
+    pub fn admit(&mut self, url: &str, listed: bool) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+        let Ok(parsed) = Url::parse(url) else {
+            return true;
+        };
+        let template = url_template(&parsed);
+
+        let structural = if parsed.path().len() > self.config.max_path_length as usize {
+            Some(CrawlTrapKind::PathTooLong)
+        } else if parsed.query().map_or(0, str::len) > self.config.max_query_length as usize {
+            Some(CrawlTrapKind::QueryTooLong)
+        } else if has_repeated_segments(parsed.path(), self.config.max_segment_repeats) {
+            Some(CrawlTrapKind::RepeatedSegments)
+        } else {
+            None
+        };
+        if let Some(kind) = structural {
+            self.record(kind, template, url);
+            return false;
+        }
+        if listed {
+            return true;
+        }
+
+        let path_key = parsed
+            .query()
+            .map(|_| format!("{}{}", parsed.host_str().unwrap_or_default(), parsed.path()));
+        if let Some(ref key) = path_key {
+            if self
+                .state
+                .query_variant_counts
+                .get(key)
+                .copied()
+                .unwrap_or(0)
+                >= self.config.max_query_variants_per_path
+            {
+                self.record(CrawlTrapKind::QueryVariantCap, format!("{}?*", key), url);
+                return false;
+            }
+        }
+        let capped = template != url_template_literal(&parsed);
+        if capped
+            && self
+                .state
+                .template_counts
+                .get(&template)
+                .copied()
+                .unwrap_or(0)
+                >= self.config.max_urls_per_template
+        {
+            self.record(CrawlTrapKind::TemplateCap, template, url);
+            return false;
+        }
+
+        if let Some(key) = path_key {
+            *self.state.query_variant_counts.entry(key).or_default() += 1;
+        }
+        if capped {
+            *self.state.template_counts.entry(template).or_default() += 1;
+        }
+        true
+    }
+
+    fn record(&mut self, kind: CrawlTrapKind, pattern: String, url: &str) {
+        if let Some(trap) = self
+            .state
+            .traps
+            .iter_mut()
+            .find(|t| t.kind == kind && t.pattern == pattern)
+        {
+            trap.skipped_count += 1;
+            return;
+        }
+        if self.state.traps.len() >= MAX_REPORTED_TRAPS {
+            return;
+        }
+        tracing::info!(?kind, %pattern, url, "Suspected crawler trap");
+        self.state.traps.push(CrawlTrap {
+            kind,
+            pattern,
+            example_url: url.to_string(),
+            skipped_count: 1,
+        });
+    }
+}
+
+/// `host/path?names`, with numbers, dates and IDs in the path replaced by
+/// placeholders and query values dropped: both
+/// `https://example.com/events/2024/05?view=month&page=2` and its
+/// neighbours become `example.com/events/{n}/{n}?page=&view=`.
+fn url_template(url: &Url) -> String {
+    let path: Vec<&str> = url.path().split('/').map(segment_template).collect();
+    with_query_names(url, path.join("/"))
+}
+
+/// The template `url` would have if no segment were a placeholder.
+fn url_template_literal(url: &Url) -> String {
+    with_query_names(url, url.path().to_string())
+}
+
+fn with_query_names(url: &Url, path: String) -> String {
+    let mut template = format!("{}{}", url.host_str().unwrap_or_default(), path);
+    if url.query().is_some() {
+        let names: BTreeSet<String> = url
+            .query_pairs()
+            .map(|(name, _)| name.into_owned())
+            .collect();
+        template.push('?');
+        template.push_str(
+            &names
+                .iter()
+                .map(|n| format!("{}=", n))
+                .collect::<Vec<_>>()
+                .join("&"),
+        );
+    }
+    template
+}
+
+fn segment_template(segment: &str) -> &str {
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if is_digits(segment) {
+        return "{n}";
+    }
+    let parts: Vec<&str> = segment.split('-').collect();
+    if (2..=3).contains(&parts.len()) && parts[0].len() == 4 && parts.iter().all(|p| is_digits(p)) {
+        return "{date}";
+    }
+    let hex_id = segment.len() >= 16 && segment.bytes().all(|b| b.is_ascii_hexdigit() || b == b'-');
+    let token = segment.len() >= 24
+        && segment
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+        && segment.bytes().any(|b| b.is_ascii_digit());
+    if hex_id || token {
+        return "{id}";
+    }
+    segment
+}
+
+/// True when a segment, or a run of up to `MAX_REPEATED_RUN` segments,
+/// occurs more than `max_repeats` times back to back.
+fn has_repeated_segments(path: &str, max_repeats: u32) -> bool {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let max_repeats = max_repeats.max(1) as usize;
+    for run in 1..=MAX_REPEATED_RUN {
+        if segments.len() < run * (max_repeats + 1) {
+            break;
+        }
+        for start in 0..segments.len() - run {
+            let unit = &segments[start..start + run];
+            let repeats = segments[start..]
+                .chunks_exact(run)
+                .take_while(|chunk| *chunk == unit)
+                .count();
+            if repeats > max_repeats {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector(config: TrapDetectionConfig) -> TrapDetector {
+        TrapDetector::new(config)
+    }
+
+    #[test]
+    fn test_repeated_segments() {
+        assert!(has_repeated_segments("/a/b/a/b/a/b", 2));
+        assert!(has_repeated_segments("/x/page/page/page", 2));
+        assert!(!has_repeated_segments("/a/b/a/b", 2));
+        assert!(!has_repeated_segments("/docs/v1/docs/v2/docs", 2));
+
+        let mut d = detector(TrapDetectionConfig::default());
+        assert!(!d.admit("https://example.com/a/b/a/b/a/b", true));
+        assert_eq!(d.report()[0].kind, CrawlTrapKind::RepeatedSegments);
+    }
+
+    #[test]
+    fn test_length_limits() {
+        let mut d = detector(TrapDetectionConfig {
+            max_path_length: 20,
+            max_query_length: 10,
+            ..Default::default()
+        });
+        assert!(d.admit("https://example.com/short?q=1", false));
+        assert!(!d.admit("https://example.com/a-very-long-path-indeed", false));
+        assert!(!d.admit("https://example.com/s?q=aaaaaaaaaaaa", false));
+        let kinds: Vec<CrawlTrapKind> = d.report().iter().map(|t| t.kind).collect();
+        assert!(kinds.contains(&CrawlTrapKind::PathTooLong));
+        assert!(kinds.contains(&CrawlTrapKind::QueryTooLong));
+    }
+
+    #[test]
+    fn test_template_cap_catches_calendars() {
+        let mut d = detector(TrapDetectionConfig {
+            max_urls_per_template: 3,
+            ..Default::default()
+        });
+        let admitted = (1..=10)
+            .filter(|day| {
+                d.admit(
+                    &format!("https://example.com/calendar/2024/05/{day}"),
+                    false,
+                )
+            })
+            .count();
+        assert_eq!(admitted, 3);
+        // Other pages are unaffected.
+        assert!(d.admit("https://example.com/about", false));
+        let report = d.report();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].kind, CrawlTrapKind::TemplateCap);
+        assert_eq!(report[0].pattern, "example.com/calendar/{n}/{n}/{n}");
+        assert_eq!(report[0].skipped_count, 7);
+        assert_eq!(
+            report[0].example_url,
+            "https://example.com/calendar/2024/05/4"
+        );
+    }
+
+    #[test]
+    fn test_query_variant_cap_and_listed_urls() {
+        let mut d = detector(TrapDetectionConfig {
+            max_query_variants_per_path: 2,
+            ..Default::default()
+        });
+        assert!(d.admit("https://example.com/shoes?color=red", false));
+        assert!(d.admit("https://example.com/shoes?size=9", false));
+        assert!(!d.admit("https://example.com/shoes?color=red&size=9", false));
+        // Sitemap URLs are neither capped nor counted.
+        assert!(d.admit("https://example.com/shoes?color=blue", true));
+        assert_eq!(d.report()[0].pattern, "example.com/shoes?*");
+    }
+
+    #[test]
+    fn test_templates() {
+        let url = Url::parse(
+            "https://example.com/events/2024-05-01/3f2b8c1e-9a7d-4c2e-8f1a-0b9c8d7e6f5a?view=m&page=2",
+        )
+        .unwrap();
+        assert_eq!(
+            url_template(&url),
+            "example.com/events/{date}/{id}?page=&view="
+        );
+    }
+
+    #[test]
+    fn test_disabled_admits_everything() {
+        let mut d = TrapDetector::default();
+        assert!(d.admit("https://example.com/a/a/a/a/a/a", false));
+        assert!(d.report().is_empty());
+    }
+}
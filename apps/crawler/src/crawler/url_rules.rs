@@ -0,0 +1,268 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+use thiserror::Error;
+use url::Url;
+
+use crate::models::{CrawlConfig, UrlPattern};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum UrlRuleError {
+    #[error("Invalid regex {pattern:?}: {message}")]
+    InvalidRegex { pattern: String, message: String },
+    #[error("Empty URL pattern")]
+    EmptyPattern,
+}
+
+/// Stat key for URLs that matched no include rule.
+pub const NOT_INCLUDED: &str = "include";
+
+/// Stat key for URLs carrying a query parameter outside the allow list.
+pub const PARAM_NOT_ALLOWED: &str = "allowed_query_params";
+
+#[derive(Debug, Clone)]
+struct CompiledPattern {
+    /// Stat key, e.g. `exclude:glob:/tag/**`.
+    label: String,
+    regex: Regex,
+}
+
+/// Include/exclude and query-parameter rules from `CrawlConfig`, compiled.
+/// URL patterns match the path plus query, e.g. `/blog/post?page=2`.
+///
+/// Rules see the canonical URL, so a parameter canonicalization already
+/// dropped (e.g. `utm_*` under `strip_tracking_params`) can't trip a deny
+/// rule: the URL is crawled without it.
+#[derive(Debug, Clone, Default)]
+pub struct UrlFilter {
+    include: Vec<CompiledPattern>,
+    exclude: Vec<CompiledPattern>,
+    /// Parameter-name globs; empty allows any parameter.
+    allowed_params: Vec<Regex>,
+    denied_params: Vec<(String, Regex)>,
+}
+
+impl UrlFilter {
+    pub fn from_config(config: &CrawlConfig) -> Result<Self, UrlRuleError> {
+        let compile_all = |kind: &str, patterns: &[UrlPattern]| {
+            patterns
+                .iter()
+                .map(|p| {
+                    Ok(CompiledPattern {
+                        label: format!("{}:{}", kind, p),
+                        regex: compile_pattern(p)?,
+                    })
+                })
+                .collect::<Result<Vec<_>, UrlRuleError>>()
+        };
+        Ok(UrlFilter {
+            include: compile_all("include", &config.include_patterns)?,
+            exclude: compile_all("exclude", &config.exclude_patterns)?,
+            allowed_params: config
+                .allowed_query_params
+                .iter()
+                .map(|p| param_glob(p))
+                .collect::<Result<_, _>>()?,
+            denied_params: config
+                .denied_query_params
+                .iter()
+                .map(|p| Ok((format!("denied_query_param:{}", p), param_glob(p)?)))
+                .collect::<Result<_, UrlRuleError>>()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty()
+            && self.exclude.is_empty()
+            && self.allowed_params.is_empty()
+            && self.denied_params.is_empty()
+    }
+
+    /// The stat key of the first rule that rejects `url`, or `None` when the
+    /// URL may be crawled. Excludes win over includes.
+    pub fn rejecting_rule(&self, url: &str) -> Option<&str> {
+        if self.is_empty() {
+            return None;
+        }
+        let parsed = Url::parse(url).ok()?;
+        let target = match parsed.query() {
+            Some(q) => format!("{}?{}", parsed.path(), q),
+            None => parsed.path().to_string(),
+        };
+
+        if let Some(rule) = self.exclude.iter().find(|p| p.regex.is_match(&target)) {
+            return Some(&rule.label);
+        }
+        for (name, _) in parsed.query_pairs() {
+            if let Some((label, _)) = self.denied_params.iter().find(|(_, re)| re.is_match(&name)) {
+                return Some(label);
+            }
+            if !self.allowed_params.is_empty()
+                && !self.allowed_params.iter().any(|re| re.is_match(&name))
+            {
+                return Some(PARAM_NOT_ALLOWED);
+            }
+        }
+        if !self.include.is_empty() && !self.include.iter().any(|p| p.regex.is_match(&target)) {
+            return Some(NOT_INCLUDED);
+        }
+        None
+    }
+}
+
+/// Per-rule counts of URLs the filter kept out of the frontier.
+pub type FilteredCounts = BTreeMap<String, u32>;
+
+fn compile_pattern(pattern: &UrlPattern) -> Result<Regex, UrlRuleError> {
+    match pattern {
+        UrlPattern::Glob(glob) if glob.is_empty() => Err(UrlRuleError::EmptyPattern),
+        UrlPattern::Glob(glob) => Ok(Regex::new(&glob_to_regex(glob)).expect("escaped glob")),
+        UrlPattern::Regex(re) if re.is_empty() => Err(UrlRuleError::EmptyPattern),
+        UrlPattern::Regex(re) => Regex::new(re).map_err(|e| UrlRuleError::InvalidRegex {
+            pattern: re.clone(),
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Anchored regex for a path glob: `**` matches anything, `*` anything but
+/// `/`, and a trailing `/**` also matches the directory itself, so `/blog/**`
+/// covers `/blog`. Everything else is literal. Any query string may follow,
+/// so `/blog/**` also covers `/blog?page=2`.
+fn glob_to_regex(glob: &str) -> String {
+    let (body, dir_suffix) = match glob.strip_suffix("/**") {
+        Some(prefix) => (prefix, true),
+        None => (glob, false),
+    };
+    let mut re = String::from("^");
+    let mut rest = body;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("**") {
+            re.push_str(".*");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('*') {
+            re.push_str("[^/]*");
+            rest = after;
+        } else {
+            let ch = rest.chars().next().expect("non-empty");
+            re.push_str(&regex::escape(&ch.to_string()));
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    if dir_suffix {
+        re.push_str("(?:/.*)?");
+    }
+    re.push_str(r"(?:\?.*)?$");
+    re
+}
+
+/// Query parameter names may use `*`, e.g. `utm_*`.
+fn param_glob(name: &str) -> Result<Regex, UrlRuleError> {
+    if name.is_empty() {
+        return Err(UrlRuleError::EmptyPattern);
+    }
+    let escaped: Vec<String> = name.split('*').map(regex::escape).collect();
+    Ok(Regex::new(&format!("^{}$", escaped.join(".*"))).expect("escaped glob"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(json: serde_json::Value) -> UrlFilter {
+        let mut config = serde_json::json!({ "seed_urls": [], "max_pages": 1, "max_depth": 1 });
+        config
+            .as_object_mut()
+            .unwrap()
+            .extend(json.as_object().unwrap().clone());
+        UrlFilter::from_config(&serde_json::from_value(config).unwrap()).unwrap()
+    }
+
+    const BASE: &str = "https://example.com";
+
+    fn rule<'a>(f: &'a UrlFilter, path: &str) -> Option<&'a str> {
+        f.rejecting_rule(&format!("{BASE}{path}"))
+    }
+
+    #[test]
+    fn test_glob_semantics() {
+        let f = filter(serde_json::json!({
+            "include_patterns": [{ "type": "glob", "pattern": "/blog/**" }]
+        }));
+        assert_eq!(rule(&f, "/blog"), None);
+        assert_eq!(rule(&f, "/blog/"), None);
+        assert_eq!(rule(&f, "/blog/2024/post?page=2"), None);
+        assert_eq!(rule(&f, "/blog?page=2"), None);
+        assert_eq!(rule(&f, "/blogroll"), Some(NOT_INCLUDED));
+        assert_eq!(rule(&f, "/"), Some(NOT_INCLUDED));
+
+        let f = filter(serde_json::json!({
+            "exclude_patterns": [{ "type": "glob", "pattern": "/tag/*" }]
+        }));
+        assert_eq!(rule(&f, "/tag/rust"), Some("exclude:glob:/tag/*"));
+        assert_eq!(rule(&f, "/tag/rust?page=2"), Some("exclude:glob:/tag/*"));
+        assert_eq!(rule(&f, "/tag/rust/feed"), None);
+
+        let f = filter(serde_json::json!({
+            "exclude_patterns": [{ "type": "glob", "pattern": "/about" }]
+        }));
+        assert_eq!(rule(&f, "/about?ref=nav"), Some("exclude:glob:/about"));
+        assert_eq!(rule(&f, "/about-us"), None);
+    }
+
+    #[test]
+    fn test_exclude_wins_over_include() {
+        let f = filter(serde_json::json!({
+            "include_patterns": [{ "type": "glob", "pattern": "/shop/**" }],
+            "exclude_patterns": [
+                { "type": "regex", "pattern": "^/shop/(cart|checkout)" },
+                { "type": "glob", "pattern": "/search*" }
+            ]
+        }));
+        assert_eq!(rule(&f, "/shop/shoes"), None);
+        assert_eq!(
+            rule(&f, "/shop/cart/1"),
+            Some("exclude:regex:^/shop/(cart|checkout)")
+        );
+        assert_eq!(rule(&f, "/search?q=x"), Some("exclude:glob:/search*"));
+    }
+
+    #[test]
+    fn test_query_param_lists() {
+        let f = filter(serde_json::json!({
+            "denied_query_params": ["sessionid", "utm_*"]
+        }));
+        assert_eq!(rule(&f, "/a?page=2"), None);
+        assert_eq!(
+            rule(&f, "/a?utm_source=x"),
+            Some("denied_query_param:utm_*")
+        );
+        assert_eq!(
+            rule(&f, "/a?page=1&sessionid=abc"),
+            Some("denied_query_param:sessionid")
+        );
+
+        let f = filter(serde_json::json!({ "allowed_query_params": ["page"] }));
+        assert_eq!(rule(&f, "/a"), None);
+        assert_eq!(rule(&f, "/a?page=3"), None);
+        assert_eq!(rule(&f, "/a?page=3&color=red"), Some(PARAM_NOT_ALLOWED));
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        let config: CrawlConfig = serde_json::from_value(serde_json::json!({
+            "seed_urls": [], "max_pages": 1, "max_depth": 1,
+            "exclude_patterns": [{ "type": "regex", "pattern": "(" }]
+        }))
+        .unwrap();
+        assert!(matches!(
+            UrlFilter::from_config(&config),
+            Err(UrlRuleError::InvalidRegex { .. })
+        ));
+    }
+
+    #[test]
+    fn test_glob_escapes_regex_characters() {
+        assert_eq!(glob_to_regex("/a.b/*"), r"^/a\.b/[^/]*(?:\?.*)?$");
+        assert_eq!(glob_to_regex("/x/**/y"), r"^/x/.*/y(?:\?.*)?$");
+    }
+}
@@ -0,0 +1,147 @@
+use serde::Serialize;
+
+use crate::crawler::frontier::normalize_url;
+use crate::models::{CanonicalizationPolicy, CrawlPageResult, LinkEdge};
+
+/// R2 key of one batch's part of the job's internal link graph.
+pub fn edges_key(job_id: &str, batch_index: u32) -> String {
+    format!("crawls/{}/links/edges-{:05}.jsonl.gz", job_id, batch_index)
+}
+
+/// Internal link edges of `pages`, canonicalized under the crawl's policy.
+/// Self-links are dropped. Pages served unchanged (304) only carry bare URLs
+/// from the previous crawl, so their edges have no anchor text or placement.
+pub fn link_edges(pages: &[CrawlPageResult], policy: &CanonicalizationPolicy) -> Vec<LinkEdge> {
+    let mut edges = Vec::new();
+    for page in pages {
+        let Some(source) = normalize_url(&page.url, policy) else {
+            continue;
+        };
+        let details = &page.extracted.internal_link_details;
+        let links: Vec<(&str, &str, &str, _)> = if details.is_empty() {
+            page.extracted
+                .internal_links
+                .iter()
+                .map(|url| (url.as_str(), "", "", None))
+                .collect()
+        } else {
+            details
+                .iter()
+                .map(|l| {
+                    (
+                        l.url.as_str(),
+                        l.anchor_text.as_str(),
+                        l.rel.as_str(),
+                        l.placement,
+                    )
+                })
+                .collect()
+        };
+        for (url, anchor_text, rel, placement) in links {
+            let Some(target) = normalize_url(url, policy) else {
+                continue;
+            };
+            if target == source {
+                continue;
+            }
+            edges.push(LinkEdge {
+                source: source.clone(),
+                target,
+                anchor_text: anchor_text.to_string(),
+                rel: rel.to_string(),
+                placement,
+            });
+        }
+    }
+    edges
+}
+
+/// One JSON object per line, as uploaded for the link edges and the site
+/// summary's page metrics.
+pub fn to_json_lines<T: Serialize>(items: &[T]) -> String {
+    let mut out = String::new();
+    for item in items {
+        out.push_str(&serde_json::to_string(item).expect("plain data serializes"));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ExtractedData, ExtractedLink, LinkPlacement};
+
+    fn page(url: &str, extracted: ExtractedData) -> CrawlPageResult {
+        CrawlPageResult {
+            url: url.to_string(),
+            status_code: 200,
+            extracted,
+            ..Default::default()
+        }
+    }
+
+    fn link(url: &str, anchor: &str, placement: LinkPlacement) -> ExtractedLink {
+        ExtractedLink {
+            url: url.to_string(),
+            anchor_text: anchor.to_string(),
+            rel: String::new(),
+            is_external: false,
+            placement: Some(placement),
+        }
+    }
+
+    #[test]
+    fn test_edges_carry_link_details() {
+        let home = page(
+            "https://example.com/",
+            ExtractedData {
+                internal_link_details: vec![
+                    link(
+                        "https://example.com/pricing/",
+                        "Pricing",
+                        LinkPlacement::Nav,
+                    ),
+                    link("https://example.com/#top", "Top", LinkPlacement::Footer),
+                    link(
+                        "https://example.com/blog?utm_source=x",
+                        "Read the blog",
+                        LinkPlacement::Content,
+                    ),
+                ],
+                ..Default::default()
+            },
+        );
+        let policy = CanonicalizationPolicy {
+            strip_tracking_params: true,
+            ..Default::default()
+        };
+        let edges = link_edges(&[home], &policy);
+        assert_eq!(edges.len(), 2, "self-link dropped: {edges:?}");
+        assert_eq!(edges[0].target, "https://example.com/pricing");
+        assert_eq!(edges[0].placement, Some(LinkPlacement::Nav));
+        assert_eq!(edges[1].target, "https://example.com/blog");
+        assert_eq!(edges[1].anchor_text, "Read the blog");
+
+        let lines = to_json_lines(&edges);
+        assert_eq!(lines.lines().count(), 2);
+        let first: LinkEdge = serde_json::from_str(lines.lines().next().unwrap()).unwrap();
+        assert_eq!(first, edges[0]);
+    }
+
+    #[test]
+    fn test_unchanged_pages_fall_back_to_bare_links() {
+        let mut unchanged = page(
+            "https://example.com/about",
+            ExtractedData {
+                internal_links: vec!["https://example.com/team".to_string()],
+                ..Default::default()
+            },
+        );
+        unchanged.unchanged = true;
+        let edges = link_edges(&[unchanged], &CanonicalizationPolicy::default());
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].anchor_text, "");
+        assert_eq!(edges[0].placement, None);
+    }
+}
@@ -1,27 +1,61 @@
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
-use std::collections::{HashMap, HashSet};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
 use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
-use url::Url;
 
 use crate::config::Config;
+use crate::crawler::ai_crawlers::{self, AccessSample};
 use crate::crawler::checkpoint::CrawlCheckpoint;
+use crate::crawler::egress::EgressPolicy;
 use crate::crawler::fetcher::RateLimitedFetcher;
 use crate::crawler::frontier::Frontier;
-use crate::crawler::robots::RobotsChecker;
+use crate::crawler::llms_txt;
+use crate::crawler::near_duplicates::NearDuplicateIndex;
+use crate::crawler::robots::{HostRobots, RobotsChecker};
+use crate::crawler::scope::{self, ScopeMatcher};
+use crate::crawler::simhash;
+use crate::crawler::sitemap::SitemapEntry;
+use crate::crawler::traps::TrapDetector;
+use crate::crawler::url_rules::UrlFilter;
 use crate::crawler::{CrawlEngine, CrawlEngineError};
 use crate::lighthouse::LighthouseRunner;
 use crate::models::*;
 use crate::renderer::JsRenderer;
 use crate::storage::{StorageClient, StorageConfig};
 
+pub mod link_graph;
+pub mod outbox;
+pub mod reconcile;
+pub mod site_graph;
+pub mod store;
+
+use outbox::Outbox;
+use reconcile::SitemapReconciler;
+use site_graph::SiteGraph;
+use store::{JobStore, JobStoreError};
+
 type HmacSha256 = Hmac<Sha256>;
 
+/// How often a running job polls a shared store for cancel requests made
+/// through another crawler instance.
+const CANCEL_POLL_INTERVAL_SECS: u64 = 5;
+
+/// Attempts per callback POST before a batch is spooled to the outbox.
+const CALLBACK_MAX_ATTEMPTS: u32 = 4;
+/// Delay before the first callback retry; doubles on each further attempt.
+const CALLBACK_BACKOFF_BASE_MS: u64 = 500;
+/// How often a finished job's outbox is replayed until the API acknowledges
+/// every batch.
+const REDELIVERY_INTERVAL_SECS: u64 = 30;
+/// How long a batch may wait in the outbox before redelivery gives up on it.
+const REDELIVERY_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+
 /// Matches the API's backlinks ingestion payload shape.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -107,6 +141,25 @@ fn collect_backlink_entries(pages: &[CrawlPageResult]) -> Vec<BacklinkEntry> {
     entries
 }
 
+/// Outcome record for a URL whose crawl failed, classified by the underlying
+/// `FetchError` when there is one.
+fn failure_outcome(url: String, error: &CrawlEngineError) -> PageOutcome {
+    let (error_kind, status_code) = match error {
+        CrawlEngineError::FetchError(e) => (e.kind(), e.status_code()),
+        CrawlEngineError::ParseError(_) => ("parse", None),
+        CrawlEngineError::BlockedByRobots { .. } => ("blocked", None),
+    };
+    PageOutcome {
+        url,
+        outcome: PageOutcomeKind::Failed,
+        error_kind: Some(error_kind.to_string()),
+        error: Some(error.to_string()),
+        status_code,
+        robots_rule: None,
+        duplicate_of: None,
+    }
+}
+
 /// Assemble the `SiteContext` — the site-level *inputs* the scoring engine reads — from
 /// already-fetched signals. Kept pure and network-free so this wiring can be unit-tested:
 /// the scorer itself is covered by the `packages/scoring` golden harness (#117), but
@@ -118,20 +171,40 @@ fn collect_backlink_entries(pages: &[CrawlPageResult]) -> Vec<BacklinkEntry> {
 /// argument through `Url::parse`; a bare `"/"` fails to parse and silently falls back to
 /// "allowed", so every site reports `[]` and the `AI_CRAWLER_BLOCKED` (-25) factor never
 /// fires. That was #119 — a wrong *input* to a correct scorer.
+///
+/// `ai_crawler_access` covers the whole AI crawler catalog, probing the root plus the
+/// sections and key page types sampled from `known_urls` (seeds and sitemap entries).
+///
+/// An unreachable robots.txt (5xx, 429, network error) blocks crawling but says nothing
+/// about which bots the site blocks, so it is treated like having no checker.
+///
+/// `has_llms_txt` counts only a real llms.txt: a report flagged as a placeholder (empty,
+/// or HTML served with a 200) is attached for diagnostics but leaves the flag false.
 fn build_site_context(
     robots: Option<&RobotsChecker>,
     domain: Option<&str>,
-    has_llms_txt: bool,
+    llms_txt: Option<LlmsTxtReport>,
+    llms_full_txt: Option<LlmsFullTxt>,
     sitemap_analysis: Option<SitemapAnalysis>,
+    known_urls: &[String],
 ) -> SiteContext {
-    let ai_crawlers_blocked = match (robots, domain) {
-        (Some(checker), Some(d)) => checker.blocked_bots(&format!("https://{}/", d)),
-        _ => Vec::new(),
+    // An unreachable robots.txt disallows everything for crawling, but isn't
+    // the site blocking AI bots.
+    let robots = robots.filter(|checker| !checker.unreachable);
+    let (ai_crawlers_blocked, ai_crawler_access) = match (robots, domain) {
+        (Some(checker), Some(d)) => (
+            checker.blocked_bots(&format!("https://{}/", d)),
+            ai_crawlers::crawler_access(checker, d, &AccessSample::from_urls(known_urls)),
+        ),
+        _ => (Vec::new(), Vec::new()),
     };
 
     SiteContext {
-        has_llms_txt,
+        has_llms_txt: llms_txt.as_ref().is_some_and(|r| !r.is_placeholder),
+        llms_txt,
+        llms_full_txt,
         ai_crawlers_blocked,
+        ai_crawler_access,
         has_sitemap: sitemap_analysis.is_some(),
         sitemap_analysis,
         content_hashes: HashMap::new(),
@@ -140,14 +213,429 @@ fn build_site_context(
     }
 }
 
+/// What the pre-crawl discovery found on one host.
+struct HostDiscovery {
+    robots: Option<RobotsChecker>,
+    /// Sitemap entries to queue, filtered through robots.txt when it's enforced.
+    sitemap_entries: Vec<SitemapEntry>,
+    /// Every URL the host's sitemaps list, for reconciliation.
+    sitemap_urls: Vec<String>,
+    site_context: SiteContext,
+}
+
+/// Fetch `host`'s robots.txt, sitemaps and llms.txt and assemble its site
+/// context. `seed_urls` are the crawl's seeds on this host.
+async fn discover_host(
+    host: &str,
+    seed_urls: Vec<String>,
+    crawl_config: &CrawlConfig,
+    egress: &EgressPolicy,
+    fetcher: &RateLimitedFetcher,
+    job_id: &str,
+) -> HostDiscovery {
+    // URLs the AI crawler access report samples sections and page types from.
+    let mut known_urls = seed_urls;
+
+    // Always fetch robots.txt for sitemap discovery and bot analysis.
+    // The checker feeds two independent things: (1) SiteContext's `ai_crawlers_blocked`
+    // bot analysis — always, regardless of respect_robots — and (2) per-URL crawl
+    // blocking, only when respect_robots.
+    let site_url = known_urls
+        .first()
+        .cloned()
+        .unwrap_or_else(|| format!("https://{host}/"));
+    let robots_checker = RobotsChecker::new(&site_url, egress).await.ok();
+    let mut sitemap_urls_from_robots: Vec<String> = Vec::new();
+    if let Some(ref checker) = robots_checker {
+        sitemap_urls_from_robots = checker.sitemaps.clone();
+
+        // Pace the host to its Crawl-delay when we're honouring robots.txt.
+        if let Some(delay) = checker.crawl_delay(&crawl_config.user_agent) {
+            if crawl_config.respect_robots {
+                tracing::info!(
+                    job_id = %job_id,
+                    host = %host,
+                    crawl_delay_s = delay.as_secs_f64(),
+                    "Honouring robots.txt Crawl-delay"
+                );
+                fetcher.set_crawl_delay(host, delay).await;
+            }
+        }
+    }
+
+    // Fallback: if robots.txt declared no `Sitemap:`, probe the well-known
+    // /sitemap.xml (Googlebot does this too). Sites that omit the directive
+    // — e.g. families.care — would otherwise get zero sitemap coverage and
+    // fall back to homepage BFS, never reaching deep page types like
+    // /us/location/* that aren't shallowly linked.
+    if sitemap_urls_from_robots.is_empty() {
+        sitemap_urls_from_robots.push(format!("https://{}/sitemap.xml", host));
+    }
+
+    // Fetch and parse sitemaps discovered in robots.txt
+    let max_child = (crawl_config.max_pages as usize / 100).clamp(10, 100);
+    let sitemap_result = crate::crawler::sitemap::fetch_sitemap_urls(
+        &sitemap_urls_from_robots,
+        host,
+        max_child,
+        3, // max recursion depth for nested sitemap indexes
+        egress,
+    )
+    .await;
+
+    tracing::info!(
+        job_id = %job_id,
+        host = %host,
+        sitemap_urls = sitemap_result.entries.len(),
+        total_in_sitemap = sitemap_result.total_count,
+        sitemap_files = sitemap_result.sitemap_count,
+        issues = ?sitemap_result.issues,
+        "Sitemap discovery complete"
+    );
+
+    let sitemap_urls: Vec<String> = sitemap_result.urls().map(str::to_string).collect();
+    known_urls.extend(sitemap_urls.iter().cloned());
+    let sitemap_analysis = sitemap_result.analysis();
+
+    // Filter sitemap URLs through robots.txt only when we're enforcing it.
+    let sitemap_entries: Vec<SitemapEntry> = match robots_checker.as_ref() {
+        Some(checker) if crawl_config.respect_robots => sitemap_result
+            .entries
+            .into_iter()
+            .filter(|e| checker.is_allowed(&e.loc, &crawl_config.user_agent))
+            .collect(),
+        _ => sitemap_result.entries,
+    };
+
+    // Check llms.txt (and llms-full.txt). Its same-site links go through the
+    // crawl's fetcher, so they share the host's rate limit.
+    let (llms_txt_report, llms_full_txt) = if crawl_config.check_llms_txt {
+        (
+            llms_txt::audit_llms_txt(host, egress, fetcher).await,
+            llms_txt::fetch_llms_full_txt(host, egress).await,
+        )
+    } else {
+        (None, None)
+    };
+
+    // Assemble the scorer's site-level inputs from the fetched signals.
+    let site_context = build_site_context(
+        robots_checker.as_ref(),
+        Some(host),
+        llms_txt_report,
+        llms_full_txt,
+        sitemap_analysis,
+        &known_urls,
+    );
+
+    HostDiscovery {
+        robots: robots_checker,
+        sitemap_entries,
+        sitemap_urls,
+        site_context,
+    }
+}
+
 /// Internal state for a running or completed job.
 #[derive(Debug)]
 struct JobEntry {
     status: JobStatusKind,
     stats: Option<CrawlStats>,
+    last_batch_index: Option<u32>,
+    /// Batches spooled to the outbox that the API hasn't acknowledged yet.
+    undelivered_batches: usize,
     cancel_token: CancellationToken,
 }
 
+impl JobEntry {
+    /// Move a queued job to crawling as its task starts. False when a cancel
+    /// landed between dispatch and now; the status stays `Cancelled` and the
+    /// task must not run.
+    fn start(&mut self) -> bool {
+        if self.cancel_token.is_cancelled() {
+            return false;
+        }
+        if self.status == JobStatusKind::Queued {
+            self.status = JobStatusKind::Crawling;
+        }
+        true
+    }
+}
+
+/// A job handed to the processing loop: a fresh submission, or a resumption
+/// from an on-disk checkpoint.
+#[derive(Debug)]
+struct QueuedJob {
+    payload: CrawlJobPayload,
+    resume_from: Option<CrawlCheckpoint>,
+    cancel_token: CancellationToken,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum CallbackError {
+    #[error("Failed to serialize batch: {0}")]
+    Serialize(#[from] serde_json::Error),
+    #[error("Callback request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Callback rejected with HTTP {0}")]
+    Status(u16),
+}
+
+impl CallbackError {
+    /// Transport errors, timeouts, throttling and 5xx are worth retrying; other
+    /// rejections won't change on a retry.
+    fn is_retryable(&self) -> bool {
+        match self {
+            CallbackError::Serialize(_) => false,
+            CallbackError::Request(_) => true,
+            CallbackError::Status(code) => *code >= 500 || *code == 408 || *code == 429,
+        }
+    }
+}
+
+/// Delay before retry number `attempt` (1-based) of a callback POST.
+fn callback_backoff(attempt: u32) -> Duration {
+    Duration::from_millis(CALLBACK_BACKOFF_BASE_MS << attempt.saturating_sub(1).min(10))
+}
+
+/// Delivers a job's result batches to the API in `batch_index` order. Batches
+/// the API doesn't acknowledge after retries go to the on-disk outbox, and
+/// every later batch queues behind them until the outbox is replayed. Once
+/// the API rejects a batch outright, it and everything after it are
+/// dead-lettered and the job fails.
+#[derive(Debug, Clone)]
+struct CallbackDelivery {
+    job_id: String,
+    client: reqwest::Client,
+    callback_url: String,
+    secret: String,
+    outbox: Outbox,
+    store: Arc<JobStore>,
+    entry: Arc<Mutex<JobEntry>>,
+    given_up: Arc<AtomicBool>,
+}
+
+impl CallbackDelivery {
+    /// Deliver a batch. Returns `true` once the API has acknowledged it, and
+    /// `false` when it was left in the outbox or dead-lettered.
+    async fn deliver(&self, batch: &CrawlResultBatch) -> bool {
+        if self.has_given_up() {
+            // An earlier batch never arrived; this one must not overtake it.
+            self.spool(batch).await;
+            self.dead_letter().await;
+            return false;
+        }
+        if !self.outbox.has_pending(&self.job_id) {
+            let error = match JobManager::send_callback(
+                &self.client,
+                &self.callback_url,
+                batch,
+                &self.secret,
+            )
+            .await
+            {
+                Ok(()) => {
+                    self.acknowledged(batch.batch_index).await;
+                    return true;
+                }
+                Err(e) => e,
+            };
+            tracing::error!(
+                job_id = %self.job_id,
+                batch_index = batch.batch_index,
+                error = %error,
+                "Callback delivery failed; spooling batch to outbox"
+            );
+            self.spool(batch).await;
+            if !error.is_retryable() {
+                self.give_up(&error.to_string()).await;
+            }
+            return false;
+        }
+
+        // Earlier batches are still waiting: this one goes behind them.
+        self.spool(batch).await;
+        self.flush().await;
+        !self.has_given_up()
+            && !self
+                .outbox
+                .pending(&self.job_id)
+                .contains(&batch.batch_index)
+    }
+
+    /// Replay spooled batches in order, stopping at the first one the API
+    /// still doesn't acknowledge. Returns `true` once the outbox is empty.
+    async fn flush(&self) -> bool {
+        for batch_index in self.outbox.pending(&self.job_id) {
+            let spooled = match self.outbox.load(&self.job_id, batch_index) {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::error!(
+                        job_id = %self.job_id,
+                        batch_index,
+                        error = %e,
+                        "Dropping unreadable outbox batch"
+                    );
+                    let _ = self.outbox.remove(&self.job_id, batch_index);
+                    continue;
+                }
+            };
+            if let Err(e) = JobManager::send_callback(
+                &self.client,
+                &spooled.callback_url,
+                &spooled.batch,
+                &self.secret,
+            )
+            .await
+            {
+                tracing::warn!(
+                    job_id = %self.job_id,
+                    batch_index,
+                    error = %e,
+                    "Outbox redelivery failed"
+                );
+                if !e.is_retryable() {
+                    self.give_up(&e.to_string()).await;
+                }
+                break;
+            }
+            if let Err(e) = self.outbox.remove(&self.job_id, batch_index) {
+                tracing::warn!(job_id = %self.job_id, error = %e, "Failed to remove delivered outbox batch");
+            }
+            self.acknowledged(batch_index).await;
+        }
+        self.refresh_undelivered().await;
+        !self.outbox.has_pending(&self.job_id)
+    }
+
+    /// Replay the outbox every `REDELIVERY_INTERVAL_SECS` until it drains, then
+    /// mark the job complete (unless it was cancelled). Gives up when the API
+    /// rejects a batch or the oldest one has waited `REDELIVERY_MAX_AGE_SECS`.
+    async fn redeliver_until_drained(self, final_stats: Option<CrawlStats>) {
+        loop {
+            if self.has_given_up() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_secs(REDELIVERY_INTERVAL_SECS)).await;
+            let drained = self.flush().await;
+            if self.has_given_up() {
+                return;
+            }
+            if drained {
+                break;
+            }
+            let expired = self
+                .outbox
+                .oldest_age(&self.job_id)
+                .is_some_and(|age| age.as_secs() >= REDELIVERY_MAX_AGE_SECS);
+            if expired {
+                self.give_up("batch exceeded the redelivery age limit")
+                    .await;
+                return;
+            }
+        }
+        let status = {
+            let mut e = self.entry.lock().await;
+            if e.status != JobStatusKind::Cancelled {
+                e.status = JobStatusKind::Complete;
+            }
+            if final_stats.is_some() {
+                e.stats = final_stats.clone();
+            }
+            e.status
+        };
+        warn_on_store_error(
+            &self.job_id,
+            self.store
+                .set_status(&self.job_id, status, final_stats.as_ref())
+                .await,
+        );
+        tracing::info!(job_id = %self.job_id, "Outbox drained; all batches delivered");
+    }
+
+    fn has_given_up(&self) -> bool {
+        self.given_up.load(Ordering::Relaxed)
+    }
+
+    /// Stop delivering: dead-letter the job's undelivered batches and mark the
+    /// job failed (unless it was cancelled).
+    async fn give_up(&self, reason: &str) {
+        self.given_up.store(true, Ordering::Relaxed);
+        tracing::error!(
+            job_id = %self.job_id,
+            reason,
+            "Giving up on callback delivery; moving batches to dead letter"
+        );
+        self.dead_letter().await;
+        let status = {
+            let mut e = self.entry.lock().await;
+            if e.status != JobStatusKind::Cancelled {
+                e.status = JobStatusKind::Failed;
+            }
+            e.status
+        };
+        warn_on_store_error(
+            &self.job_id,
+            self.store.set_status(&self.job_id, status, None).await,
+        );
+    }
+
+    async fn dead_letter(&self) {
+        if let Err(e) = self.outbox.dead_letter(&self.job_id) {
+            tracing::error!(job_id = %self.job_id, error = %e, "Failed to dead-letter outbox batches");
+        }
+        self.refresh_undelivered().await;
+    }
+
+    async fn spool(&self, batch: &CrawlResultBatch) {
+        if let Err(e) = self.outbox.spool(&self.callback_url, batch) {
+            tracing::error!(
+                job_id = %self.job_id,
+                batch_index = batch.batch_index,
+                error = %e,
+                "Failed to spool batch to outbox; batch lost"
+            );
+        }
+        self.refresh_undelivered().await;
+    }
+
+    async fn acknowledged(&self, batch_index: u32) {
+        self.entry.lock().await.last_batch_index = Some(batch_index);
+        warn_on_store_error(
+            &self.job_id,
+            self.store.record_batch(&self.job_id, batch_index).await,
+        );
+    }
+
+    async fn refresh_undelivered(&self) {
+        self.entry.lock().await.undelivered_batches = self.outbox.pending(&self.job_id).len();
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CancelError {
+    #[error("Job {0} not found")]
+    NotFound(String),
+    #[error("Job {0} has already finished")]
+    AlreadyFinished(String),
+}
+
+/// Whether a job has reached a state a cancel can no longer change.
+fn is_finished(status: JobStatusKind) -> bool {
+    matches!(
+        status,
+        JobStatusKind::Complete | JobStatusKind::Failed | JobStatusKind::Cancelled
+    )
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResumeError {
+    #[error("No checkpoint found for job {0}")]
+    NotFound(String),
+    #[error("Job {0} is already running")]
+    AlreadyRunning(String),
+}
+
 /// Aggregate metrics for the crawler service.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct CrawlMetrics {
@@ -157,50 +645,131 @@ pub struct CrawlMetrics {
     pub uptime_secs: u64,
 }
 
+/// Fallback per-job duration used for queue estimates before any job has
+/// finished in this process.
+const DEFAULT_JOB_ESTIMATE_SECS: u64 = 300;
+
+/// FIFO admission state: jobs waiting for a crawl slot, and the ones whose
+/// task holds one (until it ends, even after a cancel).
+#[derive(Debug, Default)]
+struct Scheduler {
+    waiting: VecDeque<QueuedJob>,
+    running: HashSet<String>,
+}
+
+impl Scheduler {
+    /// 1-based position of a job in the waiting queue.
+    fn position(&self, job_id: &str) -> Option<usize> {
+        self.waiting
+            .iter()
+            .position(|j| j.payload.job_id == job_id)
+            .map(|i| i + 1)
+    }
+}
+
+/// Estimated seconds until a queued job starts: every `max_jobs` jobs ahead of
+/// it is one more "wave" of average job duration.
+fn estimate_start_secs(position: usize, max_jobs: usize, avg_job_secs: u64) -> u64 {
+    let waves = (position.saturating_sub(1) / max_jobs.max(1)) as u64 + 1;
+    waves * avg_job_secs
+}
+
+/// A crawl slot held by a running job. Dropping it frees the slot and admits
+/// the next waiting job, including when the job task panics or is aborted.
+struct SlotGuard {
+    ctx: JobContext,
+    job_id: String,
+}
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        let mut sched = self
+            .ctx
+            .scheduler
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        sched.running.remove(&self.job_id);
+        drop(sched);
+        // Tasks dropped while the runtime shuts down have nowhere to spawn.
+        if tokio::runtime::Handle::try_current().is_ok() {
+            JobManager::dispatch(&self.ctx);
+        }
+    }
+}
+
+/// Shared handles the processing loop and job tasks need to run and admit jobs.
+#[derive(Debug, Clone)]
+struct JobContext {
+    jobs: Arc<RwLock<HashMap<String, Arc<Mutex<JobEntry>>>>>,
+    config: Arc<Config>,
+    total_pages_crawled: Arc<AtomicU64>,
+    total_pages_errored: Arc<AtomicU64>,
+    event_senders: Arc<RwLock<HashMap<String, broadcast::Sender<String>>>>,
+    scheduler: Arc<std::sync::Mutex<Scheduler>>,
+    /// Moving average of finished job durations (ms); `0` until one finishes.
+    avg_job_ms: Arc<AtomicU64>,
+    store: Arc<JobStore>,
+}
+
 /// Manages crawl job lifecycle: submission, status queries, and cancellation.
 #[derive(Debug)]
 pub struct JobManager {
-    _config: Arc<Config>,
+    config: Arc<Config>,
     jobs: Arc<RwLock<HashMap<String, Arc<Mutex<JobEntry>>>>>,
-    tx: mpsc::Sender<CrawlJobPayload>,
+    tx: mpsc::Sender<QueuedJob>,
     total_pages_crawled: Arc<AtomicU64>,
     total_pages_errored: Arc<AtomicU64>,
     start_time: Instant,
     event_senders: Arc<RwLock<HashMap<String, broadcast::Sender<String>>>>,
+    scheduler: Arc<std::sync::Mutex<Scheduler>>,
+    avg_job_ms: Arc<AtomicU64>,
+    store: Arc<JobStore>,
+}
+
+/// Log a failed job-store write. The local entry stays authoritative for this
+/// process, so a store outage degrades persistence but never fails a crawl.
+fn warn_on_store_error(job_id: &str, result: Result<(), JobStoreError>) {
+    if let Err(e) = result {
+        tracing::warn!(job_id = %job_id, error = %e, "Job store write failed");
+    }
 }
 
 impl JobManager {
-    /// Create a new JobManager.
+    /// Create a new JobManager backed by an in-memory job store.
     /// Spawns a background task that processes incoming jobs from the mpsc channel.
     pub fn new(config: Arc<Config>) -> Self {
-        let (tx, rx) = mpsc::channel::<CrawlJobPayload>(64);
-        let jobs: Arc<RwLock<HashMap<String, Arc<Mutex<JobEntry>>>>> =
-            Arc::new(RwLock::new(HashMap::new()));
-        let total_pages_crawled = Arc::new(AtomicU64::new(0));
-        let total_pages_errored = Arc::new(AtomicU64::new(0));
+        Self::with_store(config, JobStore::memory())
+    }
 
-        let event_senders: Arc<RwLock<HashMap<String, broadcast::Sender<String>>>> =
-            Arc::new(RwLock::new(HashMap::new()));
+    /// Create a JobManager that persists job state to `store`.
+    pub fn with_store(config: Arc<Config>, store: JobStore) -> Self {
+        let (tx, rx) = mpsc::channel::<QueuedJob>(64);
+        let ctx = JobContext {
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            config: config.clone(),
+            total_pages_crawled: Arc::new(AtomicU64::new(0)),
+            total_pages_errored: Arc::new(AtomicU64::new(0)),
+            event_senders: Arc::new(RwLock::new(HashMap::new())),
+            scheduler: Arc::new(std::sync::Mutex::new(Scheduler::default())),
+            avg_job_ms: Arc::new(AtomicU64::new(0)),
+            store: Arc::new(store),
+        };
 
         let manager = JobManager {
-            _config: config.clone(),
-            jobs: jobs.clone(),
+            config,
+            jobs: ctx.jobs.clone(),
             tx,
-            total_pages_crawled: total_pages_crawled.clone(),
-            total_pages_errored: total_pages_errored.clone(),
+            total_pages_crawled: ctx.total_pages_crawled.clone(),
+            total_pages_errored: ctx.total_pages_errored.clone(),
             start_time: Instant::now(),
-            event_senders: event_senders.clone(),
+            event_senders: ctx.event_senders.clone(),
+            scheduler: ctx.scheduler.clone(),
+            avg_job_ms: ctx.avg_job_ms.clone(),
+            store: ctx.store.clone(),
         };
 
         // Spawn the consumer loop
-        tokio::spawn(Self::process_loop(
-            rx,
-            jobs,
-            config,
-            total_pages_crawled,
-            total_pages_errored,
-            event_senders,
-        ));
+        tokio::spawn(Self::process_loop(rx, ctx));
 
         manager
     }
@@ -226,33 +795,215 @@ impl JobManager {
         }
     }
 
-    /// Submit a new crawl job. Returns the job_id.
+    /// Submit a new crawl job. Returns the job_id. A cancel left over from an
+    /// earlier run under the same id is cleared so it can't stop this one.
     pub async fn submit(&self, payload: CrawlJobPayload) -> String {
+        warn_on_store_error(
+            &payload.job_id,
+            self.store.clear_cancel(&payload.job_id).await,
+        );
+        self.enqueue(payload, None).await
+    }
+
+    /// Resume a job from its on-disk checkpoint. Fails when there is no
+    /// checkpoint, or when the job is still running in this process,
+    /// including a cancelled job whose task hasn't wound down yet.
+    pub async fn resume(&self, job_id: &str) -> Result<(), ResumeError> {
+        if !is_valid_job_id(job_id) {
+            return Err(ResumeError::NotFound(job_id.to_string()));
+        }
+        if let Some(entry) = self.jobs.read().await.get(job_id) {
+            let status = entry.lock().await.status;
+            if matches!(status, JobStatusKind::Queued | JobStatusKind::Crawling)
+                || self.scheduler.lock().unwrap().running.contains(job_id)
+            {
+                return Err(ResumeError::AlreadyRunning(job_id.to_string()));
+            }
+        }
+
+        let path = CrawlCheckpoint::path_for(&self.checkpoint_dir(), job_id);
+        let checkpoint =
+            CrawlCheckpoint::load(&path).map_err(|_| ResumeError::NotFound(job_id.to_string()))?;
+        warn_on_store_error(job_id, self.store.clear_cancel(job_id).await);
+        self.enqueue(checkpoint.payload.clone(), Some(checkpoint))
+            .await;
+        Ok(())
+    }
+
+    /// Resume every unfinished job found in the checkpoint directory. Called once
+    /// at startup so a machine restart doesn't lose in-progress crawls. Returns
+    /// the number of jobs resumed.
+    pub async fn resume_unfinished(&self) -> usize {
+        let checkpoints = CrawlCheckpoint::load_all(&self.checkpoint_dir());
+        let count = checkpoints.len();
+        for checkpoint in checkpoints {
+            tracing::info!(
+                job_id = %checkpoint.job_id,
+                batch_index = checkpoint.batch_index,
+                pages_crawled = checkpoint.pages_crawled,
+                "Resuming crawl from checkpoint"
+            );
+            self.enqueue(checkpoint.payload.clone(), Some(checkpoint))
+                .await;
+        }
+
+        // Jobs whose crawl finished but whose batches never reached the API:
+        // keep replaying their outbox. (Resumed jobs flush theirs on the next
+        // delivery.)
+        let outbox = Outbox::new(&self.checkpoint_dir());
+        for job_id in outbox.job_ids() {
+            if self.jobs.read().await.contains_key(&job_id) {
+                continue;
+            }
+            self.redeliver_spooled(&outbox, &job_id).await;
+        }
+        count
+    }
+
+    /// Register a local entry for a finished job with spooled batches and start
+    /// replaying its outbox in the background.
+    async fn redeliver_spooled(&self, outbox: &Outbox, job_id: &str) {
+        let pending = outbox.pending(job_id);
+        let Some(spooled) = pending
+            .first()
+            .and_then(|idx| outbox.load(job_id, *idx).ok())
+        else {
+            return;
+        };
+        let final_stats = pending
+            .last()
+            .and_then(|idx| outbox.load(job_id, *idx).ok())
+            .filter(|s| s.batch.is_final)
+            .map(|s| s.batch.stats);
+        let record = self.store.get(job_id).await.ok().flatten();
+        let status = match record.as_ref().map(|r| r.status) {
+            Some(JobStatusKind::Cancelled) => JobStatusKind::Cancelled,
+            _ => JobStatusKind::Crawling,
+        };
+
+        let entry = Arc::new(Mutex::new(JobEntry {
+            status,
+            stats: record.as_ref().and_then(|r| r.stats.clone()),
+            last_batch_index: record.and_then(|r| r.last_batch_index),
+            undelivered_batches: pending.len(),
+            cancel_token: CancellationToken::new(),
+        }));
+        self.jobs
+            .write()
+            .await
+            .insert(job_id.to_string(), entry.clone());
+
+        tracing::info!(
+            job_id = %job_id,
+            undelivered = pending.len(),
+            "Redelivering spooled callback batches"
+        );
+        let delivery = CallbackDelivery {
+            job_id: job_id.to_string(),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("Failed to build callback client"),
+            callback_url: spooled.callback_url,
+            secret: self.config.shared_secret.clone(),
+            outbox: outbox.clone(),
+            store: self.store.clone(),
+            entry,
+            given_up: Arc::new(AtomicBool::new(false)),
+        };
+        tokio::spawn(delivery.redeliver_until_drained(final_stats));
+    }
+
+    fn checkpoint_dir(&self) -> PathBuf {
+        PathBuf::from(&self.config.checkpoint_dir)
+    }
+
+    /// Register a job entry as `Queued` and hand it to the processing loop.
+    async fn enqueue(
+        &self,
+        payload: CrawlJobPayload,
+        resume_from: Option<CrawlCheckpoint>,
+    ) -> String {
         let job_id = payload.job_id.clone();
+        let cancel_token = CancellationToken::new();
 
         let entry = Arc::new(Mutex::new(JobEntry {
             status: JobStatusKind::Queued,
             stats: None,
-            cancel_token: CancellationToken::new(),
+            last_batch_index: resume_from
+                .as_ref()
+                .and_then(|cp| cp.batch_index.checked_sub(1)),
+            undelivered_batches: Outbox::new(&self.checkpoint_dir()).pending(&job_id).len(),
+            cancel_token: cancel_token.clone(),
         }));
 
         self.jobs.write().await.insert(job_id.clone(), entry);
+        warn_on_store_error(
+            &job_id,
+            self.store
+                .set_status(&job_id, JobStatusKind::Queued, None)
+                .await,
+        );
 
-        if let Err(e) = self.tx.send(payload).await {
+        if let Err(e) = self
+            .tx
+            .send(QueuedJob {
+                payload,
+                resume_from,
+                cancel_token,
+            })
+            .await
+        {
             tracing::error!("Failed to enqueue job: {}", e);
         }
 
         job_id
     }
 
-    /// Cancel a running job by its ID.
-    pub async fn cancel(&self, job_id: &str) {
-        let jobs = self.jobs.read().await;
-        if let Some(entry) = jobs.get(job_id) {
-            let mut e = entry.lock().await;
-            e.cancel_token.cancel();
-            e.status = JobStatusKind::Cancelled;
+    /// Cancel a job by its ID. A job still waiting for a crawl slot is removed
+    /// from the queue and never starts; a running job stops and sends its
+    /// partial results. The request is also persisted to the job store, so an
+    /// instance running the job elsewhere picks it up. Fails for a job neither
+    /// this process nor the store knows, and for one that already finished.
+    pub async fn cancel(&self, job_id: &str) -> Result<(), CancelError> {
+        let mut was_queued = false;
+        let local = self.jobs.read().await.get(job_id).cloned();
+        match local {
+            Some(entry) => {
+                let mut e = entry.lock().await;
+                if is_finished(e.status) {
+                    return Err(CancelError::AlreadyFinished(job_id.to_string()));
+                }
+                was_queued = e.status == JobStatusKind::Queued;
+                e.cancel_token.cancel();
+                e.status = JobStatusKind::Cancelled;
+            }
+            // Possibly running on another instance.
+            None => match self.store.get(job_id).await {
+                Ok(Some(record)) if is_finished(record.status) => {
+                    return Err(CancelError::AlreadyFinished(job_id.to_string()));
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => return Err(CancelError::NotFound(job_id.to_string())),
+                Err(e) => {
+                    tracing::warn!(job_id = %job_id, error = %e, "Job store read failed");
+                    return Err(CancelError::NotFound(job_id.to_string()));
+                }
+            },
         }
+        warn_on_store_error(job_id, self.store.request_cancel(job_id).await);
+
+        if was_queued {
+            self.scheduler
+                .lock()
+                .unwrap()
+                .waiting
+                .retain(|j| j.payload.job_id != job_id);
+        }
+        // A cancelled job must not be picked up again on restart. A running
+        // job stops saving checkpoints once its token is cancelled.
+        let _ = std::fs::remove_file(CrawlCheckpoint::path_for(&self.checkpoint_dir(), job_id));
+        Ok(())
     }
 
     /// Get the current status of a job.
@@ -260,17 +1011,60 @@ impl JobManager {
         let jobs = self.jobs.read().await;
         if let Some(entry) = jobs.get(job_id) {
             let e = entry.lock().await;
-            JobStatus {
+            let queue_position = if e.status == JobStatusKind::Queued {
+                self.scheduler.lock().unwrap().position(job_id)
+            } else {
+                None
+            };
+            let estimated_start_s = queue_position.map(|position| {
+                let avg_ms = self.avg_job_ms.load(Ordering::Relaxed);
+                let avg_secs = if avg_ms == 0 {
+                    DEFAULT_JOB_ESTIMATE_SECS
+                } else {
+                    avg_ms.div_ceil(1000)
+                };
+                estimate_start_secs(position, self.config.max_concurrent_jobs, avg_secs)
+            });
+            return JobStatus {
                 job_id: job_id.to_string(),
                 status: e.status,
                 stats: e.stats.clone(),
+                queue_position,
+                estimated_start_s,
+                last_batch_index: e.last_batch_index,
+                undelivered_batches: (e.undelivered_batches > 0).then_some(e.undelivered_batches),
+            };
+        }
+        drop(jobs);
+
+        // Not known to this process: fall back to the durable record, which
+        // covers jobs from before a restart or running on another instance.
+        let record = match self.store.get(job_id).await {
+            Ok(record) => record,
+            Err(e) => {
+                tracing::warn!(job_id = %job_id, error = %e, "Job store read failed");
+                None
             }
-        } else {
-            JobStatus {
+        };
+        match record {
+            Some(record) => JobStatus {
+                job_id: job_id.to_string(),
+                status: record.status,
+                stats: record.stats,
+                queue_position: None,
+                estimated_start_s: None,
+                last_batch_index: record.last_batch_index,
+                undelivered_batches: None,
+            },
+            None => JobStatus {
                 job_id: job_id.to_string(),
                 status: JobStatusKind::Pending,
                 stats: None,
-            }
+                queue_position: None,
+                estimated_start_s: None,
+                last_batch_index: None,
+                undelivered_batches: None,
+            },
         }
     }
 
@@ -290,76 +1084,187 @@ impl JobManager {
         tx.subscribe()
     }
 
-    /// Background loop that takes jobs off the channel and spawns a task for each.
-    async fn process_loop(
-        mut rx: mpsc::Receiver<CrawlJobPayload>,
-        jobs: Arc<RwLock<HashMap<String, Arc<Mutex<JobEntry>>>>>,
-        config: Arc<Config>,
-        total_pages_crawled: Arc<AtomicU64>,
-        total_pages_errored: Arc<AtomicU64>,
-        event_senders: Arc<RwLock<HashMap<String, broadcast::Sender<String>>>>,
-    ) {
-        while let Some(payload) = rx.recv().await {
-            let job_id = payload.job_id.clone();
-            let jobs_clone = jobs.clone();
-            let config_clone = config.clone();
-            let tpc = total_pages_crawled.clone();
-            let tpe = total_pages_errored.clone();
-            let es = event_senders.clone();
+    /// Background loop that takes jobs off the channel and queues them for
+    /// admission. At most `max_concurrent_jobs` crawls run at once; the rest wait
+    /// in FIFO order as `Queued`.
+    async fn process_loop(mut rx: mpsc::Receiver<QueuedJob>, ctx: JobContext) {
+        while let Some(job) = rx.recv().await {
+            ctx.scheduler.lock().unwrap().waiting.push_back(job);
+            Self::dispatch(&ctx);
+        }
+    }
 
-            // Get the job entry (created during submit)
-            let entry = {
-                let map = jobs.read().await;
-                match map.get(&job_id) {
-                    Some(e) => e.clone(),
-                    None => continue,
+    /// Start waiting jobs while crawl slots are free. Jobs cancelled while
+    /// queued are dropped without ever starting.
+    fn dispatch(ctx: &JobContext) {
+        let max_jobs = ctx.config.max_concurrent_jobs.max(1);
+        loop {
+            let job = {
+                let mut sched = ctx.scheduler.lock().unwrap();
+                if sched.running.len() >= max_jobs {
+                    return;
+                }
+                match sched.waiting.pop_front() {
+                    Some(job) if job.cancel_token.is_cancelled() => continue,
+                    Some(job) => {
+                        sched.running.insert(job.payload.job_id.clone());
+                        job
+                    }
+                    None => return,
                 }
             };
+            Self::start_job(ctx.clone(), job);
+        }
+    }
 
-            // Get or create event sender for this job
-            let event_tx = {
-                let mut senders = es.write().await;
-                senders
-                    .entry(job_id.clone())
-                    .or_insert_with(|| {
-                        let (tx, _) = broadcast::channel(64);
-                        tx
-                    })
-                    .clone()
+    /// Spawn the crawl task for an admitted job. When the task ends, however it
+    /// ends, the slot is released and the next waiting job is dispatched.
+    fn start_job(ctx: JobContext, job: QueuedJob) {
+        tokio::spawn(async move {
+            let _slot = SlotGuard {
+                ctx: ctx.clone(),
+                job_id: job.payload.job_id.clone(),
             };
+            let QueuedJob {
+                payload,
+                resume_from,
+                cancel_token,
+            } = job;
+            let job_id = payload.job_id.clone();
+            let started = Instant::now();
 
-            tokio::spawn(async move {
-                Self::run_crawl_job(payload, entry, config_clone, tpc, tpe, event_tx.clone()).await;
+            // Get the job entry (created during submit)
+            let entry = ctx.jobs.read().await.get(&job_id).cloned();
+
+            // A cancel may have been requested elsewhere while the job waited
+            // (or while this instance was down, for a resumed job).
+            let cancelled_elsewhere = ctx.store.cancel_requested(&job_id).await.unwrap_or(false);
+
+            match entry {
+                Some(entry) if cancelled_elsewhere => {
+                    cancel_token.cancel();
+                    entry.lock().await.status = JobStatusKind::Cancelled;
+                    let _ = std::fs::remove_file(CrawlCheckpoint::path_for(
+                        std::path::Path::new(&ctx.config.checkpoint_dir),
+                        &job_id,
+                    ));
+                }
+                Some(entry) => {
+                    let watcher_done = CancellationToken::new();
+                    if ctx.store.is_shared() {
+                        tokio::spawn(Self::watch_cancel_requests(
+                            ctx.store.clone(),
+                            job_id.clone(),
+                            entry.clone(),
+                            cancel_token,
+                            watcher_done.clone(),
+                        ));
+                    }
 
-                // Remove event sender on completion
-                let mut senders = es.write().await;
-                senders.remove(&job_id);
+                    // Get or create event sender for this job
+                    let event_tx = {
+                        let mut senders = ctx.event_senders.write().await;
+                        senders
+                            .entry(job_id.clone())
+                            .or_insert_with(|| {
+                                let (tx, _) = broadcast::channel(64);
+                                tx
+                            })
+                            .clone()
+                    };
+
+                    Self::run_crawl_job(
+                        payload,
+                        resume_from,
+                        entry,
+                        ctx.config.clone(),
+                        ctx.total_pages_crawled.clone(),
+                        ctx.total_pages_errored.clone(),
+                        event_tx,
+                        ctx.store.clone(),
+                    )
+                    .await;
+                    watcher_done.cancel();
+
+                    // Remove event sender on completion
+                    ctx.event_senders.write().await.remove(&job_id);
+
+                    // Fold this run into the average used for queue estimates.
+                    let elapsed_ms = started.elapsed().as_millis() as u64;
+                    let _ =
+                        ctx.avg_job_ms
+                            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |avg| {
+                                Some(if avg == 0 {
+                                    elapsed_ms
+                                } else {
+                                    (avg * 3 + elapsed_ms) / 4
+                                })
+                            });
+                }
+                None => {}
+            }
+        });
+    }
 
-                // Clean up is not needed -- we keep the entry for status queries.
-                let _ = jobs_clone;
-            });
+    /// Poll a shared job store for a cancel request against a running job,
+    /// until the job finishes (`done`) or is cancelled locally.
+    async fn watch_cancel_requests(
+        store: Arc<JobStore>,
+        job_id: String,
+        entry: Arc<Mutex<JobEntry>>,
+        cancel_token: CancellationToken,
+        done: CancellationToken,
+    ) {
+        loop {
+            tokio::select! {
+                _ = done.cancelled() => return,
+                _ = cancel_token.cancelled() => return,
+                _ = tokio::time::sleep(Duration::from_secs(CANCEL_POLL_INTERVAL_SECS)) => {}
+            }
+            if let Ok(true) = store.cancel_requested(&job_id).await {
+                tracing::info!(job_id = %job_id, "Cancel requested through job store");
+                entry.lock().await.status = JobStatusKind::Cancelled;
+                cancel_token.cancel();
+                return;
+            }
         }
     }
 
-    /// Execute the actual crawl job with concurrent page workers.
+    /// Execute the actual crawl job with concurrent page workers. With
+    /// `resume_from`, the frontier, counters and batch index are restored from
+    /// the checkpoint instead of being seeded from scratch.
+    #[allow(clippy::too_many_arguments)]
     async fn run_crawl_job(
         payload: CrawlJobPayload,
+        resume_from: Option<CrawlCheckpoint>,
         entry: Arc<Mutex<JobEntry>>,
         config: Arc<Config>,
         total_pages_crawled: Arc<AtomicU64>,
         total_pages_errored: Arc<AtomicU64>,
         event_tx: broadcast::Sender<String>,
+        store: Arc<JobStore>,
     ) {
         let cancel_token = {
             let e = entry.lock().await;
             e.cancel_token.clone()
         };
 
-        // Mark as crawling
-        {
-            let mut e = entry.lock().await;
-            e.status = JobStatusKind::Crawling;
+        if !entry.lock().await.start() {
+            tracing::info!(job_id = %payload.job_id, "Job cancelled before it started");
+            warn_on_store_error(
+                &payload.job_id,
+                store
+                    .set_status(&payload.job_id, JobStatusKind::Cancelled, None)
+                    .await,
+            );
+            return;
         }
+        warn_on_store_error(
+            &payload.job_id,
+            store
+                .set_status(&payload.job_id, JobStatusKind::Crawling, None)
+                .await,
+        );
 
         let job_start = Instant::now();
         let crawl_config = payload.config.clone();
@@ -367,27 +1272,33 @@ impl JobManager {
         // Set up components — use known_rate_limit hint if provided
         let rate_per_sec = if let Some(known_rate) = crawl_config.known_rate_limit {
             known_rate.max(1)
-        } else if crawl_config.rate_limit_ms > 0 {
-            (1000 / crawl_config.rate_limit_ms).max(1)
         } else {
-            2
+            1000u32
+                .checked_div(crawl_config.rate_limit_ms)
+                .unwrap_or(2)
+                .max(1)
         };
 
+        let egress = EgressPolicy::from_config(&config);
         let fetcher = RateLimitedFetcher::new(
             rate_per_sec,
             crawl_config.timeout_s as u64,
             &crawl_config.user_agent,
+            egress.clone(),
         );
 
         let lighthouse_runner = if crawl_config.run_lighthouse {
-            Some(LighthouseRunner::new(
-                config.max_concurrent_lighthouse,
-                config.lighthouse_mode.clone(),
-                config.pagespeed_api_key.clone(),
-                config.max_lighthouse_pages,
-                config.lighthouse_timeout_s,
-                config.lighthouse_failure_threshold,
-            ))
+            Some(
+                LighthouseRunner::new(
+                    config.max_concurrent_lighthouse,
+                    config.lighthouse_mode.clone(),
+                    config.pagespeed_api_key.clone(),
+                    config.max_lighthouse_pages,
+                    config.lighthouse_timeout_s,
+                    config.lighthouse_failure_threshold,
+                )
+                .with_egress(egress.clone()),
+            )
         } else {
             None
         };
@@ -421,150 +1332,197 @@ impl JobManager {
             bucket: config.r2_bucket.clone(),
         }));
 
-        // Determine domain from first seed URL for robots check
-        let domain = crawl_config
-            .seed_urls
-            .first()
-            .and_then(|u| Url::parse(u).ok())
-            .and_then(|u| u.host_str().map(|h| h.to_string()));
-
-        // SiteContext pieces, collected below and assembled via `build_site_context`
-        // right before the crawl engine is constructed.
-        let mut sitemap_analysis: Option<SitemapAnalysis> = None;
-        let mut has_llms_txt = false;
-
-        // Always fetch robots.txt for sitemap discovery and bot analysis.
-        // The checker feeds two independent things: (1) SiteContext's `ai_crawlers_blocked`
-        // bot analysis — always, regardless of respect_robots — and (2) per-URL crawl
-        // blocking, only when respect_robots. Keep the checker bound here so
-        // `build_site_context` can read it even when we don't enforce it for crawling.
-        let mut sitemap_urls_from_robots: Vec<String> = Vec::new();
-        let robots_checker: Option<RobotsChecker> = if let Some(ref d) = domain {
-            RobotsChecker::new(d).await.ok()
-        } else {
-            None
-        };
-        if let Some(ref checker) = robots_checker {
-            sitemap_urls_from_robots = checker.sitemaps.clone();
-        }
-
-        // Fallback: if robots.txt declared no `Sitemap:`, probe the well-known
-        // /sitemap.xml (Googlebot does this too). Sites that omit the directive
-        // — e.g. families.care — would otherwise get zero sitemap coverage and
-        // fall back to homepage BFS, never reaching deep page types like
-        // /us/location/* that aren't shallowly linked.
-        if sitemap_urls_from_robots.is_empty() {
-            if let Some(ref d) = domain {
-                sitemap_urls_from_robots.push(format!("https://{}/sitemap.xml", d));
-            }
-        }
-
-        // Fetch and parse sitemaps discovered in robots.txt
-        if !sitemap_urls_from_robots.is_empty() {
-            if let Some(ref d) = domain {
-                let max_child = (crawl_config.max_pages as usize / 100).max(10).min(100);
-                let sitemap_result = crate::crawler::sitemap::fetch_sitemap_urls(
-                    &sitemap_urls_from_robots,
-                    d,
-                    max_child,
-                    3, // max recursion depth for nested sitemap indexes
-                )
-                .await;
-
-                tracing::info!(
-                    job_id = %payload.job_id,
-                    sitemap_urls = sitemap_result.urls.len(),
-                    total_in_sitemap = sitemap_result.total_count,
-                    "Sitemap discovery complete"
-                );
-
-                sitemap_analysis = Some(SitemapAnalysis {
-                    is_valid: true,
-                    url_count: sitemap_result.total_count,
-                    stale_url_count: 0,
-                    discovered_page_count: sitemap_result.urls.len() as u32,
-                });
-
-                // Filter sitemap URLs through robots.txt only when we're enforcing it.
-                let sitemap_seed_urls: Vec<String> = match robots_checker.as_ref() {
-                    Some(checker) if crawl_config.respect_robots => sitemap_result
-                        .urls
-                        .into_iter()
-                        .filter(|u| checker.is_allowed(u, &crawl_config.user_agent))
-                        .collect(),
-                    _ => sitemap_result.urls,
-                };
-
-                // These will be added to the frontier below
-                sitemap_urls_from_robots = sitemap_seed_urls;
+        // robots.txt, sitemaps and llms.txt are discovered for every host known
+        // up front: the seeds' and any the scope lists. The first is the primary
+        // host, whose site context also covers hosts only found through links.
+        let known_hosts = scope::known_hosts(&crawl_config.scope, &crawl_config.seed_urls);
+        let domain = known_hosts.first().cloned();
+        let mut site_context: Option<SiteContext> = None;
+        let mut host_site_contexts: HashMap<String, SiteContext> = HashMap::new();
+        let mut host_robots = HostRobots::new(egress.clone());
+        let mut sitemap_entries: Vec<SitemapEntry> = Vec::new();
+        let mut sitemap_urls: Vec<String> = Vec::new();
+        for host in &known_hosts {
+            let on_host = ScopeMatcher::single_host(host);
+            let seeds_on_host: Vec<String> = crawl_config
+                .seed_urls
+                .iter()
+                .filter(|u| on_host.contains(u))
+                .cloned()
+                .collect();
+            let discovery = discover_host(
+                host,
+                seeds_on_host,
+                &crawl_config,
+                &egress,
+                &fetcher,
+                &payload.job_id,
+            )
+            .await;
+            sitemap_entries.extend(discovery.sitemap_entries);
+            sitemap_urls.extend(discovery.sitemap_urls);
+            if let Some(checker) = discovery.robots {
+                host_robots.insert(host, checker);
             }
-        }
-
-        // Check llms.txt
-        if crawl_config.check_llms_txt {
-            if let Some(ref d) = domain {
-                if crate::crawler::robots::fetch_llms_txt(d).await.is_some() {
-                    has_llms_txt = true;
-                }
+            if site_context.is_none() {
+                site_context = Some(discovery.site_context);
+            } else {
+                host_site_contexts.insert(host.clone(), discovery.site_context);
             }
         }
-
-        // Assemble the scorer's site-level inputs from the fetched signals. Borrows the
-        // robots checker (for root bot analysis) before it's handed to the engine below.
-        let site_context = build_site_context(
-            robots_checker.as_ref(),
-            domain.as_deref(),
-            has_llms_txt,
-            sitemap_analysis,
+        // Compares the sitemaps with what the crawl finds, for the final batch.
+        let mut reconciler = SitemapReconciler::new(
+            sitemap_urls.iter().map(String::as_str),
+            crawl_config.canonicalization.clone(),
         );
 
         // Only enforce robots.txt for per-URL crawl blocking when respect_robots is set.
-        let robots = if crawl_config.respect_robots {
-            robots_checker
-        } else {
-            None
-        };
+        // Hosts first seen mid-crawl have theirs fetched on first use.
+        let robots = crawl_config.respect_robots.then_some(host_robots);
 
         // Initialize CrawlEngine wrapped in Arc for sharing across workers
-        let engine = Arc::new(CrawlEngine::new(
-            fetcher,
-            lighthouse_runner,
-            js_renderer,
-            storage,
-            robots,
-            crawl_config.clone(),
-            Some(site_context),
-        ));
+        let engine = Arc::new(
+            CrawlEngine::new(
+                fetcher,
+                lighthouse_runner,
+                js_renderer,
+                storage,
+                robots,
+                crawl_config.clone(),
+                site_context,
+            )
+            .with_host_site_contexts(host_site_contexts),
+        );
 
         // Reuse a single HTTP client for all callbacks
         let callback_client = reqwest::Client::builder()
             .timeout(Duration::from_secs(30))
             .build()
             .expect("Failed to build callback client");
+        let delivery = CallbackDelivery {
+            job_id: payload.job_id.clone(),
+            client: callback_client.clone(),
+            callback_url: payload.callback_url.clone(),
+            secret: config.shared_secret.clone(),
+            outbox: Outbox::new(std::path::Path::new(&config.checkpoint_dir)),
+            store: store.clone(),
+            entry: entry.clone(),
+            given_up: Arc::new(AtomicBool::new(false)),
+        };
+
+        let checkpoint_path = CrawlCheckpoint::path_for(
+            std::path::Path::new(&config.checkpoint_dir),
+            &payload.job_id,
+        );
 
-        // Initialize frontier with seed URLs + sitemap-discovered URLs
-        let mut frontier = Frontier::new(&crawl_config.seed_urls, crawl_config.max_depth);
-        if !sitemap_urls_from_robots.is_empty() {
+        // Initialize frontier with seed URLs + sitemap-discovered URLs, or restore
+        // it (with counters and batch index) from the checkpoint when resuming.
+        let mut pages_crawled: u32 = 0;
+        let mut pages_errored: u32 = 0;
+        let mut content_hashes_seen: HashMap<String, String> = HashMap::new();
+        let mut near_duplicates = NearDuplicateIndex::default();
+        let near_duplicate_distance = simhash::max_distance(crawl_config.near_duplicate_threshold);
+        // Internal links of every delivered page, for the final site summary.
+        let mut site_graph = SiteGraph::new(crawl_config.canonicalization.clone());
+        let mut batch_index: u32 = 0;
+        let resuming = resume_from.is_some();
+        // Rules were validated when the job was submitted.
+        let url_filter = UrlFilter::from_config(&crawl_config).unwrap_or_else(|e| {
+            tracing::warn!(job_id = %payload.job_id, error = %e, "Ignoring invalid URL rules");
+            UrlFilter::default()
+        });
+        let mut frontier = if let Some(checkpoint) = resume_from {
+            tracing::info!(
+                job_id = %payload.job_id,
+                batch_index = checkpoint.batch_index,
+                pending = checkpoint.pending_urls.len(),
+                "Restoring frontier from checkpoint"
+            );
+            pages_crawled = checkpoint.pages_crawled as u32;
+            pages_errored = checkpoint.pages_errored;
+            content_hashes_seen = checkpoint.content_hashes;
+            near_duplicates = checkpoint.near_duplicates;
+            batch_index = checkpoint.batch_index;
+            Frontier::restore(
+                checkpoint.seen_urls,
+                checkpoint.pending_urls,
+                crawl_config.max_depth,
+                crawl_config.canonicalization.clone(),
+            )
+            .with_filter(url_filter)
+            .with_filtered_counts(checkpoint.filtered_by_rule)
+            .with_trap_detection(
+                TrapDetector::new(crawl_config.trap_detection.clone())
+                    .with_state(checkpoint.trap_state),
+            )
+            .with_host_budget(crawl_config.max_pages_per_host, checkpoint.pages_per_host)
+        } else {
+            Frontier::new(
+                &crawl_config.seed_urls,
+                crawl_config.max_depth,
+                crawl_config.canonicalization.clone(),
+            )
+            .with_filter(url_filter)
+            .with_trap_detection(TrapDetector::new(crawl_config.trap_detection.clone()))
+            .with_host_budget(crawl_config.max_pages_per_host, HashMap::new())
+        }
+        .with_scope(ScopeMatcher::new(
+            &crawl_config.scope,
+            &crawl_config.seed_urls,
+        ));
+        if !resuming && !sitemap_entries.is_empty() {
             let cap = crawl_config.max_pages as usize;
+            // Each URL's frontier priority comes from its sitemap `priority` and
+            // `lastmod`. Sorting by it first means every prefix bucket below
+            // hands out its most important, freshest URLs first.
+            // Entries are keyed by canonical URL, so `?utm_source=` variants of
+            // one page share a slot (at the best priority among them).
+            let now = SystemTime::now();
+            let mut priorities: HashMap<String, u32> = HashMap::new();
+            let mut in_sitemap_order = Vec::new();
+            for entry in &sitemap_entries {
+                let Some(url) = frontier.canonicalize(&entry.loc) else {
+                    continue;
+                };
+                let priority = entry.frontier_priority(now);
+                match priorities.entry(url) {
+                    std::collections::hash_map::Entry::Occupied(mut e) => {
+                        *e.get_mut() = (*e.get()).max(priority);
+                    }
+                    std::collections::hash_map::Entry::Vacant(e) => {
+                        in_sitemap_order.push(e.key().clone());
+                        e.insert(priority);
+                    }
+                }
+            }
+            // Apply the include/exclude rules before sampling, so filtered URLs
+            // don't take up the page budget.
+            let mut by_priority: Vec<String> = in_sitemap_order
+                .into_iter()
+                .filter(|u| frontier.admits(u))
+                .collect();
+            by_priority.sort_by_key(|u| std::cmp::Reverse(priorities[u]));
             // Fair-sample across path prefixes (e.g. /us/location, /us/providers,
             // /us/category) so a budget smaller than the sitemap doesn't fill up
             // with whatever appears first in sitemap order — which dropped deep
             // page types (location pages) entirely.
-            let to_add: Vec<String> = fair_sample_by_prefix(sitemap_urls_from_robots, cap);
+            let to_add: Vec<String> = fair_sample_by_prefix(by_priority, cap);
             tracing::info!(
                 job_id = %payload.job_id,
                 added = to_add.len(),
                 "Adding sitemap URLs to frontier"
             );
-            frontier.add_discovered_with_priority(&to_add, 0, 80);
+            for url in &to_add {
+                frontier.add_listed(url, priorities[url]);
+            }
         }
         let max_workers = config.max_concurrent_fetches;
 
-        let mut pages_crawled: u32 = 0;
-        let mut pages_errored: u32 = 0;
         let mut batch_pages: Vec<CrawlPageResult> = Vec::new();
-        let mut content_hashes_seen: HashSet<String> = HashSet::new();
-        let mut batch_index: u32 = 0;
+        let mut batch_outcomes: Vec<PageOutcome> = Vec::new();
+        // URLs popped from the frontier whose results haven't been delivered yet.
+        // Checkpoints re-queue them so a restart doesn't drop them.
+        let mut in_flight: HashMap<String, u32> = HashMap::new();
         let mut last_batch_time = Instant::now();
         let mut join_set: JoinSet<(String, u32, Result<CrawlPageResult, CrawlEngineError>)> =
             JoinSet::new();
@@ -577,6 +1535,7 @@ impl JobManager {
                     break;
                 }
                 if let Some((url, depth)) = frontier.next() {
+                    in_flight.insert(url.clone(), depth);
                     let eng = engine.clone();
                     let jid = payload.job_id.clone();
                     join_set.spawn(async move {
@@ -599,57 +1558,48 @@ impl JobManager {
                 _ = cancel_token.cancelled() => {
                     tracing::info!(job_id = %payload.job_id, "Job cancelled — sending partial results");
 
-                    // Send any accumulated pages as a final batch
-                    if !batch_pages.is_empty() {
-                        let cancel_batch = CrawlResultBatch {
-                            job_id: payload.job_id.clone(),
-                            batch_index,
-                            is_final: true,
-                            pages: std::mem::take(&mut batch_pages),
-                            stats: CrawlStats {
-                                pages_found: frontier.pending_count() as u32
-                                    + pages_crawled
-                                    + pages_errored,
-                                pages_crawled,
-                                pages_errored,
-                                elapsed_s: job_start.elapsed().as_secs_f64(),
-                            },
-                        };
-
-                        Self::send_callback(
-                            &callback_client,
-                            &payload.callback_url,
-                            &cancel_batch,
-                            &config.shared_secret,
-                        )
-                        .await;
-
-                        let backlink_entries = collect_backlink_entries(&cancel_batch.pages);
-                        Self::send_backlinks(
-                            &callback_client,
-                            &config.api_base_url,
-                            backlink_entries,
-                            &config.shared_secret,
-                        )
-                        .await;
-                    }
-
+                    // Accumulated pages go out with the final batch below, so a
+                    // cancelled job emits exactly one `is_final` batch.
                     join_set.abort_all();
                     break;
                 }
                 Some(result) = join_set.join_next() => {
+                    if let Ok((ref url, _, _)) = result {
+                        in_flight.remove(url);
+                    }
                     match result {
-                        Ok((url, depth, Ok(page_result))) => {
-                            // Content deduplication: skip pages with a hash we've already seen
-                            if !page_result.content_hash.is_empty()
-                                && !content_hashes_seen.insert(page_result.content_hash.clone())
-                            {
+                        Ok((url, depth, Ok(mut page_result))) => {
+                            reconciler.record_page(&url, &page_result);
+                            // Content deduplication: skip pages with a hash we've already
+                            // seen, reporting which page they duplicate.
+                            let original = (!page_result.content_hash.is_empty())
+                                .then(|| content_hashes_seen.get(&page_result.content_hash))
+                                .flatten();
+                            if let Some(original) = original {
                                 tracing::debug!(
                                     url = %url,
                                     hash = %page_result.content_hash,
                                     "Skipping duplicate content"
                                 );
+                                batch_outcomes.push(PageOutcome {
+                                    url: page_result.url.clone(),
+                                    outcome: PageOutcomeKind::Duplicate,
+                                    error_kind: None,
+                                    error: None,
+                                    status_code: Some(page_result.status_code),
+                                    robots_rule: None,
+                                    duplicate_of: Some(original.clone()),
+                                });
                             } else {
+                                if !page_result.content_hash.is_empty() {
+                                    content_hashes_seen.insert(
+                                        page_result.content_hash.clone(),
+                                        page_result.url.clone(),
+                                    );
+                                }
+                                // Byte-identical pages were dropped above; near-identical
+                                // ones are delivered, flagged with their cluster.
+                                near_duplicates.assign(&mut page_result, near_duplicate_distance);
                                 if crawl_config.extract_links {
                                     frontier.add_discovered(
                                         &page_result.extracted.internal_links,
@@ -663,16 +1613,18 @@ impl JobManager {
                                 // Canonical URL resolution: add canonical to frontier if it differs
                                 if let Some(ref canonical) = page_result.canonical_url {
                                     if !canonical.is_empty() && canonical != &url {
-                                        frontier.add_discovered(&[canonical.clone()], depth);
+                                        frontier.add_discovered(std::slice::from_ref(canonical), depth);
                                     }
                                 }
                                 // SPA detection: use JS renderer if is_spa hint is set,
                                 // or if first page has few links and low word count
                                 let spa_hint = crawl_config.is_spa == Some(true) && pages_crawled == 0;
-                                if spa_hint
-                                    || (pages_crawled == 0
-                                        && page_result.word_count < 50
-                                        && page_result.extracted.internal_links.len() < 3)
+                                // Unchanged (304) pages carry no parsed content to judge by.
+                                if !page_result.unchanged
+                                    && (spa_hint
+                                        || (pages_crawled == 0
+                                            && page_result.word_count < 50
+                                            && page_result.extracted.internal_links.len() < 3))
                                 {
                                     tracing::info!(
                                         job_id = %payload.job_id,
@@ -703,18 +1655,33 @@ impl JobManager {
                                     }
                                 }
 
+                                site_graph.record_page(&url, &page_result);
                                 batch_pages.push(page_result);
                                 pages_crawled += 1;
                                 total_pages_crawled.fetch_add(1, Ordering::Relaxed);
                             }
                         }
-                        Ok((_url, _, Err(CrawlEngineError::BlockedByRobots(u)))) => {
-                            tracing::debug!(url = %u, "Blocked by robots.txt");
+                        Ok((_, _, Err(CrawlEngineError::BlockedByRobots { url, rule }))) => {
+                            tracing::debug!(url = %url, rule = %rule, "Blocked by robots.txt");
+                            let outcome = PageOutcome {
+                                url,
+                                outcome: PageOutcomeKind::Blocked,
+                                error_kind: None,
+                                error: None,
+                                status_code: None,
+                                robots_rule: Some(rule),
+                                duplicate_of: None,
+                            };
+                            reconciler.record_outcome(&outcome);
+                            batch_outcomes.push(outcome);
                         }
                         Ok((url, _, Err(e))) => {
                             tracing::warn!(url = %url, error = %e, "Crawl failed");
                             pages_errored += 1;
                             total_pages_errored.fetch_add(1, Ordering::Relaxed);
+                            let outcome = failure_outcome(url, &e);
+                            reconciler.record_outcome(&outcome);
+                            batch_outcomes.push(outcome);
                         }
                         Err(e) => {
                             tracing::error!("Worker task panicked: {}", e);
@@ -733,6 +1700,9 @@ impl JobManager {
                             pages_crawled,
                             pages_errored,
                             elapsed_s: job_start.elapsed().as_secs_f64(),
+                            effective_rate_per_s: None,
+                            effective_rate_per_host: Default::default(),
+                            filtered_by_rule: Default::default(),
                         });
                     }
 
@@ -740,12 +1710,22 @@ impl JobManager {
                         batch_pages.len() >= config.batch_page_threshold
                             || last_batch_time.elapsed().as_secs() >= config.batch_interval_secs;
 
-                    if should_send_batch && !batch_pages.is_empty() {
+                    if should_send_batch && !(batch_pages.is_empty() && batch_outcomes.is_empty()) {
+                        let pages = std::mem::take(&mut batch_pages);
+                        let link_edges_r2_key = Self::upload_link_edges(
+                            &engine.storage,
+                            &payload.job_id,
+                            batch_index,
+                            &pages,
+                            &crawl_config.canonicalization,
+                        )
+                        .await;
                         let batch = CrawlResultBatch {
                             job_id: payload.job_id.clone(),
                             batch_index,
                             is_final: false,
-                            pages: std::mem::take(&mut batch_pages),
+                            pages,
+                            outcomes: std::mem::take(&mut batch_outcomes),
                             stats: CrawlStats {
                                 pages_found: frontier.pending_count() as u32
                                     + pages_crawled
@@ -753,16 +1733,18 @@ impl JobManager {
                                 pages_crawled,
                                 pages_errored,
                                 elapsed_s: job_start.elapsed().as_secs_f64(),
+                                effective_rate_per_s: None,
+                                effective_rate_per_host: Default::default(),
+                                filtered_by_rule: Default::default(),
                             },
+                            sitemap_reconciliation: None,
+                            crawl_traps: Vec::new(),
+                            site_summary: None,
+                            link_edges_r2_key,
+                            near_duplicate_clusters: Vec::new(),
                         };
 
-                        Self::send_callback(
-                            &callback_client,
-                            &payload.callback_url,
-                            &batch,
-                            &config.shared_secret,
-                        )
-                        .await;
+                        delivery.deliver(&batch).await;
 
                         // POST external links to backlinks ingestion endpoint
                         let backlink_entries = collect_backlink_entries(&batch.pages);
@@ -774,6 +1756,13 @@ impl JobManager {
                         )
                         .await;
 
+                        warn_on_store_error(
+                            &payload.job_id,
+                            store
+                                .set_status(&payload.job_id, JobStatusKind::Crawling, Some(&batch.stats))
+                                .await,
+                        );
+
                         batch_index += 1;
                         last_batch_time = Instant::now();
 
@@ -786,45 +1775,98 @@ impl JobManager {
                             "batch_index": batch_index,
                         })).unwrap());
 
-                        // Save checkpoint every 5 batches
-                        if batch_index % 5 == 0 {
-                            let checkpoint = CrawlCheckpoint {
-                                job_id: payload.job_id.clone(),
-                                seen_urls: content_hashes_seen.clone(),
-                                pending_urls: vec![],
-                                pages_crawled: pages_crawled as usize,
-                                batch_index,
-                            };
-                            let _ = checkpoint.save(&CrawlCheckpoint::path_for(&payload.job_id));
+                        // Checkpoint after every delivered batch so a restart resumes
+                        // at `batch_index` without re-crawling delivered pages.
+                        // In-flight URLs are re-queued ahead of the rest. A
+                        // cancelled job saves none: `cancel` removed its file.
+                        let (seen_urls, mut pending_urls) = frontier.snapshot();
+                        pending_urls.splice(
+                            0..0,
+                            in_flight.iter().map(|(u, d)| (u.clone(), *d, u32::MAX)),
+                        );
+                        let checkpoint = CrawlCheckpoint {
+                            job_id: payload.job_id.clone(),
+                            payload: payload.clone(),
+                            seen_urls,
+                            pending_urls,
+                            content_hashes: content_hashes_seen.clone(),
+                            pages_crawled: pages_crawled as usize,
+                            pages_errored,
+                            batch_index,
+                            filtered_by_rule: frontier.filtered_counts().clone(),
+                            trap_state: frontier.traps().state().clone(),
+                            near_duplicates: near_duplicates.snapshot(),
+                            pages_per_host: frontier
+                                .host_counts_requeuing(in_flight.keys().map(String::as_str)),
+                        };
+                        let saved = if cancel_token.is_cancelled() {
+                            Ok(())
+                        } else {
+                            checkpoint.save(&checkpoint_path)
+                        };
+                        if let Err(e) = saved {
+                            tracing::warn!(job_id = %payload.job_id, error = %e, "Failed to save checkpoint");
                         }
                     }
                 }
             }
         }
 
-        // Send final batch
+        // Send final batch. Every host fetched is reported, so a scoped crawl
+        // shows the Crawl-delay or Retry-After of hosts other than the seed's.
+        let effective_rate_per_host = engine.fetcher.effective_rates().await;
+        let effective_rate_per_s = match effective_rate_per_host.values().copied().reduce(f64::min)
+        {
+            Some(rate) => Some(rate),
+            None => match domain.as_deref() {
+                Some(d) => Some(engine.fetcher.effective_rate(d).await),
+                None => None,
+            },
+        };
         let final_stats = CrawlStats {
             pages_found: frontier.pending_count() as u32 + pages_crawled + pages_errored,
             pages_crawled,
             pages_errored,
             elapsed_s: job_start.elapsed().as_secs_f64(),
+            effective_rate_per_s,
+            effective_rate_per_host,
+            filtered_by_rule: frontier.filtered_counts().clone(),
         };
 
+        // Orphans are only conclusive when the crawl reached everything it could:
+        // not cut off by the page budget, cancelled, or resumed without the
+        // link data gathered before the restart.
+        let crawl_partial = resuming || frontier.pending_count() > 0 || cancel_token.is_cancelled();
+        let sitemap_reconciliation =
+            (!reconciler.is_empty()).then(|| reconciler.finish(crawl_partial));
+
+        let link_edges_r2_key = Self::upload_link_edges(
+            &engine.storage,
+            &payload.job_id,
+            batch_index,
+            &batch_pages,
+            &crawl_config.canonicalization,
+        )
+        .await;
+        let mut site_summary = site_graph.finish(&crawl_config.seed_urls, crawl_partial);
+        site_summary.page_metrics_r2_key =
+            Self::upload_page_metrics(&engine.storage, &payload.job_id, &site_summary.pages).await;
+        site_summary.pages.truncate(site_graph::MAX_SUMMARY_PAGES);
         let final_batch = CrawlResultBatch {
             job_id: payload.job_id.clone(),
             batch_index,
             is_final: true,
             pages: batch_pages,
+            outcomes: batch_outcomes,
             stats: final_stats.clone(),
+            sitemap_reconciliation,
+            crawl_traps: frontier.traps().report(),
+            site_summary: Some(site_summary),
+            link_edges_r2_key,
+            near_duplicate_clusters: near_duplicates.clusters(),
         };
 
-        Self::send_callback(
-            &callback_client,
-            &payload.callback_url,
-            &final_batch,
-            &config.shared_secret,
-        )
-        .await;
+        let final_delivered = delivery.deliver(&final_batch).await;
 
         // POST final batch backlinks
         let backlink_entries = collect_backlink_entries(&final_batch.pages);
@@ -844,17 +1886,33 @@ impl JobManager {
             .unwrap(),
         );
 
-        // Update final status
-        {
+        // Update final status. The job is only complete once the API has the
+        // final batch; until then it stays `crawling` with undelivered batches
+        // and the outbox is replayed in the background.
+        let final_status = {
             let mut e = entry.lock().await;
-            if e.status != JobStatusKind::Cancelled {
+            if e.status != JobStatusKind::Cancelled && final_delivered {
                 e.status = JobStatusKind::Complete;
             }
-            e.stats = Some(final_stats);
+            e.stats = Some(final_stats.clone());
+            e.status
+        };
+        warn_on_store_error(
+            &payload.job_id,
+            store
+                .set_status(&payload.job_id, final_status, Some(&final_stats))
+                .await,
+        );
+        if !final_delivered && !delivery.has_given_up() {
+            tracing::warn!(
+                job_id = %payload.job_id,
+                "Final batch not acknowledged; redelivering from outbox"
+            );
+            tokio::spawn(delivery.redeliver_until_drained(Some(final_stats)));
         }
 
-        // Clean up checkpoint file on completion
-        let _ = std::fs::remove_file(CrawlCheckpoint::path_for(&payload.job_id));
+        // Clean up checkpoint file on completion (or cancellation)
+        let _ = std::fs::remove_file(&checkpoint_path);
 
         tracing::info!(
             job_id = %payload.job_id,
@@ -865,21 +1923,44 @@ impl JobManager {
         );
     }
 
-    /// POST a CrawlResultBatch to the callback URL with HMAC-SHA256 authentication.
-    /// Accepts a pre-built client to reuse TCP connections across batches.
+    /// POST a CrawlResultBatch to the callback URL, retrying transient failures
+    /// with exponential backoff. Returns the last error once retries run out.
     async fn send_callback(
         client: &reqwest::Client,
         callback_url: &str,
         batch: &CrawlResultBatch,
         secret: &str,
-    ) {
-        let body = match serde_json::to_string(batch) {
-            Ok(b) => b,
-            Err(e) => {
-                tracing::error!("Failed to serialize batch: {}", e);
-                return;
+    ) -> Result<(), CallbackError> {
+        let mut attempt = 1;
+        loop {
+            match Self::post_callback(client, callback_url, batch, secret).await {
+                Ok(()) => return Ok(()),
+                Err(e) if e.is_retryable() && attempt < CALLBACK_MAX_ATTEMPTS => {
+                    let delay = callback_backoff(attempt);
+                    tracing::warn!(
+                        error = %e,
+                        batch_index = batch.batch_index,
+                        attempt,
+                        retry_in_ms = delay.as_millis() as u64,
+                        "Callback failed; retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
             }
-        };
+        }
+    }
+
+    /// Single POST of a CrawlResultBatch with HMAC-SHA256 authentication.
+    /// Accepts a pre-built client to reuse TCP connections across batches.
+    async fn post_callback(
+        client: &reqwest::Client,
+        callback_url: &str,
+        batch: &CrawlResultBatch,
+        secret: &str,
+    ) -> Result<(), CallbackError> {
+        let body = serde_json::to_string(batch)?;
 
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -894,29 +1975,73 @@ impl JobManager {
         mac.update(body.as_bytes());
         let signature = format!("hmac-sha256={}", hex::encode(mac.finalize().into_bytes()));
 
-        match client
+        let resp = client
             .post(callback_url)
             .header("Content-Type", "application/json")
             .header("X-Timestamp", &timestamp)
             .header("X-Signature", &signature)
             .body(body)
             .send()
+            .await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(CallbackError::Status(status.as_u16()));
+        }
+        tracing::info!(
+            status = status.as_u16(),
+            batch_index = batch.batch_index,
+            is_final = batch.is_final,
+            "Callback sent"
+        );
+        Ok(())
+    }
+
+    /// Upload a batch's part of the internal link graph, returning its key.
+    /// Non-fatal: a failed upload is logged and the batch goes out without it.
+    async fn upload_link_edges(
+        storage: &StorageClient,
+        job_id: &str,
+        batch_index: u32,
+        pages: &[CrawlPageResult],
+        canonicalization: &CanonicalizationPolicy,
+    ) -> Option<String> {
+        let edges = link_graph::link_edges(pages, canonicalization);
+        if edges.is_empty() {
+            return None;
+        }
+        let key = link_graph::edges_key(job_id, batch_index);
+        match storage
+            .upload_json_lines(&key, &link_graph::to_json_lines(&edges))
             .await
         {
-            Ok(resp) => {
-                tracing::info!(
-                    status = resp.status().as_u16(),
-                    batch_index = batch.batch_index,
-                    is_final = batch.is_final,
-                    "Callback sent"
-                );
+            Ok(()) => Some(key),
+            Err(e) => {
+                tracing::warn!(job_id, batch_index, error = %e, "Failed to upload link edges");
+                None
             }
+        }
+    }
+
+    /// Upload the full per-page link metrics; the final batch only lists
+    /// the top pages.
+    async fn upload_page_metrics(
+        storage: &StorageClient,
+        job_id: &str,
+        pages: &[PageLinkMetrics],
+    ) -> Option<String> {
+        if pages.is_empty() {
+            return None;
+        }
+        let key = site_graph::metrics_key(job_id);
+        match storage
+            .upload_json_lines(&key, &link_graph::to_json_lines(pages))
+            .await
+        {
+            Ok(()) => Some(key),
             Err(e) => {
-                tracing::error!(
-                    error = %e,
-                    batch_index = batch.batch_index,
-                    "Failed to send callback"
-                );
+                tracing::warn!(job_id, error = %e, "Failed to upload page link metrics");
+                None
             }
         }
     }
@@ -1032,61 +2157,93 @@ mod tests {
         assert_eq!(path_prefix_key("https://families.care/"), "/");
     }
 
+    #[test]
+    fn estimate_start_counts_waves_of_running_slots() {
+        // With 2 slots, positions 1-2 start after one average job, 3-4 after two.
+        assert_eq!(estimate_start_secs(1, 2, 60), 60);
+        assert_eq!(estimate_start_secs(2, 2, 60), 60);
+        assert_eq!(estimate_start_secs(3, 2, 60), 120);
+        // A zero slot count is treated as one slot rather than dividing by zero.
+        assert_eq!(estimate_start_secs(3, 0, 10), 30);
+    }
+
+    #[test]
+    fn failure_outcome_classifies_fetch_errors() {
+        let err = CrawlEngineError::FetchError(crate::crawler::fetcher::FetchError::TimeoutError(
+            "operation timed out".to_string(),
+        ));
+        let outcome = failure_outcome("https://example.com/slow".to_string(), &err);
+        assert_eq!(outcome.outcome, PageOutcomeKind::Failed);
+        assert_eq!(outcome.error_kind.as_deref(), Some("timeout"));
+        assert!(outcome.error.unwrap().contains("operation timed out"));
+        assert_eq!(outcome.status_code, None);
+
+        // Serialized records carry only the fields relevant to their kind.
+        let json = serde_json::to_value(failure_outcome(
+            "https://example.com/loop".to_string(),
+            &CrawlEngineError::FetchError(crate::crawler::fetcher::FetchError::TooManyRedirects),
+        ))
+        .unwrap();
+        assert_eq!(json["outcome"], "failed");
+        assert_eq!(json["error_kind"], "too_many_redirects");
+        assert!(json.get("duplicate_of").is_none());
+    }
+
+    #[test]
+    fn callback_backoff_doubles_per_attempt() {
+        assert_eq!(callback_backoff(1), Duration::from_millis(500));
+        assert_eq!(callback_backoff(2), Duration::from_millis(1000));
+        assert_eq!(callback_backoff(3), Duration::from_millis(2000));
+        // Large attempt counts are capped instead of overflowing the shift.
+        assert_eq!(callback_backoff(100), Duration::from_millis(500 << 10));
+    }
+
+    #[test]
+    fn only_transient_callback_failures_are_retried() {
+        assert!(CallbackError::Status(503).is_retryable());
+        assert!(CallbackError::Status(429).is_retryable());
+        assert!(CallbackError::Status(408).is_retryable());
+        assert!(!CallbackError::Status(400).is_retryable());
+        assert!(!CallbackError::Status(401).is_retryable());
+    }
+
+    #[test]
+    fn scheduler_position_is_one_based_fifo() {
+        let config: CrawlConfig = serde_json::from_value(serde_json::json!({
+            "seed_urls": ["https://example.com/"],
+            "max_pages": 1,
+            "max_depth": 1
+        }))
+        .unwrap();
+        let mut sched = Scheduler::default();
+        for id in ["a", "b"] {
+            sched.waiting.push_back(QueuedJob {
+                payload: CrawlJobPayload {
+                    job_id: id.to_string(),
+                    callback_url: String::new(),
+                    config: config.clone(),
+                },
+                resume_from: None,
+                cancel_token: CancellationToken::new(),
+            });
+        }
+        assert_eq!(sched.position("a"), Some(1));
+        assert_eq!(sched.position("b"), Some(2));
+        assert_eq!(sched.position("c"), None);
+    }
+
     fn make_page(url: &str, external_links: Vec<ExtractedLink>) -> CrawlPageResult {
         CrawlPageResult {
             url: url.to_string(),
             status_code: 200,
-            title: None,
-            meta_description: None,
-            canonical_url: None,
-            word_count: 0,
             content_hash: "abc".to_string(),
             html_r2_key: "key".to_string(),
             extracted: ExtractedData {
-                h1: vec![],
-                h2: vec![],
-                h3: vec![],
-                h4: vec![],
-                h5: vec![],
-                h6: vec![],
-                schema_types: vec![],
-                internal_links: vec![],
-                external_links: vec![],
                 external_link_details: external_links,
-                images_without_alt: 0,
-                has_robots_meta: false,
-                robots_directives: vec![],
-                og_tags: None,
-                structured_data: None,
-                flesch_score: None,
-                flesch_classification: None,
-                avg_sentence_length: None,
-                text_html_ratio: None,
-                text_length: None,
-                html_length: None,
-                pdf_links: vec![],
-                cors_unsafe_blank_links: 0,
-                cors_mixed_content: 0,
-                cors_has_issues: false,
-                sentence_length_variance: None,
-                top_transition_words: vec![],
-                feed_urls: vec![],
-                hreflang_urls: vec![],
-                hreflang: vec![],
-                analytics_tools: vec![],
-                has_faq_schema: false,
-                has_howto_schema: false,
-                has_breadcrumb_schema: false,
+                ..Default::default()
             },
-            lighthouse: None,
-            js_rendered_link_count: None,
-            site_context: None,
             timing_ms: 100,
-            etag: None,
-            last_modified: None,
-            redirect_chain: vec![],
-            is_cross_domain_redirect: false,
-            redirect_url: None,
+            ..Default::default()
         }
     }
 
@@ -1100,12 +2257,14 @@ mod tests {
                     anchor_text: "check this out".to_string(),
                     rel: "nofollow".to_string(),
                     is_external: true,
+                    placement: None,
                 },
                 ExtractedLink {
                     url: "https://reference.org/docs".to_string(),
                     anchor_text: "documentation".to_string(),
                     rel: "".to_string(),
                     is_external: true,
+                    placement: None,
                 },
             ],
         )];
@@ -1133,6 +2292,7 @@ mod tests {
                 anchor_text: "bad".to_string(),
                 rel: "".to_string(),
                 is_external: true,
+                placement: None,
             }],
         )];
 
@@ -1157,7 +2317,7 @@ mod tests {
     #[test]
     fn blocking_robots_populates_ai_crawlers_blocked() {
         let robots = RobotsChecker::from_content("User-agent: GPTBot\nDisallow: /\n");
-        let ctx = build_site_context(Some(&robots), Some("example.com"), false, None);
+        let ctx = build_site_context(Some(&robots), Some("example.com"), None, None, None, &[]);
 
         assert!(
             !ctx.ai_crawlers_blocked.is_empty(),
@@ -1189,7 +2349,7 @@ mod tests {
              User-agent: GoogleOther\n\
              Allow: /\n",
         );
-        let ctx = build_site_context(Some(&robots), Some("families.care"), false, None);
+        let ctx = build_site_context(Some(&robots), Some("families.care"), None, None, None, &[]);
 
         assert!(
             ctx.ai_crawlers_blocked.is_empty(),
@@ -1198,6 +2358,32 @@ mod tests {
         );
     }
 
+    /// The catalog report covers every AI crawler and probes the sampled sections, so a
+    /// training-only block shows up separately from the retrieval bots it leaves alone.
+    #[test]
+    fn site_context_reports_access_for_the_ai_crawler_catalog() {
+        let robots = RobotsChecker::from_content("User-agent: CCBot\nDisallow: /blog/\n");
+        let known = vec!["https://example.com/blog/post".to_string()];
+        let ctx = build_site_context(Some(&robots), Some("example.com"), None, None, None, &known);
+
+        assert_eq!(ctx.ai_crawler_access.len(), ai_crawlers::AI_CRAWLERS.len());
+        let ccbot = ctx
+            .ai_crawler_access
+            .iter()
+            .find(|a| a.user_agent == "CCBot")
+            .unwrap();
+        assert!(ccbot.root_allowed);
+        assert_eq!(ccbot.blocked_sections, vec!["/blog/"]);
+        assert_eq!(ccbot.blocked_page_types, vec!["blog"]);
+        assert!(ctx
+            .ai_crawler_access
+            .iter()
+            .filter(|a| a.user_agent != "CCBot")
+            .all(|a| a.blocked_sections.is_empty()));
+        // CCBot isn't one of the scored bots, so the score input is unchanged.
+        assert!(ctx.ai_crawlers_blocked.is_empty());
+    }
+
     /// `has_llms_txt` and `has_sitemap` must reflect the presence of their inputs — and
     /// `has_sitemap` is derived from the sitemap analysis being present, never diverging.
     #[test]
@@ -1208,19 +2394,183 @@ mod tests {
             url_count: 42,
             stale_url_count: 0,
             discovered_page_count: 10,
+            ..Default::default()
         };
-        let present = build_site_context(None, None, true, Some(sitemap));
+        let present = build_site_context(
+            None,
+            None,
+            Some(llms_txt_report(false)),
+            None,
+            Some(sitemap),
+            &[],
+        );
         assert!(present.has_llms_txt);
+        assert!(present.llms_txt.is_some());
         assert!(present.has_sitemap);
         assert_eq!(present.sitemap_analysis.map(|s| s.url_count), Some(42));
 
         // Both absent.
-        let absent = build_site_context(None, None, false, None);
+        let absent = build_site_context(None, None, None, None, None, &[]);
         assert!(!absent.has_llms_txt);
+        assert!(absent.llms_txt.is_none());
         assert!(!absent.has_sitemap);
         assert!(absent.sitemap_analysis.is_none());
     }
 
+    fn llms_txt_report(is_placeholder: bool) -> LlmsTxtReport {
+        LlmsTxtReport {
+            url: "https://example.com/llms.txt".into(),
+            size_bytes: 120,
+            token_estimate: 30,
+            is_placeholder,
+            is_valid: !is_placeholder,
+            title: None,
+            summary: None,
+            sections: Vec::new(),
+            link_count: 0,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            external_link_count: 0,
+            links_checked: 0,
+            broken_links: Vec::new(),
+        }
+    }
+
+    fn result_batch(job_id: &str, batch_index: u32) -> CrawlResultBatch {
+        CrawlResultBatch {
+            job_id: job_id.to_string(),
+            batch_index,
+            is_final: false,
+            pages: vec![],
+            outcomes: vec![],
+            stats: CrawlStats {
+                pages_found: 1,
+                pages_crawled: 1,
+                pages_errored: 0,
+                elapsed_s: 0.5,
+                effective_rate_per_s: None,
+                effective_rate_per_host: Default::default(),
+                filtered_by_rule: Default::default(),
+            },
+            sitemap_reconciliation: None,
+            crawl_traps: Vec::new(),
+            site_summary: None,
+            link_edges_r2_key: None,
+            near_duplicate_clusters: Vec::new(),
+        }
+    }
+
+    /// A cancel between dispatch and the task starting must not be
+    /// overwritten by the crawling status (and later reported complete).
+    #[test]
+    fn cancelled_entry_does_not_start() {
+        let mut entry = JobEntry {
+            status: JobStatusKind::Queued,
+            stats: None,
+            last_batch_index: None,
+            undelivered_batches: 0,
+            cancel_token: CancellationToken::new(),
+        };
+        assert!(entry.start());
+        assert_eq!(entry.status, JobStatusKind::Crawling);
+
+        entry.status = JobStatusKind::Cancelled;
+        entry.cancel_token.cancel();
+        assert!(!entry.start());
+        assert_eq!(entry.status, JobStatusKind::Cancelled);
+    }
+
+    /// A 4xx other than 408/429 won't change on retry: the batch and every
+    /// later one are dead-lettered and the job fails.
+    #[tokio::test]
+    async fn rejected_callback_dead_letters_and_fails_the_job() {
+        use std::sync::atomic::AtomicUsize;
+
+        let posts = Arc::new(AtomicUsize::new(0));
+        let api = axum::Router::new()
+            .route(
+                "/callback",
+                axum::routing::post(
+                    |axum::extract::State(posts): axum::extract::State<Arc<AtomicUsize>>| async move {
+                        posts.fetch_add(1, Ordering::SeqCst);
+                        axum::http::StatusCode::BAD_REQUEST
+                    },
+                ),
+            )
+            .with_state(posts.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, api).await.unwrap() });
+
+        let root = std::env::temp_dir().join(format!("delivery-test-{}", uuid::Uuid::new_v4()));
+        let job_id = format!("rejected-{}", uuid::Uuid::new_v4());
+        let store = Arc::new(JobStore::memory());
+        let entry = Arc::new(Mutex::new(JobEntry {
+            status: JobStatusKind::Crawling,
+            stats: None,
+            last_batch_index: None,
+            undelivered_batches: 0,
+            cancel_token: CancellationToken::new(),
+        }));
+        let delivery = CallbackDelivery {
+            job_id: job_id.clone(),
+            client: reqwest::Client::new(),
+            callback_url: format!("http://{addr}/callback"),
+            secret: "secret".to_string(),
+            outbox: Outbox::new(&root),
+            store: store.clone(),
+            entry: entry.clone(),
+            given_up: Arc::new(AtomicBool::new(false)),
+        };
+
+        assert!(!delivery.deliver(&result_batch(&job_id, 0)).await);
+        assert!(!delivery.deliver(&result_batch(&job_id, 1)).await);
+
+        // One POST, no retries, and batch 1 never sent ahead of batch 0.
+        assert_eq!(posts.load(Ordering::SeqCst), 1);
+        assert!(!delivery.outbox.has_pending(&job_id));
+        assert_eq!(delivery.outbox.dead_lettered(&job_id), vec![0, 1]);
+        assert_eq!(entry.lock().await.status, JobStatusKind::Failed);
+        assert_eq!(entry.lock().await.undelivered_batches, 0);
+        let record = store.get(&job_id).await.unwrap().unwrap();
+        assert_eq!(record.status, JobStatusKind::Failed);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// A 503 robots.txt blocks the crawl (RFC 9309) but must not report every
+    /// AI bot as blocked. Also checks robots.txt is fetched over the seed's
+    /// scheme and port rather than https on the default port.
+    #[tokio::test]
+    async fn unreachable_robots_yields_no_blocked_bots() {
+        let site = axum::Router::new().route(
+            "/robots.txt",
+            axum::routing::get(|| async { axum::http::StatusCode::SERVICE_UNAVAILABLE }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, site).await.unwrap() });
+
+        let egress = EgressPolicy::new(vec!["127.0.0.1".parse().unwrap()]);
+        let checker = RobotsChecker::new(&format!("http://{addr}/page"), &egress)
+            .await
+            .unwrap();
+        assert!(checker.unreachable);
+        assert!(!checker.is_allowed(&format!("http://{addr}/"), "GPTBot"));
+
+        let ctx = build_site_context(Some(&checker), Some("127.0.0.1"), None, None, None, &[]);
+        assert!(ctx.ai_crawlers_blocked.is_empty());
+        assert!(ctx.ai_crawler_access.is_empty());
+    }
+
+    /// A soft-404 or empty llms.txt is reported but doesn't count as present.
+    #[test]
+    fn placeholder_llms_txt_is_not_counted_as_present() {
+        let ctx = build_site_context(None, None, Some(llms_txt_report(true)), None, None, &[]);
+        assert!(!ctx.has_llms_txt);
+        assert!(ctx.llms_txt.is_some_and(|r| r.is_placeholder));
+    }
+
     /// Belt-and-suspenders on the match arms: with no robots checker (or no domain) there
     /// is nothing to probe, so `ai_crawlers_blocked` is empty — this is a genuine
     /// "couldn't fetch robots.txt" case, distinct from the #119 "fetched but mis-probed" bug.
@@ -1229,13 +2579,17 @@ mod tests {
         let robots = RobotsChecker::from_content("User-agent: GPTBot\nDisallow: /\n");
 
         // No checker at all.
-        assert!(build_site_context(None, Some("example.com"), false, None)
-            .ai_crawlers_blocked
-            .is_empty());
+        assert!(
+            build_site_context(None, Some("example.com"), None, None, None, &[])
+                .ai_crawlers_blocked
+                .is_empty()
+        );
 
         // Checker present but no domain to build the probe URL from.
-        assert!(build_site_context(Some(&robots), None, false, None)
-            .ai_crawlers_blocked
-            .is_empty());
+        assert!(
+            build_site_context(Some(&robots), None, None, None, None, &[])
+                .ai_crawlers_blocked
+                .is_empty()
+        );
     }
 }
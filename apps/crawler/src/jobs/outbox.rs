@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::models::{is_valid_job_id, CrawlResultBatch};
+
+const OUTBOX_DIR: &str = "callback-outbox";
+const DEAD_LETTER_DIR: &str = "callback-dead-letter";
+
+/// A spooled batch plus where it has to go, so it can be redelivered after a
+/// restart without the original job payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpooledBatch {
+    pub callback_url: String,
+    pub batch: CrawlResultBatch,
+}
+
+/// Durable spool of callback batches the API has not acknowledged yet.
+///
+/// Each undelivered `CrawlResultBatch` is written to
+/// `{root}/callback-outbox/{job_id}/{batch_index}.json` and removed once a
+/// redelivery succeeds. Batches are always replayed in `batch_index` order so
+/// the API never sees a later batch before an earlier one. Batches the API
+/// won't take are moved to `{root}/callback-dead-letter/{job_id}/`, where they
+/// are kept for inspection but never replayed. Job ids that aren't
+/// `is_valid_job_id` are refused rather than joined into a path.
+#[derive(Debug, Clone)]
+pub struct Outbox {
+    dir: PathBuf,
+    dead_letter_dir: PathBuf,
+}
+
+impl Outbox {
+    pub fn new(root: &Path) -> Self {
+        Outbox {
+            dir: root.join(OUTBOX_DIR),
+            dead_letter_dir: root.join(DEAD_LETTER_DIR),
+        }
+    }
+
+    fn job_dir(&self, job_id: &str) -> std::io::Result<PathBuf> {
+        checked_join(&self.dir, job_id)
+    }
+
+    fn batch_path(&self, job_id: &str, batch_index: u32) -> std::io::Result<PathBuf> {
+        Ok(self
+            .job_dir(job_id)?
+            .join(format!("{:010}.json", batch_index)))
+    }
+
+    /// Write a batch to the spool atomically (temp file + rename).
+    pub fn spool(&self, callback_url: &str, batch: &CrawlResultBatch) -> std::io::Result<()> {
+        std::fs::create_dir_all(self.job_dir(&batch.job_id)?)?;
+        let path = self.batch_path(&batch.job_id, batch.batch_index)?;
+        let spooled = SpooledBatch {
+            callback_url: callback_url.to_string(),
+            batch: batch.clone(),
+        };
+        let json = serde_json::to_string(&spooled).map_err(std::io::Error::other)?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)
+    }
+
+    /// Indexes of the spooled batches for a job, in ascending order.
+    pub fn pending(&self, job_id: &str) -> Vec<u32> {
+        self.job_dir(job_id)
+            .map(|dir| batch_indexes(&dir))
+            .unwrap_or_default()
+    }
+
+    pub fn has_pending(&self, job_id: &str) -> bool {
+        !self.pending(job_id).is_empty()
+    }
+
+    pub fn load(&self, job_id: &str, batch_index: u32) -> std::io::Result<SpooledBatch> {
+        let json = std::fs::read_to_string(self.batch_path(job_id, batch_index)?)?;
+        serde_json::from_str(&json).map_err(std::io::Error::other)
+    }
+
+    /// Drop an acknowledged batch, and the job's directory once it is empty.
+    pub fn remove(&self, job_id: &str, batch_index: u32) -> std::io::Result<()> {
+        std::fs::remove_file(self.batch_path(job_id, batch_index)?)?;
+        if !self.has_pending(job_id) {
+            let _ = std::fs::remove_dir_all(self.job_dir(job_id)?);
+        }
+        Ok(())
+    }
+
+    /// How long the oldest spooled batch of a job has been waiting.
+    pub fn oldest_age(&self, job_id: &str) -> Option<Duration> {
+        let oldest = *self.pending(job_id).first()?;
+        std::fs::metadata(self.batch_path(job_id, oldest).ok()?)
+            .and_then(|m| m.modified())
+            .ok()?
+            .elapsed()
+            .ok()
+    }
+
+    /// Move every spooled batch of a job to the dead-letter directory.
+    pub fn dead_letter(&self, job_id: &str) -> std::io::Result<()> {
+        let target = checked_join(&self.dead_letter_dir, job_id)?;
+        std::fs::create_dir_all(&target)?;
+        for batch_index in self.pending(job_id) {
+            let path = self.batch_path(job_id, batch_index)?;
+            std::fs::rename(&path, target.join(path.file_name().unwrap_or_default()))?;
+        }
+        let _ = std::fs::remove_dir_all(self.job_dir(job_id)?);
+        Ok(())
+    }
+
+    /// Indexes of a job's dead-lettered batches, in ascending order.
+    pub fn dead_lettered(&self, job_id: &str) -> Vec<u32> {
+        checked_join(&self.dead_letter_dir, job_id)
+            .map(|dir| batch_indexes(&dir))
+            .unwrap_or_default()
+    }
+
+    /// Jobs with at least one spooled batch.
+    pub fn job_ids(&self) -> Vec<String> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(e) => e,
+            Err(_) => return vec![],
+        };
+        entries
+            .flatten()
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| e.file_name().to_str().map(str::to_string))
+            .filter(|job_id| self.has_pending(job_id))
+            .collect()
+    }
+}
+
+/// `dir/job_id`, or an `InvalidInput` error for an id that could escape `dir`.
+fn checked_join(dir: &Path, job_id: &str) -> std::io::Result<PathBuf> {
+    if !is_valid_job_id(job_id) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid job id {job_id:?}"),
+        ));
+    }
+    Ok(dir.join(job_id))
+}
+
+/// Indexes of the batch files in `dir`, in ascending order.
+fn batch_indexes(dir: &Path) -> Vec<u32> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return vec![],
+    };
+    let mut indexes: Vec<u32> = entries
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name();
+            name.to_str()?.strip_suffix(".json")?.parse().ok()
+        })
+        .collect();
+    indexes.sort_unstable();
+    indexes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CrawlStats;
+
+    fn batch(job_id: &str, batch_index: u32, is_final: bool) -> CrawlResultBatch {
+        CrawlResultBatch {
+            job_id: job_id.to_string(),
+            batch_index,
+            is_final,
+            pages: vec![],
+            outcomes: vec![],
+            stats: CrawlStats {
+                pages_found: 1,
+                pages_crawled: 1,
+                pages_errored: 0,
+                elapsed_s: 0.5,
+                effective_rate_per_s: None,
+                effective_rate_per_host: Default::default(),
+                filtered_by_rule: Default::default(),
+            },
+            sitemap_reconciliation: None,
+            crawl_traps: Vec::new(),
+            site_summary: None,
+            link_edges_r2_key: None,
+            near_duplicate_clusters: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_unsafe_job_ids_are_refused() {
+        let root = std::env::temp_dir().join(format!("outbox-test-{}", uuid::Uuid::new_v4()));
+        let outbox = Outbox::new(&root);
+        outbox
+            .spool("http://localhost/cb", &batch("job-1", 0, false))
+            .unwrap();
+        for job_id in ["..", "", "a/b", "../callback-outbox"] {
+            let err = outbox
+                .spool("http://localhost/cb", &batch(job_id, 0, false))
+                .unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+            assert!(outbox.remove(job_id, 0).is_err());
+            assert!(outbox.dead_letter(job_id).is_err());
+            assert!(outbox.pending(job_id).is_empty());
+        }
+        assert_eq!(outbox.pending("job-1"), vec![0]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_spool_replays_in_batch_index_order() {
+        let root = std::env::temp_dir().join(format!("outbox-test-{}", uuid::Uuid::new_v4()));
+        let outbox = Outbox::new(&root);
+        assert!(outbox.pending("job-1").is_empty());
+
+        // Spooled out of order; 10 must sort after 9, not after 1.
+        for idx in [10, 2, 9] {
+            outbox
+                .spool("http://localhost/cb", &batch("job-1", idx, idx == 10))
+                .unwrap();
+        }
+        assert_eq!(outbox.pending("job-1"), vec![2, 9, 10]);
+        assert_eq!(outbox.job_ids(), vec!["job-1".to_string()]);
+        let last = outbox.load("job-1", 10).unwrap();
+        assert!(last.batch.is_final);
+        assert_eq!(last.callback_url, "http://localhost/cb");
+
+        for idx in outbox.pending("job-1") {
+            outbox.remove("job-1", idx).unwrap();
+        }
+        assert!(!outbox.has_pending("job-1"));
+        assert!(outbox.job_ids().is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_dead_letter_moves_batches_out_of_replay() {
+        let root = std::env::temp_dir().join(format!("outbox-test-{}", uuid::Uuid::new_v4()));
+        let outbox = Outbox::new(&root);
+        for idx in [3, 4] {
+            outbox
+                .spool("http://localhost/cb", &batch("job-2", idx, false))
+                .unwrap();
+        }
+        assert!(outbox.oldest_age("job-2").is_some());
+
+        outbox.dead_letter("job-2").unwrap();
+        assert!(!outbox.has_pending("job-2"));
+        assert!(outbox.job_ids().is_empty());
+        assert!(outbox.oldest_age("job-2").is_none());
+        assert_eq!(outbox.dead_lettered("job-2"), vec![3, 4]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
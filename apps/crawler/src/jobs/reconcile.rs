@@ -0,0 +1,372 @@
+use std::collections::{BTreeSet, HashSet};
+
+use crate::crawler::frontier::normalize_url;
+use crate::models::{
+    CanonicalizationPolicy, CrawlPageResult, PageOutcome, PageOutcomeKind, SitemapReconciliation,
+    SitemapUrlIssue, SitemapUrlIssueKind,
+};
+
+/// URLs kept per list in the report. Agencies want examples to fix, not the
+/// whole site; the counts carry the totals.
+const MAX_REPORTED_URLS: usize = 500;
+
+/// Accumulates what the crawl saw so the final batch can compare it with the
+/// sitemap. Fed every page and outcome as workers finish; holds only URLs and
+/// issues, never page bodies.
+#[derive(Debug, Default)]
+pub struct SitemapReconciler {
+    /// The crawl's policy, so sitemap URLs, links and canonical tags compare
+    /// the way the frontier dedups them.
+    canonicalization: CanonicalizationPolicy,
+    /// Normalized sitemap URLs.
+    sitemap: HashSet<String>,
+    /// Normalized targets of internal links, excluding self-links.
+    linked: HashSet<String>,
+    crawled_sitemap_urls: HashSet<String>,
+    /// Indexable pages the sitemap doesn't list.
+    missing: BTreeSet<String>,
+    issues: Vec<SitemapUrlIssue>,
+}
+
+impl SitemapReconciler {
+    pub fn new<'a>(
+        sitemap_urls: impl IntoIterator<Item = &'a str>,
+        canonicalization: CanonicalizationPolicy,
+    ) -> Self {
+        SitemapReconciler {
+            sitemap: sitemap_urls
+                .into_iter()
+                .filter_map(|url| normalize_url(url, &canonicalization))
+                .collect(),
+            canonicalization,
+            ..Default::default()
+        }
+    }
+
+    fn canonicalize(&self, url: &str) -> Option<String> {
+        normalize_url(url, &self.canonicalization)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sitemap.is_empty()
+    }
+
+    /// Record a fetched page. `requested` is the frontier URL, which differs
+    /// from `page.url` after a redirect.
+    pub fn record_page(&mut self, requested: &str, page: &CrawlPageResult) {
+        let Some(requested) = self.canonicalize(requested) else {
+            return;
+        };
+        let landed = self
+            .canonicalize(&page.url)
+            .unwrap_or_else(|| requested.clone());
+
+        for link in &page.extracted.internal_links {
+            if let Some(link) = self.canonicalize(link) {
+                if link != requested && link != landed {
+                    self.linked.insert(link);
+                }
+            }
+        }
+
+        // A 304 carries no fresh status, directives or canonical to judge.
+        if page.unchanged {
+            if self.sitemap.contains(&requested) {
+                self.crawled_sitemap_urls.insert(requested);
+            }
+            return;
+        }
+
+        let noindex = page
+            .extracted
+            .robots_directives
+            .iter()
+            .any(|d| d == "noindex" || d == "none");
+        let canonical = page
+            .canonical_url
+            .as_deref()
+            .filter(|c| !c.is_empty())
+            .and_then(|c| self.canonicalize(c))
+            .filter(|c| *c != landed);
+        let redirected = !page.redirect_chain.is_empty() || landed != requested;
+
+        if !self.sitemap.contains(&requested) {
+            let indexable = page.status_code == 200
+                && !noindex
+                && canonical.is_none()
+                && !page.is_cross_domain_redirect;
+            if indexable && !self.sitemap.contains(&landed) {
+                self.missing.insert(landed);
+            }
+            return;
+        }
+
+        self.crawled_sitemap_urls.insert(requested.clone());
+        if redirected {
+            self.issues.push(SitemapUrlIssue {
+                url: requested.clone(),
+                kind: SitemapUrlIssueKind::Redirect,
+                status_code: page.redirect_chain.first().map(|hop| hop.status_code),
+                target: Some(
+                    page.redirect_url
+                        .clone()
+                        .unwrap_or_else(|| page.url.clone()),
+                ),
+                error_kind: None,
+                robots_rule: None,
+            });
+        }
+        if page.status_code != 200 {
+            self.issues.push(SitemapUrlIssue {
+                url: requested.clone(),
+                kind: SitemapUrlIssueKind::Non200,
+                status_code: Some(page.status_code),
+                target: None,
+                error_kind: None,
+                robots_rule: None,
+            });
+        }
+        if noindex {
+            self.issues.push(SitemapUrlIssue {
+                url: requested.clone(),
+                kind: SitemapUrlIssueKind::Noindex,
+                status_code: None,
+                target: None,
+                error_kind: None,
+                robots_rule: None,
+            });
+        }
+        if let Some(canonical) = canonical {
+            self.issues.push(SitemapUrlIssue {
+                url: requested,
+                kind: SitemapUrlIssueKind::CanonicalizedElsewhere,
+                status_code: None,
+                target: Some(canonical),
+                error_kind: None,
+                robots_rule: None,
+            });
+        }
+    }
+
+    /// Record a URL that produced no page. Only failed and robots-blocked
+    /// sitemap URLs matter here; duplicates were already recorded as pages.
+    pub fn record_outcome(&mut self, outcome: &PageOutcome) {
+        let kind = match outcome.outcome {
+            PageOutcomeKind::Failed => SitemapUrlIssueKind::Non200,
+            PageOutcomeKind::Blocked => SitemapUrlIssueKind::BlockedByRobots,
+            _ => return,
+        };
+        let Some(url) = self.canonicalize(&outcome.url) else {
+            return;
+        };
+        if !self.sitemap.contains(&url) {
+            return;
+        }
+        self.crawled_sitemap_urls.insert(url.clone());
+        self.issues.push(SitemapUrlIssue {
+            url,
+            kind,
+            status_code: outcome.status_code,
+            target: None,
+            error_kind: outcome.error_kind.clone(),
+            robots_rule: outcome.robots_rule.clone(),
+        });
+    }
+
+    /// Build the report. `partial` marks a crawl that didn't reach every page.
+    pub fn finish(self, partial: bool) -> SitemapReconciliation {
+        let orphans: BTreeSet<&String> = self
+            .sitemap
+            .iter()
+            .filter(|url| !self.linked.contains(*url))
+            .collect();
+        let mut issues = self.issues;
+        issues.sort_by(|a, b| a.url.cmp(&b.url));
+
+        SitemapReconciliation {
+            sitemap_url_count: self.sitemap.len() as u32,
+            crawled_sitemap_url_count: self.crawled_sitemap_urls.len() as u32,
+            partial,
+            orphan_count: orphans.len() as u32,
+            orphan_urls: orphans
+                .into_iter()
+                .take(MAX_REPORTED_URLS)
+                .cloned()
+                .collect(),
+            missing_from_sitemap_count: self.missing.len() as u32,
+            missing_from_sitemap: self.missing.into_iter().take(MAX_REPORTED_URLS).collect(),
+            issue_count: issues.len() as u32,
+            issues: issues.into_iter().take(MAX_REPORTED_URLS).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::fetcher::RedirectHop;
+    use crate::models::ExtractedData;
+
+    fn page(url: &str, links: &[&str]) -> CrawlPageResult {
+        CrawlPageResult {
+            url: url.to_string(),
+            status_code: 200,
+            extracted: ExtractedData {
+                internal_links: links.iter().map(|l| l.to_string()).collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    const HOME: &str = "https://example.com/";
+
+    #[test]
+    fn test_orphans_and_pages_missing_from_sitemap() {
+        let mut r = SitemapReconciler::new(
+            [
+                HOME,
+                "https://example.com/linked/",
+                "https://example.com/orphan",
+            ],
+            Default::default(),
+        );
+        r.record_page(
+            HOME,
+            &page(
+                HOME,
+                &["https://example.com/linked", "https://example.com/unlisted"],
+            ),
+        );
+        // Self-links don't rescue a page from being an orphan.
+        r.record_page(
+            "https://example.com/orphan",
+            &page(
+                "https://example.com/orphan",
+                &["https://example.com/orphan"],
+            ),
+        );
+        r.record_page(
+            "https://example.com/unlisted",
+            &page("https://example.com/unlisted", &[HOME]),
+        );
+
+        let report = r.finish(false);
+        assert_eq!(report.sitemap_url_count, 3);
+        assert_eq!(report.crawled_sitemap_url_count, 2);
+        assert_eq!(report.orphan_urls, vec!["https://example.com/orphan"]);
+        assert_eq!(
+            report.missing_from_sitemap,
+            vec!["https://example.com/unlisted"]
+        );
+        assert!(report.issues.is_empty());
+    }
+
+    #[test]
+    fn test_flags_sitemap_urls_that_should_not_be_listed() {
+        let mut r = SitemapReconciler::new(
+            [
+                "https://example.com/moved",
+                "https://example.com/gone",
+                "https://example.com/hidden",
+                "https://example.com/copy",
+                "https://example.com/down",
+                "https://example.com/private",
+            ],
+            Default::default(),
+        );
+
+        let mut moved = page("https://example.com/new-home", &[]);
+        moved.redirect_chain = vec![RedirectHop {
+            url: "https://example.com/moved".into(),
+            status_code: 301,
+        }];
+        r.record_page("https://example.com/moved", &moved);
+
+        let mut gone = page("https://example.com/gone", &[]);
+        gone.status_code = 404;
+        r.record_page("https://example.com/gone", &gone);
+
+        let mut hidden = page("https://example.com/hidden", &[]);
+        hidden.extracted.robots_directives = vec!["noindex".into(), "follow".into()];
+        r.record_page("https://example.com/hidden", &hidden);
+
+        let mut copy = page("https://example.com/copy", &[]);
+        copy.canonical_url = Some("https://example.com/original".into());
+        r.record_page("https://example.com/copy", &copy);
+
+        r.record_outcome(&PageOutcome {
+            url: "https://example.com/down".into(),
+            outcome: PageOutcomeKind::Failed,
+            error_kind: Some("timeout".into()),
+            error: Some("Request timed out".into()),
+            status_code: None,
+            robots_rule: None,
+            duplicate_of: None,
+        });
+        r.record_outcome(&PageOutcome {
+            url: "https://example.com/private".into(),
+            outcome: PageOutcomeKind::Blocked,
+            error_kind: None,
+            error: None,
+            status_code: None,
+            robots_rule: Some("Disallow: /private".into()),
+            duplicate_of: None,
+        });
+
+        let report = r.finish(true);
+        assert!(report.partial);
+        assert_eq!(report.crawled_sitemap_url_count, 6);
+        let kinds: Vec<(&str, SitemapUrlIssueKind)> = report
+            .issues
+            .iter()
+            .map(|i| (i.url.as_str(), i.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (
+                    "https://example.com/copy",
+                    SitemapUrlIssueKind::CanonicalizedElsewhere
+                ),
+                ("https://example.com/down", SitemapUrlIssueKind::Non200),
+                ("https://example.com/gone", SitemapUrlIssueKind::Non200),
+                ("https://example.com/hidden", SitemapUrlIssueKind::Noindex),
+                ("https://example.com/moved", SitemapUrlIssueKind::Redirect),
+                (
+                    "https://example.com/private",
+                    SitemapUrlIssueKind::BlockedByRobots
+                ),
+            ]
+        );
+        assert_eq!(
+            report.issues[5].robots_rule.as_deref(),
+            Some("Disallow: /private")
+        );
+        let moved = &report.issues[4];
+        assert_eq!(moved.status_code, Some(301));
+        assert_eq!(
+            moved.target.as_deref(),
+            Some("https://example.com/new-home")
+        );
+        assert_eq!(report.issues[1].error_kind.as_deref(), Some("timeout"));
+        // None of them are indexable pages missing from the sitemap.
+        assert!(report.missing_from_sitemap.is_empty());
+    }
+
+    #[test]
+    fn test_non_indexable_pages_are_not_missing_from_sitemap() {
+        let mut r = SitemapReconciler::new([HOME], Default::default());
+        let mut noindex = page("https://example.com/private", &[]);
+        noindex.extracted.robots_directives = vec!["noindex".into()];
+        r.record_page("https://example.com/private", &noindex);
+        let mut canonicalized = page("https://example.com/a?ref=x", &[]);
+        canonicalized.canonical_url = Some("https://example.com/a".into());
+        r.record_page("https://example.com/a?ref=x", &canonicalized);
+        let mut broken = page("https://example.com/broken", &[]);
+        broken.status_code = 500;
+        r.record_page("https://example.com/broken", &broken);
+
+        assert_eq!(r.finish(false).missing_from_sitemap_count, 0);
+    }
+}
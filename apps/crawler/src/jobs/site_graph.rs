@@ -0,0 +1,382 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::crawler::frontier::normalize_url;
+use crate::models::{CanonicalizationPolicy, CrawlPageResult, PageLinkMetrics, SiteSummary};
+
+/// PageRank damping factor: the chance a random surfer follows a link rather
+/// than jumping to a random page.
+const DAMPING: f64 = 0.85;
+const MAX_ITERATIONS: usize = 100;
+/// Stop iterating once no score moves by more than this.
+const TOLERANCE: f64 = 1e-9;
+
+/// Pages listed in `SiteSummary::pages`; the full list goes to R2.
+pub const MAX_SUMMARY_PAGES: usize = 500;
+
+/// R2 key of every delivered page's link metrics.
+pub fn metrics_key(job_id: &str) -> String {
+    format!("crawls/{}/links/page-metrics.jsonl.gz", job_id)
+}
+
+/// The internal link graph of the delivered pages, built up as workers finish
+/// and analyzed once the crawl ends. Holds interned URLs and edges only.
+/// It isn't checkpointed: a resumed crawl only sees links from pages
+/// delivered after the restart, so the caller marks its summary partial.
+#[derive(Debug, Default)]
+pub struct SiteGraph {
+    canonicalization: CanonicalizationPolicy,
+    ids: HashMap<String, usize>,
+    urls: Vec<String>,
+    /// Ids of pages that were delivered, in delivery order.
+    pages: Vec<usize>,
+    /// Requested URL -> the URL it redirected to.
+    redirects: HashMap<usize, usize>,
+    /// `(source, target, followed)`; `followed` is false for `rel=nofollow`.
+    edges: Vec<(usize, usize, bool)>,
+}
+
+impl SiteGraph {
+    pub fn new(canonicalization: CanonicalizationPolicy) -> Self {
+        SiteGraph {
+            canonicalization,
+            ..Default::default()
+        }
+    }
+
+    fn id(&mut self, url: &str) -> Option<usize> {
+        let url = normalize_url(url, &self.canonicalization)?;
+        if let Some(&id) = self.ids.get(&url) {
+            return Some(id);
+        }
+        let id = self.urls.len();
+        self.ids.insert(url.clone(), id);
+        self.urls.push(url);
+        Some(id)
+    }
+
+    /// Record a delivered page and its internal links. `requested` is the
+    /// frontier URL, which differs from `page.url` after a redirect.
+    pub fn record_page(&mut self, requested: &str, page: &CrawlPageResult) {
+        let Some(source) = self.id(&page.url) else {
+            return;
+        };
+        if let Some(requested) = self.id(requested) {
+            if requested != source {
+                self.redirects.insert(requested, source);
+            }
+        }
+        self.pages.push(source);
+
+        let details = &page.extracted.internal_link_details;
+        let links: Vec<(&str, bool)> = if details.is_empty() {
+            page.extracted
+                .internal_links
+                .iter()
+                .map(|url| (url.as_str(), true))
+                .collect()
+        } else {
+            details
+                .iter()
+                .map(|l| {
+                    (
+                        l.url.as_str(),
+                        !l.rel.to_ascii_lowercase().contains("nofollow"),
+                    )
+                })
+                .collect()
+        };
+        for (url, followed) in links {
+            if let Some(target) = self.id(url) {
+                self.edges.push((source, target, followed));
+            }
+        }
+    }
+
+    /// Compute per-page metrics. `seeds` are the crawl's seed URLs, where
+    /// click depth 0 starts; `partial` marks a crawl that didn't reach every
+    /// page, so orphan status is only indicative. `pages` holds every
+    /// delivered page; the caller uploads it and keeps the top
+    /// `MAX_SUMMARY_PAGES`.
+    pub fn finish(mut self, seeds: &[String], partial: bool) -> SiteSummary {
+        let seed_ids: Vec<usize> = seeds.iter().filter_map(|s| self.id(s)).collect();
+        let resolve = |id: usize| self.redirects.get(&id).copied().unwrap_or(id);
+
+        // Index delivered pages 0..n; edges to anything else are dropped.
+        let mut index: HashMap<usize, usize> = HashMap::new();
+        for &id in &self.pages {
+            let next = index.len();
+            index.entry(id).or_insert(next);
+        }
+        let n = index.len();
+        let mut nodes = vec![0usize; n];
+        for (&id, &i) in &index {
+            nodes[i] = id;
+        }
+
+        let mut outlinks: Vec<HashSet<usize>> = vec![HashSet::new(); n];
+        let mut followed: Vec<HashSet<usize>> = vec![HashSet::new(); n];
+        let mut inlinks: Vec<HashSet<usize>> = vec![HashSet::new(); n];
+        for &(source, target, is_followed) in &self.edges {
+            let (Some(&s), Some(&t)) = (index.get(&source), index.get(&resolve(target))) else {
+                continue;
+            };
+            if s == t {
+                continue;
+            }
+            outlinks[s].insert(t);
+            inlinks[t].insert(s);
+            if is_followed {
+                followed[s].insert(t);
+            }
+        }
+
+        let seeds: HashSet<usize> = seed_ids
+            .iter()
+            .filter_map(|&id| index.get(&resolve(id)).copied())
+            .collect();
+        let depths = click_depths(&outlinks, &seeds);
+        let ranks = pagerank(&followed);
+
+        let mut pages: Vec<PageLinkMetrics> = (0..n)
+            .map(|i| PageLinkMetrics {
+                url: self.urls[nodes[i]].clone(),
+                click_depth: depths[i],
+                inlinks: inlinks[i].len() as u32,
+                outlinks: outlinks[i].len() as u32,
+                pagerank: ranks[i],
+                is_dead_end: outlinks[i].is_empty(),
+                is_orphan: inlinks[i].is_empty() && !seeds.contains(&i),
+            })
+            .collect();
+        pages.sort_by(|a, b| {
+            b.pagerank
+                .total_cmp(&a.pagerank)
+                .then_with(|| a.url.cmp(&b.url))
+        });
+
+        let max_click_depth = depths.iter().flatten().copied().max();
+        let mut click_depth_histogram = vec![0u32; max_click_depth.map_or(0, |d| d as usize + 1)];
+        for depth in depths.iter().flatten() {
+            click_depth_histogram[*depth as usize] += 1;
+        }
+
+        SiteSummary {
+            partial,
+            page_count: n as u32,
+            orphan_count: pages.iter().filter(|p| p.is_orphan).count() as u32,
+            dead_end_count: pages.iter().filter(|p| p.is_dead_end).count() as u32,
+            unreachable_count: depths.iter().filter(|d| d.is_none()).count() as u32,
+            max_click_depth,
+            click_depth_histogram,
+            pages,
+            page_metrics_r2_key: None,
+        }
+    }
+}
+
+/// Shortest number of clicks from any seed, by BFS; `None` when no seed
+/// links through to the page.
+fn click_depths(outlinks: &[HashSet<usize>], seeds: &HashSet<usize>) -> Vec<Option<u32>> {
+    let mut depths = vec![None; outlinks.len()];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    for &seed in seeds {
+        depths[seed] = Some(0);
+        queue.push_back(seed);
+    }
+    while let Some(node) = queue.pop_front() {
+        let next = depths[node].expect("queued nodes have a depth") + 1;
+        for &target in &outlinks[node] {
+            if depths[target].is_none() {
+                depths[target] = Some(next);
+                queue.push_back(target);
+            }
+        }
+    }
+    depths
+}
+
+/// PageRank over followed links by power iteration. Pages without outlinks
+/// spread their score evenly, so the scores always sum to 1.
+fn pagerank(outlinks: &[HashSet<usize>]) -> Vec<f64> {
+    let n = outlinks.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let uniform = 1.0 / n as f64;
+    let mut ranks = vec![uniform; n];
+    for _ in 0..MAX_ITERATIONS {
+        let dangling: f64 = (0..n)
+            .filter(|&i| outlinks[i].is_empty())
+            .map(|i| ranks[i])
+            .sum();
+        let base = (1.0 - DAMPING) * uniform + DAMPING * dangling * uniform;
+        let mut next = vec![base; n];
+        for (source, targets) in outlinks.iter().enumerate() {
+            if targets.is_empty() {
+                continue;
+            }
+            let share = DAMPING * ranks[source] / targets.len() as f64;
+            for &target in targets {
+                next[target] += share;
+            }
+        }
+        let delta = next
+            .iter()
+            .zip(&ranks)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max);
+        ranks = next;
+        if delta < TOLERANCE {
+            break;
+        }
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::fetcher::RedirectHop;
+    use crate::models::{ExtractedData, ExtractedLink};
+
+    fn page(url: &str, links: &[&str]) -> CrawlPageResult {
+        CrawlPageResult {
+            url: url.to_string(),
+            status_code: 200,
+            extracted: ExtractedData {
+                internal_links: links.iter().map(|l| l.to_string()).collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn metrics<'a>(summary: &'a SiteSummary, path: &str) -> &'a PageLinkMetrics {
+        let url = format!("https://example.com{path}");
+        summary.pages.iter().find(|p| p.url == url).unwrap()
+    }
+
+    const HOME: &str = "https://example.com/";
+
+    #[test]
+    fn test_depth_links_orphans_and_dead_ends() {
+        let mut graph = SiteGraph::new(Default::default());
+        graph.record_page(
+            HOME,
+            &page(
+                HOME,
+                &["https://example.com/a", "https://example.com/b", HOME],
+            ),
+        );
+        graph.record_page(
+            "https://example.com/a",
+            &page("https://example.com/a", &["https://example.com/c", HOME]),
+        );
+        graph.record_page(
+            "https://example.com/b",
+            &page("https://example.com/b", &["https://example.com/a/"]),
+        );
+        graph.record_page("https://example.com/c", &page("https://example.com/c", &[]));
+        // Only reached through the sitemap.
+        graph.record_page(
+            "https://example.com/lonely",
+            &page("https://example.com/lonely", &[HOME]),
+        );
+
+        let summary = graph.finish(&[HOME.to_string()], false);
+        assert_eq!(summary.page_count, 5);
+
+        let home = metrics(&summary, "/");
+        assert_eq!(home.click_depth, Some(0));
+        assert_eq!(home.inlinks, 2, "self-link ignored");
+        assert_eq!(home.outlinks, 2);
+        assert!(!home.is_orphan);
+
+        let a = metrics(&summary, "/a");
+        assert_eq!(a.click_depth, Some(1));
+        assert_eq!(a.inlinks, 2);
+        assert_eq!(metrics(&summary, "/c").click_depth, Some(2));
+        assert!(metrics(&summary, "/c").is_dead_end);
+
+        let lonely = metrics(&summary, "/lonely");
+        assert!(lonely.is_orphan);
+        assert_eq!(lonely.click_depth, None);
+
+        assert_eq!(summary.orphan_count, 1);
+        assert_eq!(summary.dead_end_count, 1);
+        assert_eq!(summary.unreachable_count, 1);
+        assert_eq!(summary.max_click_depth, Some(2));
+        assert_eq!(summary.click_depth_histogram, vec![1, 2, 1]);
+        // Highest PageRank first; nothing passes any to the orphan.
+        assert_eq!(summary.pages[0].url, "https://example.com/a");
+        assert_eq!(summary.pages[4].url, "https://example.com/lonely");
+        let total: f64 = summary.pages.iter().map(|p| p.pagerank).sum();
+        assert!((total - 1.0).abs() < 1e-6, "{total}");
+    }
+
+    #[test]
+    fn test_redirects_and_nofollow() {
+        let mut graph = SiteGraph::new(Default::default());
+        let mut home = page(HOME, &[]);
+        home.extracted.internal_link_details = vec![
+            ExtractedLink {
+                url: "https://example.com/old".into(),
+                anchor_text: "Old".into(),
+                rel: String::new(),
+                is_external: false,
+                placement: None,
+            },
+            ExtractedLink {
+                url: "https://example.com/login".into(),
+                anchor_text: "Log in".into(),
+                rel: "nofollow".into(),
+                is_external: false,
+                placement: None,
+            },
+        ];
+        graph.record_page(HOME, &home);
+        let mut moved = page("https://example.com/new", &[]);
+        moved.redirect_chain = vec![RedirectHop {
+            url: "https://example.com/old".into(),
+            status_code: 301,
+        }];
+        graph.record_page("https://example.com/old", &moved);
+        graph.record_page(
+            "https://example.com/login",
+            &page("https://example.com/login", &[]),
+        );
+
+        let summary = graph.finish(&[HOME.to_string()], true);
+        assert!(summary.partial);
+        // The link to /old counts for the page it redirects to.
+        assert_eq!(metrics(&summary, "/new").inlinks, 1);
+        assert_eq!(metrics(&summary, "/new").click_depth, Some(1));
+        // Nofollow links count as links but pass no PageRank.
+        let login = metrics(&summary, "/login");
+        assert_eq!(login.inlinks, 1);
+        assert!(login.pagerank < metrics(&summary, "/new").pagerank);
+    }
+
+    #[test]
+    fn test_metrics_json_lines() {
+        let mut graph = SiteGraph::new(Default::default());
+        graph.record_page(HOME, &page(HOME, &["https://example.com/a"]));
+        graph.record_page(
+            "https://example.com/a",
+            &page("https://example.com/a", &[HOME]),
+        );
+        let summary = graph.finish(&[HOME.to_string()], false);
+        assert_eq!(summary.page_metrics_r2_key, None);
+
+        let lines = crate::jobs::link_graph::to_json_lines(&summary.pages);
+        let parsed: Vec<PageLinkMetrics> = lines
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(parsed, summary.pages);
+        assert_eq!(
+            metrics_key("job-1"),
+            "crawls/job-1/links/page-metrics.jsonl.gz"
+        );
+    }
+}
@@ -0,0 +1,387 @@
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+use crate::config::Config;
+use crate::models::{CrawlStats, JobStatusKind};
+
+/// How long a job record lives in Redis after its last update.
+const REDIS_RECORD_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+#[derive(Error, Debug)]
+pub enum JobStoreError {
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("Invalid job record: {0}")]
+    Decode(String),
+}
+
+/// Durable view of a job: what a status query needs after a restart, or from
+/// another crawler instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub status: JobStatusKind,
+    pub stats: Option<CrawlStats>,
+    pub cancel_requested: bool,
+    pub last_batch_index: Option<u32>,
+    /// Unix seconds of the last write.
+    pub updated_at: u64,
+}
+
+impl JobRecord {
+    fn new(status: JobStatusKind) -> Self {
+        JobRecord {
+            status,
+            stats: None,
+            cancel_requested: false,
+            last_batch_index: None,
+            updated_at: now_secs(),
+        }
+    }
+}
+
+/// Pluggable job-state registry. `Memory` keeps records for the life of the
+/// process; `Redis` persists them so status and cancellation survive deploys
+/// and are shared across crawler instances.
+pub enum JobStore {
+    Memory(MemoryJobStore),
+    Redis(RedisJobStore),
+}
+
+impl std::fmt::Debug for JobStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobStore::Memory(_) => f.write_str("JobStore::Memory"),
+            JobStore::Redis(_) => f.write_str("JobStore::Redis"),
+        }
+    }
+}
+
+impl JobStore {
+    pub fn memory() -> Self {
+        JobStore::Memory(MemoryJobStore::default())
+    }
+
+    pub async fn redis(url: &str) -> Result<Self, JobStoreError> {
+        Ok(JobStore::Redis(RedisJobStore::connect(url).await?))
+    }
+
+    /// Build the store selected by `JOB_STORE`. A Redis store that can't connect
+    /// at startup falls back to memory rather than keeping the service down.
+    pub async fn from_config(config: &Config) -> Self {
+        match (config.job_store.as_str(), config.redis_url.as_deref()) {
+            ("redis", Some(url)) => match Self::redis(url).await {
+                Ok(store) => store,
+                Err(e) => {
+                    tracing::error!(error = %e, "Redis job store unavailable; using in-memory store");
+                    Self::memory()
+                }
+            },
+            ("redis", None) => {
+                tracing::error!("JOB_STORE=redis but REDIS_URL is unset; using in-memory store");
+                Self::memory()
+            }
+            _ => Self::memory(),
+        }
+    }
+
+    /// Whether other processes can write to this store, i.e. whether a cancel
+    /// request can arrive from somewhere other than this instance.
+    pub fn is_shared(&self) -> bool {
+        matches!(self, JobStore::Redis(_))
+    }
+
+    pub async fn get(&self, job_id: &str) -> Result<Option<JobRecord>, JobStoreError> {
+        match self {
+            JobStore::Memory(s) => Ok(s.records.read().await.get(job_id).cloned()),
+            JobStore::Redis(s) => s.get(job_id).await,
+        }
+    }
+
+    /// Record a status transition, and the latest stats when given.
+    pub async fn set_status(
+        &self,
+        job_id: &str,
+        status: JobStatusKind,
+        stats: Option<&CrawlStats>,
+    ) -> Result<(), JobStoreError> {
+        match self {
+            JobStore::Memory(s) => {
+                let mut records = s.records.write().await;
+                let record = records
+                    .entry(job_id.to_string())
+                    .or_insert_with(|| JobRecord::new(status));
+                record.status = status;
+                if let Some(stats) = stats {
+                    record.stats = Some(stats.clone());
+                }
+                record.updated_at = now_secs();
+                Ok(())
+            }
+            JobStore::Redis(s) => {
+                let mut fields = vec![("status", encode(&status)?)];
+                if let Some(stats) = stats {
+                    fields.push(("stats", encode(stats)?));
+                }
+                s.hset(job_id, fields).await
+            }
+        }
+    }
+
+    /// Persist a cancel request; the job is reported as cancelled from now on.
+    pub async fn request_cancel(&self, job_id: &str) -> Result<(), JobStoreError> {
+        match self {
+            JobStore::Memory(s) => {
+                let mut records = s.records.write().await;
+                let record = records
+                    .entry(job_id.to_string())
+                    .or_insert_with(|| JobRecord::new(JobStatusKind::Cancelled));
+                record.status = JobStatusKind::Cancelled;
+                record.cancel_requested = true;
+                record.updated_at = now_secs();
+                Ok(())
+            }
+            JobStore::Redis(s) => {
+                let fields = vec![
+                    ("status", encode(&JobStatusKind::Cancelled)?),
+                    ("cancel_requested", "1".to_string()),
+                ];
+                s.hset(job_id, fields).await
+            }
+        }
+    }
+
+    /// Drop a cancel request before the job id is run again. A job with no
+    /// record has nothing to clear, so none is created.
+    pub async fn clear_cancel(&self, job_id: &str) -> Result<(), JobStoreError> {
+        match self {
+            JobStore::Memory(s) => {
+                if let Some(record) = s.records.write().await.get_mut(job_id) {
+                    record.cancel_requested = false;
+                    record.updated_at = now_secs();
+                }
+                Ok(())
+            }
+            JobStore::Redis(s) => s.clear_cancel(job_id).await,
+        }
+    }
+
+    pub async fn cancel_requested(&self, job_id: &str) -> Result<bool, JobStoreError> {
+        Ok(self
+            .get(job_id)
+            .await?
+            .map(|r| r.cancel_requested)
+            .unwrap_or(false))
+    }
+
+    /// Record the index of the last batch the callback endpoint acknowledged.
+    pub async fn record_batch(&self, job_id: &str, batch_index: u32) -> Result<(), JobStoreError> {
+        match self {
+            JobStore::Memory(s) => {
+                if let Some(record) = s.records.write().await.get_mut(job_id) {
+                    record.last_batch_index = Some(batch_index);
+                    record.updated_at = now_secs();
+                }
+                Ok(())
+            }
+            JobStore::Redis(s) => {
+                s.hset(job_id, vec![("last_batch_index", batch_index.to_string())])
+                    .await
+            }
+        }
+    }
+}
+
+/// Process-local store: a plain map of job records.
+#[derive(Default)]
+pub struct MemoryJobStore {
+    records: RwLock<HashMap<String, JobRecord>>,
+}
+
+/// Redis-backed store: one hash per job under `crawler:job:{id}`, refreshed to
+/// a 7-day TTL on every write.
+pub struct RedisJobStore {
+    conn: ConnectionManager,
+}
+
+impl RedisJobStore {
+    pub async fn connect(url: &str) -> Result<Self, JobStoreError> {
+        let client = redis::Client::open(url)?;
+        let conn = client.get_connection_manager().await?;
+        Ok(RedisJobStore { conn })
+    }
+
+    fn key(job_id: &str) -> String {
+        format!("crawler:job:{}", job_id)
+    }
+
+    async fn hset(
+        &self,
+        job_id: &str,
+        mut fields: Vec<(&str, String)>,
+    ) -> Result<(), JobStoreError> {
+        let key = Self::key(job_id);
+        fields.push(("updated_at", now_secs().to_string()));
+        let mut conn = self.conn.clone();
+        redis::pipe()
+            .atomic()
+            .cmd("HSET")
+            .arg(&key)
+            .arg(&fields)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(REDIS_RECORD_TTL_SECS)
+            .ignore()
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Reset `cancel_requested` only on an existing hash; a bare `HSET` would
+    /// create a record without a `status` that `decode_record` rejects.
+    async fn clear_cancel(&self, job_id: &str) -> Result<(), JobStoreError> {
+        const SCRIPT: &str = "if redis.call('EXISTS', KEYS[1]) == 1 then \
+             redis.call('HSET', KEYS[1], 'cancel_requested', '0', 'updated_at', ARGV[1]) \
+             redis.call('EXPIRE', KEYS[1], ARGV[2]) end";
+        let mut conn = self.conn.clone();
+        redis::cmd("EVAL")
+            .arg(SCRIPT)
+            .arg(1)
+            .arg(Self::key(job_id))
+            .arg(now_secs())
+            .arg(REDIS_RECORD_TTL_SECS)
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, job_id: &str) -> Result<Option<JobRecord>, JobStoreError> {
+        let mut conn = self.conn.clone();
+        let fields: HashMap<String, String> = redis::cmd("HGETALL")
+            .arg(Self::key(job_id))
+            .query_async(&mut conn)
+            .await?;
+        if fields.is_empty() {
+            return Ok(None);
+        }
+        decode_record(&fields).map(Some)
+    }
+}
+
+/// Rebuild a `JobRecord` from its Redis hash fields.
+fn decode_record(fields: &HashMap<String, String>) -> Result<JobRecord, JobStoreError> {
+    let status = fields
+        .get("status")
+        .ok_or_else(|| JobStoreError::Decode("missing status".to_string()))
+        .and_then(|s| decode(s))?;
+    let stats = fields.get("stats").map(|s| decode(s)).transpose()?;
+    let last_batch_index = fields
+        .get("last_batch_index")
+        .map(|s| s.parse::<u32>())
+        .transpose()
+        .map_err(|e| JobStoreError::Decode(e.to_string()))?;
+
+    Ok(JobRecord {
+        status,
+        stats,
+        cancel_requested: fields.get("cancel_requested").map(String::as_str) == Some("1"),
+        last_batch_index,
+        updated_at: fields
+            .get("updated_at")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0),
+    })
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<String, JobStoreError> {
+    serde_json::to_string(value).map_err(|e| JobStoreError::Decode(e.to_string()))
+}
+
+fn decode<T: serde::de::DeserializeOwned>(raw: &str) -> Result<T, JobStoreError> {
+    serde_json::from_str(raw).map_err(|e| JobStoreError::Decode(e.to_string()))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(pages: u32) -> CrawlStats {
+        CrawlStats {
+            pages_found: pages,
+            pages_crawled: pages,
+            pages_errored: 0,
+            elapsed_s: 1.0,
+            effective_rate_per_s: None,
+            effective_rate_per_host: Default::default(),
+            filtered_by_rule: Default::default(),
+        }
+    }
+
+    /// Exercise the full record lifecycle against any store backend.
+    async fn lifecycle(store: JobStore, job_id: &str) {
+        assert!(store.get(job_id).await.unwrap().is_none());
+        store.clear_cancel(job_id).await.unwrap();
+        assert!(store.get(job_id).await.unwrap().is_none());
+
+        store
+            .set_status(job_id, JobStatusKind::Queued, None)
+            .await
+            .unwrap();
+        store
+            .set_status(job_id, JobStatusKind::Crawling, Some(&stats(10)))
+            .await
+            .unwrap();
+        store.record_batch(job_id, 3).await.unwrap();
+
+        let record = store.get(job_id).await.unwrap().unwrap();
+        assert_eq!(record.status, JobStatusKind::Crawling);
+        assert_eq!(record.stats.unwrap().pages_crawled, 10);
+        assert_eq!(record.last_batch_index, Some(3));
+        assert!(!store.cancel_requested(job_id).await.unwrap());
+
+        store.request_cancel(job_id).await.unwrap();
+        let record = store.get(job_id).await.unwrap().unwrap();
+        assert_eq!(record.status, JobStatusKind::Cancelled);
+        assert!(store.cancel_requested(job_id).await.unwrap());
+        // Stats and batch progress survive the cancel.
+        assert_eq!(record.last_batch_index, Some(3));
+
+        // Running the id again starts from a clean slate.
+        store.clear_cancel(job_id).await.unwrap();
+        assert!(!store.cancel_requested(job_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_lifecycle() {
+        lifecycle(JobStore::memory(), "mem-job").await;
+    }
+
+    /// Needs a local redis-server:
+    /// `REDIS_URL=redis://127.0.0.1:6379 cargo test -- --ignored`.
+    #[tokio::test]
+    #[ignore = "needs a Redis server at REDIS_URL"]
+    async fn test_redis_store_lifecycle() {
+        let url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+        let store = JobStore::redis(&url).await.expect("connect to redis");
+        let job_id = format!("test-{}", uuid::Uuid::new_v4());
+        lifecycle(store, &job_id).await;
+    }
+
+    #[test]
+    fn test_decode_record_rejects_missing_status() {
+        let fields: HashMap<String, String> =
+            [("cancel_requested".to_string(), "1".to_string())].into();
+        assert!(decode_record(&fields).is_err());
+    }
+}
@@ -41,6 +41,7 @@ pub fn build_app(state: AppState) -> Router {
             get(server::routes::get_job_status),
         )
         .route("/api/v1/jobs/{id}/cancel", post(server::routes::cancel_job))
+        .route("/api/v1/jobs/{id}/resume", post(server::routes::resume_job))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             server::auth::verify_hmac,
@@ -4,6 +4,7 @@ use std::time::Duration;
 use thiserror::Error;
 use tokio::sync::Semaphore;
 
+use crate::crawler::egress::EgressPolicy;
 use crate::models::LighthouseResult;
 
 #[derive(Error, Debug)]
@@ -16,6 +17,8 @@ pub enum LighthouseError {
     ParseError(String),
     #[error("Lighthouse CLI not found")]
     NotInstalled,
+    #[error("Blocked by egress policy: {0}")]
+    EgressBlocked(String),
 }
 
 /// Lighthouse runner. Default backend is PageSpeed Insights (server-side, no
@@ -44,6 +47,11 @@ pub struct LighthouseRunner {
     /// Stop attempting audits once `consecutive_failures` reaches this. `0` =
     /// breaker disabled.
     failure_threshold: usize,
+    /// Checked before a local audit; PSI fetches from Google's side. Only the
+    /// audited URL is checked: Chromium resolves and follows redirects itself,
+    /// so a redirect or DNS rebind can still reach a blocked address. Run
+    /// `"local"` only against trusted targets.
+    egress: EgressPolicy,
 }
 
 impl LighthouseRunner {
@@ -71,9 +79,16 @@ impl LighthouseRunner {
             },
             consecutive_failures: Arc::new(AtomicUsize::new(0)),
             failure_threshold,
+            egress: EgressPolicy::default(),
         }
     }
 
+    /// Replace the default (no exemptions) egress policy for local audits.
+    pub fn with_egress(mut self, egress: EgressPolicy) -> Self {
+        self.egress = egress;
+        self
+    }
+
     /// Claim one unit of the per-crawl audit budget. `true` = audit this page,
     /// `false` = sampled out. Unlimited when no budget is set.
     fn try_claim_budget(&self) -> bool {
@@ -187,6 +202,10 @@ impl LighthouseRunner {
     }
 
     async fn run_local_audit(&self, url: &str) -> Result<LighthouseResult, LighthouseError> {
+        self.egress
+            .check_resolved(url)
+            .await
+            .map_err(|e| LighthouseError::EgressBlocked(e.to_string()))?;
         let url_owned = url.to_string();
         let timeout = self.timeout_secs;
 
@@ -1,4 +1,9 @@
-use crawler::{build_app, config::Config, jobs::JobManager, AppState};
+use crawler::{
+    build_app,
+    config::Config,
+    jobs::{store::JobStore, JobManager},
+    AppState,
+};
 use std::sync::Arc;
 use tracing_subscriber::{fmt, EnvFilter};
 
@@ -16,7 +21,15 @@ async fn main() {
         Arc::new(Config::from_env().expect("Failed to load configuration from environment"));
     let port = config.port;
 
-    let job_manager = Arc::new(JobManager::new(config.clone()));
+    let job_store = JobStore::from_config(&config).await;
+    tracing::info!(store = ?job_store, "Job store ready");
+    let job_manager = Arc::new(JobManager::with_store(config.clone(), job_store));
+
+    // Pick up crawls interrupted by a restart from their checkpoints.
+    let resumed = job_manager.resume_unfinished().await;
+    if resumed > 0 {
+        tracing::info!(resumed, "Resumed unfinished crawl jobs");
+    }
 
     let state = AppState {
         config: config.clone(),
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::crawler::fetcher::RedirectHop;
 
@@ -36,6 +36,256 @@ pub struct CrawlConfig {
     pub is_spa: Option<bool>,
     #[serde(default)]
     pub previous_page_count: Option<u32>,
+    /// Cache validators from the previous crawl, keyed by URL. Pages listed here
+    /// are fetched conditionally and come back `unchanged` on a 304.
+    #[serde(default)]
+    pub previous_validators: HashMap<String, PreviousValidators>,
+    /// Discovered URLs must match one of these to be queued. Empty allows all.
+    #[serde(default)]
+    pub include_patterns: Vec<UrlPattern>,
+    /// Discovered URLs matching any of these are skipped.
+    #[serde(default)]
+    pub exclude_patterns: Vec<UrlPattern>,
+    /// When non-empty, URLs carrying any other query parameter are skipped.
+    /// Names may use `*`, e.g. `utm_*`.
+    #[serde(default)]
+    pub allowed_query_params: Vec<String>,
+    /// URLs carrying any of these query parameters are skipped. Checked on
+    /// the canonical URL, so parameters canonicalization strips never match.
+    #[serde(default)]
+    pub denied_query_params: Vec<String>,
+    /// How URLs are canonicalized before dedup, filtering and reporting.
+    #[serde(default)]
+    pub canonicalization: CanonicalizationPolicy,
+    /// Heuristics that stop the frontier following crawler traps.
+    #[serde(default)]
+    pub trap_detection: TrapDetectionConfig,
+    /// Fingerprint similarity (share of matching SimHash bits) at which two
+    /// pages count as near-duplicates.
+    #[serde(default = "default_near_duplicate_threshold")]
+    pub near_duplicate_threshold: f64,
+    /// Which hosts count as part of the site: their links are internal and
+    /// they get their own robots.txt, sitemap and llms.txt.
+    #[serde(default)]
+    pub scope: CrawlScope,
+    /// Most pages crawled from any one host; `max_pages` still caps the job.
+    #[serde(default)]
+    pub max_pages_per_host: Option<u32>,
+    /// Rules pulling custom fields (price, SKU, author, ...) out of every
+    /// page; results land in `ExtractedData.custom_extractions`.
+    #[serde(default)]
+    pub custom_extractors: Vec<ExtractorConfig>,
+}
+
+/// A custom extraction rule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractorConfig {
+    /// Unique within the job; identifies the rule's result.
+    pub name: String,
+    #[serde(rename = "type")]
+    pub extractor_type: ExtractorType,
+    /// CSS selector, regex, XPath-like path or JSON-LD path, per `type`.
+    pub selector: String,
+    /// Attribute to read instead of the text (CSS and XPath).
+    #[serde(default)]
+    pub attribute: Option<String>,
+    /// Only query JSON-LD nodes of this `@type` (JSON-LD paths).
+    #[serde(default)]
+    pub schema_type: Option<String>,
+    #[serde(default)]
+    pub mode: ExtractorMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractorType {
+    CssSelector,
+    /// Matches the raw HTML; the first capture group is the value, if any.
+    Regex,
+    /// A subset of XPath: `/` and `//` steps, `*`, `[n]`, `[@a]`, `[@a='v']`,
+    /// `contains()`/`starts-with()` on attributes, and a final `@attr` or
+    /// `text()`, e.g. `//div[@class='price']/span` or `//meta[@name='author']/@content`.
+    Xpath,
+    /// Dot-separated keys into the page's JSON-LD nodes, e.g. `offers.price`;
+    /// arrays along the way are searched element by element.
+    JsonLdPath,
+}
+
+/// What an extractor reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractorMode {
+    /// The matched values (capped) and their total count.
+    #[default]
+    Values,
+    /// Only the number of matches. CSS and XPath rules count matching
+    /// elements, whether or not they have a value.
+    Count,
+    /// Only whether anything matched.
+    Exists,
+}
+
+/// One extractor's findings on a page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractorResult {
+    pub name: String,
+    /// Matched values in page order; empty in `count` and `exists` modes.
+    #[serde(default)]
+    pub matches: Vec<String>,
+    /// All matches, including any past the values cap.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+}
+
+/// The hosts a crawl covers. Seed hosts are always in scope, and `www.` and
+/// the bare host are treated as one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CrawlScope {
+    /// Only the seed URLs' hosts.
+    #[default]
+    Host,
+    /// The seeds' registrable domains and every subdomain, e.g. `www`,
+    /// `blog` and `docs` under `example.com`.
+    Domain,
+    /// The seed hosts plus these.
+    Hosts { hosts: Vec<String> },
+}
+
+/// A URL include/exclude rule, matched against the URL's path and query
+/// (e.g. `/blog/post?page=2`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "pattern", rename_all = "lowercase")]
+pub enum UrlPattern {
+    /// `*` matches within a path segment and `**` across segments; a
+    /// trailing `/**` also matches the directory itself.
+    Glob(String),
+    Regex(String),
+}
+
+impl std::fmt::Display for UrlPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UrlPattern::Glob(p) => write!(f, "glob:{}", p),
+            UrlPattern::Regex(p) => write!(f, "regex:{}", p),
+        }
+    }
+}
+
+/// URL canonicalization applied to seeds, discovered links, sitemap URLs and
+/// canonical tags alike. Scheme and host are always lowercased, fragments
+/// dropped, trailing slashes trimmed and default ports (`:80`, `:443`)
+/// removed; every other rule is opt-in, so the default leaves URLs as they
+/// were canonicalized before the policy existed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanonicalizationPolicy {
+    /// Drop known tracking and session parameters (`utm_*`, `gclid`,
+    /// `fbclid`, `jsessionid`, ...), including `;jsessionid=` path parameters.
+    #[serde(default)]
+    pub strip_tracking_params: bool,
+    /// More parameter names to drop. A trailing `*` matches a prefix. Only
+    /// applied with `strip_tracking_params`.
+    #[serde(default)]
+    pub extra_tracking_params: Vec<String>,
+    /// Sort query parameters by name, keeping the order of repeated names.
+    #[serde(default)]
+    pub sort_query_params: bool,
+    /// Uppercase percent-escapes and decode escaped unreserved characters,
+    /// so `%7e`, `%7E` and `~` compare equal.
+    #[serde(default)]
+    pub normalize_percent_encoding: bool,
+    /// Treat `/docs/index.html` as `/docs`.
+    #[serde(default)]
+    pub collapse_index_html: bool,
+    /// Lowercase the path, for case-insensitive servers. The lowercased URL
+    /// is the one fetched.
+    #[serde(default)]
+    pub lowercase_path: bool,
+}
+
+/// Limits past which a discovered URL is treated as part of a crawler trap
+/// (infinite calendars, faceted search, `/a/b/a/b/...` loops) and skipped.
+/// Seeds are never checked; sitemap URLs skip the per-template caps.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrapDetectionConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_max_path_length")]
+    pub max_path_length: u32,
+    #[serde(default = "default_max_query_length")]
+    pub max_query_length: u32,
+    /// How often a path segment, or a run of segments, may repeat.
+    #[serde(default = "default_max_segment_repeats")]
+    pub max_segment_repeats: u32,
+    /// URLs queued per template, where a template replaces numbers, dates and
+    /// IDs in the path and drops query values (`/events/{n}/{n}?view=`).
+    #[serde(default = "default_max_urls_per_template")]
+    pub max_urls_per_template: u32,
+    /// Distinct query strings queued for one path.
+    #[serde(default = "default_max_query_variants")]
+    pub max_query_variants_per_path: u32,
+}
+
+impl Default for TrapDetectionConfig {
+    fn default() -> Self {
+        TrapDetectionConfig {
+            enabled: true,
+            max_path_length: default_max_path_length(),
+            max_query_length: default_max_query_length(),
+            max_segment_repeats: default_max_segment_repeats(),
+            max_urls_per_template: default_max_urls_per_template(),
+            max_query_variants_per_path: default_max_query_variants(),
+        }
+    }
+}
+
+fn default_near_duplicate_threshold() -> f64 {
+    0.9
+}
+
+fn default_max_path_length() -> u32 {
+    512
+}
+
+fn default_max_query_length() -> u32 {
+    256
+}
+
+fn default_max_segment_repeats() -> u32 {
+    2
+}
+
+fn default_max_urls_per_template() -> u32 {
+    500
+}
+
+fn default_max_query_variants() -> u32 {
+    50
+}
+
+/// What the previous crawl recorded for a URL: the validators to send back
+/// and the stored HTML a 304 response can reuse.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreviousValidators {
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    pub html_r2_key: String,
+    #[serde(default)]
+    pub markdown_r2_key: Option<String>,
+    #[serde(default)]
+    pub markdown_tokens: Option<u32>,
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    #[serde(default)]
+    pub content_fingerprint: Option<String>,
+    /// Internal links found on the page last time, so an unchanged page still
+    /// feeds the frontier.
+    #[serde(default)]
+    pub internal_links: Vec<String>,
 }
 
 fn default_true() -> bool {
@@ -58,20 +308,60 @@ fn default_timeout_s() -> u32 {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CrawlJobPayload {
+    /// Names the job's checkpoint and outbox files; see `is_valid_job_id`.
     pub job_id: String,
     pub callback_url: String,
     pub config: CrawlConfig,
 }
 
+/// Whether `job_id` is safe to join into a file path: one or more ASCII
+/// letters, digits, `_` or `-`.
+pub fn is_valid_job_id(job_id: &str) -> bool {
+    !job_id.is_empty()
+        && job_id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
+
 // --- Extracted Link ---
 
-/// A link extracted from a page with metadata for backlink tracking.
+/// A link extracted from a page with metadata for backlink tracking and the
+/// internal link graph.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtractedLink {
     pub url: String,
     pub anchor_text: String,
     pub rel: String, // e.g. "nofollow", "sponsored", "" for dofollow
     pub is_external: bool,
+    /// Where on the page the link sits; unknown for links only the JS
+    /// renderer found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placement: Option<LinkPlacement>,
+}
+
+/// The page region a link sits in, from its nearest `<nav>`, `<header>`,
+/// `<footer>` or `<aside>` ancestor (or the matching ARIA role). Headers and
+/// footers inside `<main>`/`<article>` belong to the content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkPlacement {
+    Nav,
+    Header,
+    Footer,
+    Aside,
+    Content,
+}
+
+/// One internal link in a job's link graph artifact. Source and target are
+/// canonicalized the way the frontier dedups URLs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkEdge {
+    pub source: String,
+    pub target: String,
+    pub anchor_text: String,
+    pub rel: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placement: Option<LinkPlacement>,
 }
 
 // --- Hreflang Alternate ---
@@ -85,7 +375,7 @@ pub struct HreflangAlternate {
 
 // --- Extracted Data ---
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ExtractedData {
     pub h1: Vec<String>,
     pub h2: Vec<String>,
@@ -98,6 +388,10 @@ pub struct ExtractedData {
     pub external_links: Vec<String>,
     #[serde(default)]
     pub external_link_details: Vec<ExtractedLink>,
+    /// `internal_links` with anchor text, rel and placement, in page order.
+    /// A URL linked twice appears twice.
+    #[serde(default)]
+    pub internal_link_details: Vec<ExtractedLink>,
     pub images_without_alt: u32,
     pub has_robots_meta: bool,
     pub robots_directives: Vec<String>,
@@ -105,6 +399,9 @@ pub struct ExtractedData {
     pub og_tags: Option<HashMap<String, String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub structured_data: Option<Vec<serde_json::Value>>,
+    /// The syntax each `structured_data` node was read from, index for index.
+    #[serde(default)]
+    pub structured_data_sources: Vec<StructuredDataSource>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub flesch_score: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -148,6 +445,97 @@ pub struct ExtractedData {
     pub has_howto_schema: bool,
     #[serde(default)]
     pub has_breadcrumb_schema: bool,
+    /// Results of the job's `custom_extractors`, in rule order.
+    #[serde(default)]
+    pub custom_extractions: Vec<ExtractorResult>,
+    /// One report per structured data node, in `structured_data` order.
+    #[serde(default)]
+    pub schema_validation: Vec<SchemaNodeReport>,
+    #[serde(default)]
+    pub schema_parse_errors: Vec<SchemaParseError>,
+    /// Main content split into quotable passages, in document order.
+    #[serde(default)]
+    pub passages: Vec<ContentPassage>,
+}
+
+/// A run of main content under one heading, split at paragraph boundaries:
+/// the unit an answer engine would quote.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentPassage {
+    /// Headings the passage sits under, outermost first (H1 > H2 > H3).
+    pub heading_path: Vec<String>,
+    /// Start of the passage text; the full text is in the page's Markdown.
+    pub excerpt: String,
+    pub word_count: u32,
+    /// Estimated LLM tokens.
+    pub token_count: u32,
+    /// States what something is ("X is a ...", "X refers to ...").
+    pub has_definition: bool,
+    /// Contains a figure: a percentage, price, measurement or quantity of
+    /// several digits. Years and single digits don't count.
+    pub has_statistic: bool,
+    pub has_list: bool,
+    pub has_table: bool,
+    /// Long enough to stand alone, and doesn't open with or lean on a
+    /// reference to text outside it ("This ...", "as mentioned above").
+    pub is_self_contained: bool,
+}
+
+/// Validation of one top-level structured data node against the required and
+/// recommended properties of its schema.org type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaNodeReport {
+    #[serde(default)]
+    pub source: StructuredDataSource,
+    /// The node's `@type` values, without any `schema:` or URL prefix.
+    pub types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Missing required properties and invalid values, here or in nested
+    /// entities. A node with errors is not eligible for rich results.
+    #[serde(default)]
+    pub errors: Vec<SchemaIssue>,
+    /// Missing recommended properties.
+    #[serde(default)]
+    pub warnings: Vec<SchemaIssue>,
+    /// Distinct types of the entities nested in this node, in document order.
+    #[serde(default)]
+    pub nested_types: Vec<String>,
+}
+
+/// The markup a structured data node came from. Microdata and RDFa are
+/// converted to JSON-LD-shaped nodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StructuredDataSource {
+    #[default]
+    JsonLd,
+    Microdata,
+    Rdfa,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaIssue {
+    /// Dotted path from the node, e.g. `offers.priceCurrency`; alternatives
+    /// are joined with `|`.
+    pub property: String,
+    pub kind: SchemaIssueKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaIssueKind {
+    Missing,
+    Invalid,
+}
+
+/// A `<script type="application/ld+json">` block that isn't valid JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaParseError {
+    /// Position of the block among the page's non-empty JSON-LD scripts.
+    pub block_index: u32,
+    pub message: String,
 }
 
 // --- Lighthouse Result ---
@@ -164,18 +552,139 @@ pub struct LighthouseResult {
 
 // --- Site Context ---
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SitemapAnalysis {
+    /// At least one sitemap parsed and none broke the protocol.
     pub is_valid: bool,
     pub url_count: u32,
+    /// URLs whose `lastmod` is more than a year old.
     pub stale_url_count: u32,
     pub discovered_page_count: u32,
+    /// Sitemap files fetched and parsed, indexes included.
+    #[serde(default)]
+    pub sitemap_count: u32,
+    #[serde(default)]
+    pub urls_with_lastmod: u32,
+    #[serde(default)]
+    pub image_count: u32,
+    #[serde(default)]
+    pub video_count: u32,
+    #[serde(default)]
+    pub news_count: u32,
+    /// `xhtml:link` hreflang alternates across all URLs.
+    #[serde(default)]
+    pub alternate_count: u32,
+    /// Protocol violations, e.g. `too_many_urls`, `file_too_large`, `invalid_lastmod`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<String>,
+}
+
+/// One H2 section of an llms.txt file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmsTxtSection {
+    pub title: String,
+    pub link_count: u32,
+    /// The `## Optional` section, whose links consumers may skip.
+    pub optional: bool,
+}
+
+/// A same-site link listed in llms.txt that didn't resolve.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmsTxtBrokenLink {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    /// Fetch error kind when there was no response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Parsed and validated /llms.txt, per the llmstxt.org proposal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmsTxtReport {
+    pub url: String,
+    pub size_bytes: u64,
+    pub token_estimate: u64,
+    /// Empty, or HTML served with a 200 (a soft 404 or catch-all route) rather
+    /// than a markdown file. `SiteContext.has_llms_txt` is false for these.
+    pub is_placeholder: bool,
+    /// No structural errors; warnings don't count against it.
+    pub is_valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub sections: Vec<LlmsTxtSection>,
+    pub link_count: u32,
+    /// Issue codes that make the file invalid, e.g. `missing_title`.
+    #[serde(default)]
+    pub errors: Vec<String>,
+    /// Issue codes worth fixing that don't break the format, e.g. `missing_summary`.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Links pointing at other hosts; these aren't fetched.
+    pub external_link_count: u32,
+    pub links_checked: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub broken_links: Vec<LlmsTxtBrokenLink>,
+}
+
+/// Size of /llms-full.txt, the single-file expansion of llms.txt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmsFullTxt {
+    pub url: String,
+    pub size_bytes: u64,
+    pub token_estimate: u64,
+    /// The body exceeded the byte cap and wasn't read to the end, so the size
+    /// is a lower bound.
+    pub truncated: bool,
+}
+
+/// What an AI crawler fetches pages for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiCrawlerPurpose {
+    /// Collects content to train models.
+    Training,
+    /// Indexes pages so an AI search or answer engine can cite them.
+    SearchRetrieval,
+    /// Fetches a page on behalf of a user, e.g. a link pasted into a chat.
+    UserFetch,
+}
+
+/// robots.txt access for one AI crawler from the catalog.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiCrawlerAccess {
+    pub user_agent: String,
+    pub vendor: String,
+    pub purpose: AiCrawlerPurpose,
+    /// robots.txt has a group for this bot, rather than it falling back to `*`.
+    pub named_in_robots: bool,
+    pub root_allowed: bool,
+    /// Sampled top-level sections (e.g. `/blog/`) this bot may not fetch.
+    #[serde(default)]
+    pub blocked_sections: Vec<String>,
+    /// Key page types (e.g. `pricing`) whose sampled URL this bot may not fetch.
+    #[serde(default)]
+    pub blocked_page_types: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SiteContext {
+    /// A real llms.txt was found; placeholders don't count.
     pub has_llms_txt: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llms_txt: Option<LlmsTxtReport>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llms_full_txt: Option<LlmsFullTxt>,
+    /// Bots the `AI_CRAWLER_BLOCKED` score factor considers that are blocked at
+    /// the site root.
     pub ai_crawlers_blocked: Vec<String>,
+    /// Per-bot access for the whole AI crawler catalog, at the root and across
+    /// sampled sections and key page types. Empty when robots.txt wasn't checked.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ai_crawler_access: Vec<AiCrawlerAccess>,
     pub has_sitemap: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sitemap_analysis: Option<SitemapAnalysis>,
@@ -188,7 +697,7 @@ pub struct SiteContext {
 
 // --- Crawl Page Result ---
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CrawlPageResult {
     pub url: String,
     pub status_code: u16,
@@ -198,6 +707,13 @@ pub struct CrawlPageResult {
     pub word_count: u32,
     pub content_hash: String,
     pub html_r2_key: String,
+    /// Main content as Markdown, gzipped in R2; absent for non-HTML pages and
+    /// pages without text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub markdown_r2_key: Option<String>,
+    /// Estimated LLM tokens in the Markdown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub markdown_tokens: Option<u32>,
     pub extracted: ExtractedData,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub lighthouse: Option<LighthouseResult>,
@@ -216,6 +732,22 @@ pub struct CrawlPageResult {
     pub is_cross_domain_redirect: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub redirect_url: Option<String>,
+    /// The server answered a conditional request with 304: the page is the
+    /// same as last crawl, `html_r2_key` and `markdown_r2_key` point at the
+    /// stored copies, and only `internal_links` is populated in `extracted`.
+    #[serde(default)]
+    pub unchanged: bool,
+    /// SimHash of the main-content text as 16 hex digits; absent for thin
+    /// pages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_fingerprint: Option<String>,
+    /// First page crawled in this page's near-duplicate cluster.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub near_duplicate_of: Option<String>,
+    /// Cluster id, shared with the pages listed in the final batch's
+    /// `near_duplicate_clusters`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub near_duplicate_cluster: Option<u32>,
 }
 
 // --- Crawl Stats ---
@@ -226,6 +758,187 @@ pub struct CrawlStats {
     pub pages_crawled: u32,
     pub pages_errored: u32,
     pub elapsed_s: f64,
+    /// Requests per second the slowest host was paced at; see
+    /// `effective_rate_per_host`. Reported on the final batch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_rate_per_s: Option<f64>,
+    /// Slowest requests per second each fetched host was held to, after any
+    /// robots.txt `Crawl-delay` or server `Retry-After`. Reported on the
+    /// final batch.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub effective_rate_per_host: BTreeMap<String, f64>,
+    /// Discovered URLs each include/exclude or query-parameter rule kept out
+    /// of the frontier, keyed by rule (e.g. `exclude:glob:/tag/**`). Reported
+    /// on the final batch.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub filtered_by_rule: BTreeMap<String, u32>,
+}
+
+// --- Page Outcomes ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageOutcomeKind {
+    /// The fetch failed (DNS, connection, timeout, ...).
+    Failed,
+    /// robots.txt disallows the URL for our user agent.
+    Blocked,
+    /// The body hashed the same as a page already delivered.
+    Duplicate,
+}
+
+/// A URL the crawler visited but didn't deliver as a page, and why.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageOutcome {
+    pub url: String,
+    pub outcome: PageOutcomeKind,
+    /// `FetchError` class for failed pages, e.g. `"timeout"` or `"dns"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Last HTTP status seen for the URL, when there was one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    /// The robots.txt rule that blocked the URL, e.g. `"Disallow: /private"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub robots_rule: Option<String>,
+    /// URL of the delivered page this one duplicates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+}
+
+// --- Sitemap Reconciliation ---
+
+/// Why a URL listed in the sitemap shouldn't be there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SitemapUrlIssueKind {
+    /// The URL redirects; the sitemap should list the target.
+    Redirect,
+    /// The final response wasn't a 200, or the fetch failed outright.
+    #[serde(rename = "non_200")]
+    Non200,
+    /// The page carries a robots `noindex`.
+    Noindex,
+    /// The page's canonical points at a different URL.
+    CanonicalizedElsewhere,
+    /// robots.txt disallows the URL, so it was never fetched.
+    BlockedByRobots,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SitemapUrlIssue {
+    pub url: String,
+    pub kind: SitemapUrlIssueKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    /// Redirect target or canonical URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// `FetchError` class when the fetch failed without a response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+    /// The robots.txt rule that blocked the URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub robots_rule: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrawlTrapKind {
+    /// A segment or run of segments repeats, e.g. `/a/b/a/b/a/b`.
+    RepeatedSegments,
+    PathTooLong,
+    QueryTooLong,
+    /// Too many URLs share one template, e.g. `/calendar/{n}/{n}`.
+    TemplateCap,
+    /// Too many query strings on one path, e.g. faceted navigation.
+    QueryVariantCap,
+}
+
+/// Site structure computed from the internal links of every delivered page.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SiteSummary {
+    /// The crawl didn't reach every page (budget, cancellation, or a resume
+    /// that lost earlier links), so orphans and depths are indicative only.
+    pub partial: bool,
+    pub page_count: u32,
+    pub orphan_count: u32,
+    pub dead_end_count: u32,
+    /// Pages no seed links through to.
+    pub unreachable_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_click_depth: Option<u32>,
+    /// Pages at each click depth, indexed by depth.
+    pub click_depth_histogram: Vec<u32>,
+    /// Highest PageRank first, capped at 500 pages.
+    pub pages: Vec<PageLinkMetrics>,
+    /// Every delivered page's metrics, as gzipped JSON lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_metrics_r2_key: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageLinkMetrics {
+    pub url: String,
+    /// Fewest clicks from a seed; `None` when unreachable by links.
+    pub click_depth: Option<u32>,
+    /// Distinct crawled pages linking here.
+    pub inlinks: u32,
+    /// Distinct crawled pages linked from here.
+    pub outlinks: u32,
+    /// Share of internal PageRank over followed links; sums to 1 across pages.
+    pub pagerank: f64,
+    /// Links to no other crawled page.
+    pub is_dead_end: bool,
+    /// No crawled page links here and it isn't a seed.
+    pub is_orphan: bool,
+}
+
+/// Pages whose main content is nearly the same. `urls` is capped; the count
+/// is not.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NearDuplicateCluster {
+    pub id: u32,
+    /// The first page crawled in the cluster; the others are near-duplicates
+    /// of it.
+    pub representative_url: String,
+    pub page_count: u32,
+    pub urls: Vec<String>,
+}
+
+/// A suspected crawler trap and how many URLs it cost. `pattern` is the URL
+/// template (or path) the skipped URLs share.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawlTrap {
+    pub kind: CrawlTrapKind,
+    pub pattern: String,
+    pub example_url: String,
+    pub skipped_count: u32,
+}
+
+/// End-of-job comparison of the sitemap against what the crawl found. URL
+/// lists are capped; the counts are not.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SitemapReconciliation {
+    pub sitemap_url_count: u32,
+    /// Sitemap URLs the crawl fetched.
+    pub crawled_sitemap_url_count: u32,
+    /// The crawl didn't cover the whole site (page budget, cancellation or a
+    /// resumed job), so some orphans may just be pages it never reached.
+    pub partial: bool,
+    /// Sitemap URLs no crawled page links to.
+    pub orphan_count: u32,
+    #[serde(default)]
+    pub orphan_urls: Vec<String>,
+    /// Indexable crawled pages the sitemap doesn't list.
+    pub missing_from_sitemap_count: u32,
+    #[serde(default)]
+    pub missing_from_sitemap: Vec<String>,
+    pub issue_count: u32,
+    #[serde(default)]
+    pub issues: Vec<SitemapUrlIssue>,
 }
 
 // --- Crawl Result Batch ---
@@ -236,7 +949,29 @@ pub struct CrawlResultBatch {
     pub batch_index: u32,
     pub is_final: bool,
     pub pages: Vec<CrawlPageResult>,
+    /// URLs that produced no page: fetch failures, robots blocks, duplicates.
+    #[serde(default)]
+    pub outcomes: Vec<PageOutcome>,
     pub stats: CrawlStats,
+    /// Sitemap vs crawl comparison; final batch only, when a sitemap was found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sitemap_reconciliation: Option<SitemapReconciliation>,
+    /// Suspected crawler traps the frontier cut off; final batch only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crawl_traps: Vec<CrawlTrap>,
+    /// Click depth, internal links and PageRank per page; final batch only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_summary: Option<SiteSummary>,
+    /// R2 key of this batch's part of the internal link graph: gzipped JSON
+    /// lines of `LinkEdge`, one per internal link on the batch's pages. The
+    /// job's edge list is the union of every batch's part.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_edges_r2_key: Option<String>,
+    /// Every near-duplicate cluster found, including each cluster's first
+    /// page, which was delivered before it had any duplicates. Final batch
+    /// only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub near_duplicate_clusters: Vec<NearDuplicateCluster>,
 }
 
 // --- Job Status ---
@@ -259,4 +994,17 @@ pub struct JobStatus {
     pub status: JobStatusKind,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stats: Option<CrawlStats>,
+    /// 1-based position in the admission queue while `Queued`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<usize>,
+    /// Rough seconds until a queued job starts, from recent job durations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_start_s: Option<u64>,
+    /// Index of the last batch delivered to the callback endpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_batch_index: Option<u32>,
+    /// Batches the callback endpoint hasn't acknowledged and that are queued
+    /// in the outbox for redelivery; present only while delivery is degraded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub undelivered_batches: Option<usize>,
 }
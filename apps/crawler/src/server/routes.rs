@@ -10,13 +10,20 @@ use axum::{
 use futures::stream::{Stream, StreamExt};
 use serde_json::json;
 
-use crate::models::CrawlJobPayload;
+use crate::crawler::egress::EgressPolicy;
+use crate::crawler::extractor::Extractors;
+use crate::crawler::url_rules::UrlFilter;
+use crate::jobs::{CancelError, ResumeError};
+use crate::models::{is_valid_job_id, CrawlJobPayload};
 use crate::AppState;
 
 /// POST /api/v1/jobs
 ///
 /// Accepts a new crawl job payload. Validates the input and returns 202 Accepted.
-/// Actual job processing will be wired up in Task 8.
+/// Seed URLs the egress policy refuses (internal addresses, non-HTTP schemes)
+/// are rejected with 422 before the job is queued, as are job ids that aren't
+/// `[A-Za-z0-9_-]+`, include/exclude rules and custom extractors that don't
+/// compile.
 pub async fn create_job(
     State(state): State<AppState>,
     Json(payload): Json<CrawlJobPayload>,
@@ -28,6 +35,59 @@ pub async fn create_job(
         "Received crawl job"
     );
 
+    // The id names files under the checkpoint directory.
+    if !is_valid_job_id(&payload.job_id) {
+        tracing::warn!(job_id = %payload.job_id, "Invalid job id");
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "job_id": payload.job_id,
+                "error": "job_id must be one or more of A-Z, a-z, 0-9, _ and -",
+                "error_kind": "invalid_job_id",
+            })),
+        );
+    }
+
+    let egress = EgressPolicy::from_config(&state.config);
+    for url in &payload.config.seed_urls {
+        if let Err(e) = egress.check_url(url) {
+            tracing::warn!(job_id = %payload.job_id, url = %url, error = %e, "Seed URL refused");
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({
+                    "job_id": payload.job_id,
+                    "error": e.to_string(),
+                    "error_kind": "egress_blocked",
+                    "url": url,
+                })),
+            );
+        }
+    }
+
+    if let Err(e) = UrlFilter::from_config(&payload.config) {
+        tracing::warn!(job_id = %payload.job_id, error = %e, "Invalid URL rules");
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "job_id": payload.job_id,
+                "error": e.to_string(),
+                "error_kind": "invalid_url_rules",
+            })),
+        );
+    }
+
+    if let Err(e) = Extractors::from_config(&payload.config.custom_extractors) {
+        tracing::warn!(job_id = %payload.job_id, error = %e, "Invalid custom extractors");
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "job_id": payload.job_id,
+                "error": e.to_string(),
+                "error_kind": "invalid_extractor",
+            })),
+        );
+    }
+
     state.job_manager.submit(payload.clone()).await;
 
     (
@@ -55,22 +115,70 @@ pub async fn get_job_status(
 
 /// POST /api/v1/jobs/:id/cancel
 ///
-/// Cancels a running crawl job. Stub implementation for now.
+/// Cancels a queued or running crawl job. Returns 404 for an unknown job and
+/// 409 when the job has already finished.
 pub async fn cancel_job(
     State(state): State<AppState>,
     Path(job_id): Path<String>,
 ) -> impl IntoResponse {
     tracing::info!(job_id = %job_id, "Cancel request");
 
-    state.job_manager.cancel(&job_id).await;
+    match state.job_manager.cancel(&job_id).await {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(json!({
+                "job_id": job_id,
+                "status": "cancelled"
+            })),
+        ),
+        Err(e) => {
+            let code = match e {
+                CancelError::NotFound(_) => StatusCode::NOT_FOUND,
+                CancelError::AlreadyFinished(_) => StatusCode::CONFLICT,
+            };
+            (
+                code,
+                Json(json!({
+                    "job_id": job_id,
+                    "error": e.to_string()
+                })),
+            )
+        }
+    }
+}
 
-    (
-        StatusCode::OK,
-        Json(json!({
-            "job_id": job_id,
-            "status": "cancelled"
-        })),
-    )
+/// POST /api/v1/jobs/:id/resume
+///
+/// Resumes an interrupted crawl from its on-disk checkpoint. Returns 404 when no
+/// checkpoint exists and 409 when the job is still running.
+pub async fn resume_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> impl IntoResponse {
+    tracing::info!(job_id = %job_id, "Resume request");
+
+    match state.job_manager.resume(&job_id).await {
+        Ok(()) => (
+            StatusCode::ACCEPTED,
+            Json(json!({
+                "job_id": job_id,
+                "status": "queued"
+            })),
+        ),
+        Err(e) => {
+            let code = match e {
+                ResumeError::NotFound(_) => StatusCode::NOT_FOUND,
+                ResumeError::AlreadyRunning(_) => StatusCode::CONFLICT,
+            };
+            (
+                code,
+                Json(json!({
+                    "job_id": job_id,
+                    "error": e.to_string()
+                })),
+            )
+        }
+    }
 }
 
 /// GET /api/v1/jobs/:id/events
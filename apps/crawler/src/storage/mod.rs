@@ -75,6 +75,42 @@ impl StorageClient {
         Ok(())
     }
 
+    /// Upload gzipped Markdown to the given key.
+    pub async fn upload_markdown(&self, key: &str, markdown: &str) -> Result<(), StorageError> {
+        let compressed = gzip_bytes(markdown.as_bytes())?;
+
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(ByteStream::from(compressed))
+            .content_type("text/markdown; charset=utf-8")
+            .content_encoding("gzip")
+            .send()
+            .await
+            .map_err(|e| StorageError::UploadError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Upload gzipped newline-delimited JSON to the given key.
+    pub async fn upload_json_lines(&self, key: &str, lines: &str) -> Result<(), StorageError> {
+        let compressed = gzip_bytes(lines.as_bytes())?;
+
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(ByteStream::from(compressed))
+            .content_type("application/x-ndjson")
+            .content_encoding("gzip")
+            .send()
+            .await
+            .map_err(|e| StorageError::UploadError(e.to_string()))?;
+
+        Ok(())
+    }
+
     /// Upload gzipped JSON content to the given key.
     pub async fn upload_json(&self, key: &str, json_content: &str) -> Result<(), StorageError> {
         let compressed = gzip_bytes(json_content.as_bytes())?;
@@ -1,4 +1,5 @@
 use axum::http::StatusCode;
+use axum::response::IntoResponse;
 use axum_test::TestServer;
 use crawler::{build_app, config::Config, jobs::JobManager, AppState};
 use hmac::{Hmac, Mac};
@@ -32,6 +33,15 @@ fn create_test_config() -> Config {
         renderer_timeout_s: 15,
         batch_page_threshold: 25,
         batch_interval_secs: 15,
+        // A fresh directory per test, so one test's checkpoints and outbox
+        // batches can't leak into another (or into a later run).
+        checkpoint_dir: std::env::temp_dir()
+            .join(format!("crawler-test-{}", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string(),
+        job_store: "memory".to_string(),
+        redis_url: None,
+        egress_allow_ips: vec!["127.0.0.1".parse().unwrap()],
     }
 }
 
@@ -125,3 +135,632 @@ async fn test_create_and_check_job() {
     let status_str = status_json["status"].as_str().unwrap();
     assert!(["queued", "crawling", "failed", "pending"].contains(&status_str));
 }
+
+#[tokio::test]
+async fn test_resume_without_checkpoint_returns_not_found() {
+    let config = Arc::new(create_test_config());
+    let job_manager = Arc::new(JobManager::new(config.clone()));
+    let state = AppState {
+        config: config.clone(),
+        job_manager,
+    };
+    let server = TestServer::new(build_app(state)).unwrap();
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+    let signature = compute_signature("", &timestamp, &config.shared_secret);
+
+    let response = server
+        .post("/api/v1/jobs/no-such-job/resume")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .await;
+
+    response.assert_status(StatusCode::NOT_FOUND);
+    let json = response.json::<serde_json::Value>();
+    assert_eq!(json["job_id"], "no-such-job");
+}
+
+#[tokio::test]
+async fn test_jobs_beyond_max_concurrent_are_queued_and_cancellable() {
+    // A listener that accepts connections but never answers keeps the first
+    // job busy fetching its seed page, holding the only crawl slot. The first
+    // accepted connection signals that the job has been admitted.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let admitted = Arc::new(tokio::sync::Notify::new());
+    let admitted_tx = admitted.clone();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((sock, _)) = listener.accept().await {
+            admitted_tx.notify_one();
+            held.push(sock);
+        }
+    });
+
+    let config = Arc::new(create_test_config()); // max_concurrent_jobs = 1
+    let job_manager = Arc::new(JobManager::new(config.clone()));
+    let state = AppState {
+        config: config.clone(),
+        job_manager,
+    };
+    let server = TestServer::new(build_app(state)).unwrap();
+
+    let sign = |body: &str| {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string();
+        let signature = compute_signature(body, &timestamp, &config.shared_secret);
+        (timestamp, signature)
+    };
+
+    let submit = |job_id: &'static str| {
+        let payload = json!({
+            "job_id": job_id,
+            "callback_url": "http://127.0.0.1:9/callback",
+            "config": {
+                "seed_urls": [format!("http://{addr}/")],
+                "max_pages": 1,
+                "max_depth": 0,
+                "respect_robots": false,
+                "run_lighthouse": false,
+                "check_llms_txt": false
+            }
+        });
+        let (timestamp, signature) = sign(&serde_json::to_string(&payload).unwrap());
+        server
+            .post("/api/v1/jobs")
+            .add_header("X-Timestamp", timestamp)
+            .add_header("X-Signature", signature)
+            .json(&payload)
+    };
+
+    submit("slot-holder")
+        .await
+        .assert_status(StatusCode::ACCEPTED);
+    tokio::time::timeout(std::time::Duration::from_secs(5), admitted.notified())
+        .await
+        .expect("first job never started");
+    submit("waiting-job")
+        .await
+        .assert_status(StatusCode::ACCEPTED);
+
+    // The processing loop puts the second job behind the slot holder.
+    let status = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        loop {
+            let (timestamp, signature) = sign("");
+            let status = server
+                .get("/api/v1/jobs/waiting-job/status")
+                .add_header("X-Timestamp", timestamp)
+                .add_header("X-Signature", signature)
+                .await
+                .json::<serde_json::Value>();
+            if status.get("queue_position").is_some() {
+                break status;
+            }
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .expect("second job never queued");
+    assert_eq!(status["status"], "queued");
+    assert_eq!(status["queue_position"], 1);
+    assert!(status["estimated_start_s"].as_u64().unwrap() > 0);
+
+    let (timestamp, signature) = sign("");
+    server
+        .post("/api/v1/jobs/waiting-job/cancel")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .await
+        .assert_status(StatusCode::OK);
+
+    let (timestamp, signature) = sign("");
+    let status = server
+        .get("/api/v1/jobs/waiting-job/status")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .await
+        .json::<serde_json::Value>();
+    assert_eq!(status["status"], "cancelled");
+    assert!(status.get("queue_position").is_none());
+
+    // A finished job can't be cancelled again, and an unknown one isn't found.
+    for (job_id, code) in [
+        ("waiting-job", StatusCode::CONFLICT),
+        ("no-such-job", StatusCode::NOT_FOUND),
+    ] {
+        let (timestamp, signature) = sign("");
+        server
+            .post(&format!("/api/v1/jobs/{job_id}/cancel"))
+            .add_header("X-Timestamp", timestamp)
+            .add_header("X-Signature", signature)
+            .await
+            .assert_status(code);
+    }
+
+    // A cancelled job still winding down (stuck on its seed fetch) can't be
+    // resumed into a second crawl.
+    let (timestamp, signature) = sign("");
+    server
+        .post("/api/v1/jobs/slot-holder/cancel")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .await
+        .assert_status(StatusCode::OK);
+    let (timestamp, signature) = sign("");
+    server
+        .post("/api/v1/jobs/slot-holder/resume")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .await
+        .assert_status(StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_status_falls_back_to_job_store_after_restart() {
+    use crawler::jobs::store::JobStore;
+    use crawler::models::{CrawlStats, JobStatusKind};
+
+    // A record written by an earlier process (or another instance).
+    let store = JobStore::memory();
+    let stats = CrawlStats {
+        pages_found: 12,
+        pages_crawled: 12,
+        pages_errored: 0,
+        elapsed_s: 3.5,
+        effective_rate_per_s: None,
+        effective_rate_per_host: Default::default(),
+        filtered_by_rule: Default::default(),
+    };
+    store
+        .set_status("finished-job", JobStatusKind::Complete, Some(&stats))
+        .await
+        .unwrap();
+    store.record_batch("finished-job", 2).await.unwrap();
+
+    let config = Arc::new(create_test_config());
+    let job_manager = Arc::new(JobManager::with_store(config.clone(), store));
+    let state = AppState {
+        config: config.clone(),
+        job_manager,
+    };
+    let server = TestServer::new(build_app(state)).unwrap();
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+    let signature = compute_signature("", &timestamp, &config.shared_secret);
+    let status = server
+        .get("/api/v1/jobs/finished-job/status")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .await
+        .json::<serde_json::Value>();
+
+    assert_eq!(status["status"], "complete");
+    assert_eq!(status["stats"]["pages_crawled"], 12);
+    assert_eq!(status["last_batch_index"], 2);
+}
+
+#[tokio::test]
+async fn test_callback_is_retried_until_acknowledged() {
+    use axum::{extract::State, routing::get, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // One server plays both the crawled site and the API: the callback endpoint
+    // answers 503 twice before accepting the batch.
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let site = Router::new()
+        .route(
+            "/",
+            get(|| async {
+                axum::response::Html(
+                    "<html><head><title>Home</title></head><body><p>Hello</p></body></html>",
+                )
+            }),
+        )
+        .route(
+            "/callback",
+            axum::routing::post(|State(attempts): State<Arc<AtomicUsize>>| async move {
+                if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::OK
+                }
+            }),
+        )
+        .with_state(attempts.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, site).await.unwrap() });
+
+    let config = Arc::new(create_test_config());
+    let job_manager = Arc::new(JobManager::new(config.clone()));
+    let state = AppState {
+        config: config.clone(),
+        job_manager,
+    };
+    let server = TestServer::new(build_app(state)).unwrap();
+
+    let sign = |body: &str| {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string();
+        let signature = compute_signature(body, &timestamp, &config.shared_secret);
+        (timestamp, signature)
+    };
+
+    let payload = json!({
+        "job_id": "retried-callback",
+        "callback_url": format!("http://{addr}/callback"),
+        "config": {
+            "seed_urls": [format!("http://{addr}/")],
+            "max_pages": 1,
+            "max_depth": 0,
+            "respect_robots": false,
+            "run_lighthouse": false,
+            "check_llms_txt": false
+        }
+    });
+    let (timestamp, signature) = sign(&serde_json::to_string(&payload).unwrap());
+    server
+        .post("/api/v1/jobs")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .json(&payload)
+        .await
+        .assert_status(StatusCode::ACCEPTED);
+
+    let mut status = serde_json::Value::Null;
+    for _ in 0..100 {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let (timestamp, signature) = sign("");
+        status = server
+            .get("/api/v1/jobs/retried-callback/status")
+            .add_header("X-Timestamp", timestamp)
+            .add_header("X-Signature", signature)
+            .await
+            .json::<serde_json::Value>();
+        if status["status"] == "complete" {
+            break;
+        }
+    }
+
+    assert_eq!(status["status"], "complete", "status: {status}");
+    assert_eq!(status["last_batch_index"], 0);
+    assert!(status.get("undelivered_batches").is_none());
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_not_modified_page_is_reported_unchanged() {
+    use axum::{extract::State, http::HeaderMap, routing::get, Router};
+
+    // The site honours If-None-Match; the callback endpoint records batches.
+    let batches = Arc::new(tokio::sync::Mutex::new(Vec::<serde_json::Value>::new()));
+    let site = Router::new()
+        .route(
+            "/",
+            get(|headers: HeaderMap| async move {
+                if headers.get("if-none-match").and_then(|v| v.to_str().ok()) == Some("\"v1\"") {
+                    StatusCode::NOT_MODIFIED.into_response()
+                } else {
+                    axum::response::Html("<html><body>changed</body></html>").into_response()
+                }
+            }),
+        )
+        .route(
+            "/callback",
+            axum::routing::post(
+                |State(batches): State<Arc<tokio::sync::Mutex<Vec<serde_json::Value>>>>,
+                 axum::Json(batch): axum::Json<serde_json::Value>| async move {
+                    batches.lock().await.push(batch);
+                    StatusCode::OK
+                },
+            ),
+        )
+        .with_state(batches.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, site).await.unwrap() });
+
+    let config = Arc::new(create_test_config());
+    let job_manager = Arc::new(JobManager::new(config.clone()));
+    let state = AppState {
+        config: config.clone(),
+        job_manager,
+    };
+    let server = TestServer::new(build_app(state)).unwrap();
+
+    let payload = json!({
+        "job_id": "conditional-recrawl",
+        "callback_url": format!("http://{addr}/callback"),
+        "config": {
+            "seed_urls": [format!("http://{addr}/")],
+            "max_pages": 1,
+            "max_depth": 0,
+            "respect_robots": false,
+            "run_lighthouse": false,
+            "check_llms_txt": false,
+            "previous_validators": {
+                format!("http://{addr}/"): {
+                    "etag": "\"v1\"",
+                    "html_r2_key": "crawls/last-week/html/0123456789abcdef.html.gz"
+                }
+            }
+        }
+    });
+    let body = serde_json::to_string(&payload).unwrap();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+    let signature = compute_signature(&body, &timestamp, &config.shared_secret);
+    server
+        .post("/api/v1/jobs")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .json(&payload)
+        .await
+        .assert_status(StatusCode::ACCEPTED);
+
+    let mut page = serde_json::Value::Null;
+    for _ in 0..100 {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        if let Some(batch) = batches.lock().await.iter().find(|b| b["is_final"] == true) {
+            page = batch["pages"][0].clone();
+            break;
+        }
+    }
+
+    assert_eq!(page["unchanged"], true, "page: {page}");
+    assert_eq!(page["status_code"], 304);
+    assert_eq!(
+        page["html_r2_key"],
+        "crawls/last-week/html/0123456789abcdef.html.gz"
+    );
+}
+
+#[tokio::test]
+async fn test_internal_seed_url_is_rejected() {
+    let config = Arc::new(create_test_config());
+    let job_manager = Arc::new(JobManager::new(config.clone()));
+    let state = AppState {
+        config: config.clone(),
+        job_manager,
+    };
+    let server = TestServer::new(build_app(state)).unwrap();
+
+    let payload = json!({
+        "job_id": "metadata-seed",
+        "callback_url": "http://localhost:3000/callback",
+        "config": {
+            "seed_urls": ["http://169.254.169.254/latest/meta-data/"],
+            "max_pages": 1,
+            "max_depth": 0
+        }
+    });
+    let body = serde_json::to_string(&payload).unwrap();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+    let signature = compute_signature(&body, &timestamp, &config.shared_secret);
+    let response = server
+        .post("/api/v1/jobs")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .json(&payload)
+        .await;
+
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    let json = response.json::<serde_json::Value>();
+    assert_eq!(json["error_kind"], "egress_blocked");
+    assert_eq!(json["url"], "http://169.254.169.254/latest/meta-data/");
+}
+
+#[tokio::test]
+async fn test_unsafe_job_id_is_rejected() {
+    let config = Arc::new(create_test_config());
+    let job_manager = Arc::new(JobManager::new(config.clone()));
+    let state = AppState {
+        config: config.clone(),
+        job_manager,
+    };
+    let server = TestServer::new(build_app(state)).unwrap();
+
+    let payload = json!({
+        "job_id": "..",
+        "callback_url": "http://localhost:3000/callback",
+        "config": {
+            "seed_urls": ["https://example.com"],
+            "max_pages": 1,
+            "max_depth": 0
+        }
+    });
+    let body = serde_json::to_string(&payload).unwrap();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+    let signature = compute_signature(&body, &timestamp, &config.shared_secret);
+    let response = server
+        .post("/api/v1/jobs")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .json(&payload)
+        .await;
+
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.json::<serde_json::Value>()["error_kind"],
+        "invalid_job_id"
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_url_rules_are_rejected() {
+    let config = Arc::new(create_test_config());
+    let job_manager = Arc::new(JobManager::new(config.clone()));
+    let state = AppState {
+        config: config.clone(),
+        job_manager,
+    };
+    let server = TestServer::new(build_app(state)).unwrap();
+
+    let payload = json!({
+        "job_id": "bad-rules",
+        "callback_url": "http://localhost:3000/callback",
+        "config": {
+            "seed_urls": ["https://example.com"],
+            "max_pages": 1,
+            "max_depth": 0,
+            "exclude_patterns": [{ "type": "regex", "pattern": "(" }]
+        }
+    });
+    let body = serde_json::to_string(&payload).unwrap();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+    let signature = compute_signature(&body, &timestamp, &config.shared_secret);
+    let response = server
+        .post("/api/v1/jobs")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .json(&payload)
+        .await;
+
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    let json = response.json::<serde_json::Value>();
+    assert_eq!(json["error_kind"], "invalid_url_rules");
+}
+
+#[tokio::test]
+async fn test_invalid_custom_extractor_is_rejected() {
+    let config = Arc::new(create_test_config());
+    let job_manager = Arc::new(JobManager::new(config.clone()));
+    let state = AppState {
+        config: config.clone(),
+        job_manager,
+    };
+    let server = TestServer::new(build_app(state)).unwrap();
+
+    let payload = json!({
+        "job_id": "bad-extractor",
+        "callback_url": "http://localhost:3000/callback",
+        "config": {
+            "seed_urls": ["https://example.com"],
+            "max_pages": 1,
+            "max_depth": 0,
+            "custom_extractors": [
+                { "name": "price", "type": "css_selector", "selector": "span[" }
+            ]
+        }
+    });
+    let body = serde_json::to_string(&payload).unwrap();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+    let signature = compute_signature(&body, &timestamp, &config.shared_secret);
+    let response = server
+        .post("/api/v1/jobs")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .json(&payload)
+        .await;
+
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    let json = response.json::<serde_json::Value>();
+    assert_eq!(json["error_kind"], "invalid_extractor");
+}
+
+#[tokio::test]
+async fn test_redirect_to_internal_address_is_reported_as_egress_blocked() {
+    use axum::{extract::State, response::Redirect, routing::get, Router};
+
+    // An allowed site that redirects into the cloud metadata range.
+    let batches = Arc::new(tokio::sync::Mutex::new(Vec::<serde_json::Value>::new()));
+    let site = Router::new()
+        .route(
+            "/",
+            get(|| async { Redirect::temporary("http://169.254.169.254/latest/meta-data/") }),
+        )
+        .route(
+            "/callback",
+            axum::routing::post(
+                |State(batches): State<Arc<tokio::sync::Mutex<Vec<serde_json::Value>>>>,
+                 axum::Json(batch): axum::Json<serde_json::Value>| async move {
+                    batches.lock().await.push(batch);
+                    StatusCode::OK
+                },
+            ),
+        )
+        .with_state(batches.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, site).await.unwrap() });
+
+    let config = Arc::new(create_test_config());
+    let job_manager = Arc::new(JobManager::new(config.clone()));
+    let state = AppState {
+        config: config.clone(),
+        job_manager,
+    };
+    let server = TestServer::new(build_app(state)).unwrap();
+
+    let payload = json!({
+        "job_id": "redirect-to-metadata",
+        "callback_url": format!("http://{addr}/callback"),
+        "config": {
+            "seed_urls": [format!("http://{addr}/")],
+            "max_pages": 1,
+            "max_depth": 0,
+            "respect_robots": false,
+            "run_lighthouse": false,
+            "check_llms_txt": false
+        }
+    });
+    let body = serde_json::to_string(&payload).unwrap();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+    let signature = compute_signature(&body, &timestamp, &config.shared_secret);
+    server
+        .post("/api/v1/jobs")
+        .add_header("X-Timestamp", timestamp)
+        .add_header("X-Signature", signature)
+        .json(&payload)
+        .await
+        .assert_status(StatusCode::ACCEPTED);
+
+    let mut outcome = serde_json::Value::Null;
+    for _ in 0..100 {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        if let Some(batch) = batches.lock().await.iter().find(|b| b["is_final"] == true) {
+            outcome = batch["outcomes"][0].clone();
+            break;
+        }
+    }
+
+    assert_eq!(outcome["outcome"], "failed", "outcome: {outcome}");
+    assert_eq!(outcome["error_kind"], "egress_blocked");
+}
@@ -0,0 +1,150 @@
+//! llms.txt and llms-full.txt discovery against a local server.
+
+use axum::{http::header, http::StatusCode, routing::get, Router};
+use crawler::crawler::egress::EgressPolicy;
+use crawler::crawler::llms_txt;
+use crawler::crawler::RateLimitedFetcher;
+
+async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{addr}")
+}
+
+fn local_egress() -> EgressPolicy {
+    EgressPolicy::new(vec!["127.0.0.1".parse().unwrap()])
+}
+
+fn fetcher() -> RateLimitedFetcher {
+    RateLimitedFetcher::new(50, 5, "TestBot/1.0", local_egress())
+}
+
+const LLMS_TXT: &str = "\
+# Example
+
+> Example builds examples.
+
+## Docs
+
+- [Guide](/guide.md): How to use it
+- [Missing](/missing.md)
+- [Elsewhere](https://other.example.org/doc.md)
+
+## Optional
+
+- [Changelog](/guide.md)
+";
+
+#[tokio::test]
+async fn audits_structure_and_checks_same_site_links() {
+    let base = serve(
+        Router::new()
+            .route("/llms.txt", get(|| async { LLMS_TXT }))
+            .route("/guide.md", get(|| async { "# Guide" }))
+            .route("/missing.md", get(|| async { StatusCode::NOT_FOUND })),
+    )
+    .await;
+
+    let report = llms_txt::audit_url(&format!("{base}/llms.txt"), &local_egress(), &fetcher())
+        .await
+        .unwrap();
+    assert!(!report.is_placeholder);
+    assert!(report.is_valid, "{:?}", report.errors);
+    assert_eq!(report.title.as_deref(), Some("Example"));
+    assert_eq!(report.summary.as_deref(), Some("Example builds examples."));
+    assert_eq!(report.size_bytes, LLMS_TXT.len() as u64);
+    assert_eq!(report.token_estimate, (LLMS_TXT.len() as u64).div_ceil(4));
+    assert_eq!(report.sections.len(), 2);
+    assert!(!report.sections[0].optional);
+    assert!(report.sections[1].optional);
+    assert_eq!(report.link_count, 4);
+    assert_eq!(report.external_link_count, 1);
+    // `/guide.md` is listed twice but fetched once.
+    assert_eq!(report.links_checked, 2);
+    assert_eq!(report.broken_links.len(), 1);
+    assert!(report.broken_links[0].url.ends_with("/missing.md"));
+    assert_eq!(report.broken_links[0].status_code, Some(404));
+}
+
+#[tokio::test]
+async fn html_and_empty_responses_are_placeholders() {
+    let base = serve(
+        Router::new()
+            .route(
+                "/html/llms.txt",
+                get(|| async {
+                    (
+                        [(header::CONTENT_TYPE, "text/html")],
+                        "<!doctype html><p>Not found</p>",
+                    )
+                }),
+            )
+            .route("/empty/llms.txt", get(|| async { "" })),
+    )
+    .await;
+
+    let html = llms_txt::audit_url(
+        &format!("{base}/html/llms.txt"),
+        &local_egress(),
+        &fetcher(),
+    )
+    .await
+    .unwrap();
+    assert!(html.is_placeholder && !html.is_valid);
+    assert_eq!(html.errors, vec!["html_content"]);
+
+    let empty = llms_txt::audit_url(
+        &format!("{base}/empty/llms.txt"),
+        &local_egress(),
+        &fetcher(),
+    )
+    .await
+    .unwrap();
+    assert!(empty.is_placeholder);
+    assert_eq!(empty.errors, vec!["empty"]);
+}
+
+#[tokio::test]
+async fn missing_file_is_none() {
+    let base = serve(Router::new()).await;
+    assert!(
+        llms_txt::audit_url(&format!("{base}/llms.txt"), &local_egress(), &fetcher())
+            .await
+            .is_none()
+    );
+    assert!(
+        llms_txt::fetch_llms_full_txt_url(&format!("{base}/llms-full.txt"), &local_egress())
+            .await
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn llms_full_txt_reports_size_and_tokens() {
+    let body = "word ".repeat(10_000);
+    let len = body.len() as u64;
+    let base = serve(
+        Router::new()
+            .route("/llms-full.txt", get(move || async move { body }))
+            .route(
+                "/soft404/llms-full.txt",
+                get(|| async { ([(header::CONTENT_TYPE, "text/html")], "<html></html>") }),
+            ),
+    )
+    .await;
+
+    let full = llms_txt::fetch_llms_full_txt_url(&format!("{base}/llms-full.txt"), &local_egress())
+        .await
+        .unwrap();
+    assert_eq!(full.size_bytes, len);
+    assert_eq!(full.token_estimate, len.div_ceil(4));
+    assert!(!full.truncated);
+
+    assert!(llms_txt::fetch_llms_full_txt_url(
+        &format!("{base}/soft404/llms-full.txt"),
+        &local_egress()
+    )
+    .await
+    .is_none());
+}
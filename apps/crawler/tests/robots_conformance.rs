@@ -0,0 +1,334 @@
+//! RFC 9309 conformance for `RobotsChecker`, built from the examples in the
+//! RFC and Google's published robots.txt specification.
+
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+use crawler::crawler::egress::EgressPolicy;
+use crawler::crawler::RobotsChecker;
+
+fn allowed(robots: &str, ua: &str, path: &str) -> bool {
+    RobotsChecker::from_content(robots).is_allowed(&format!("https://www.example.com{path}"), ua)
+}
+
+// --- §5.1: simple example ---
+
+const RFC_SIMPLE_EXAMPLE: &str = "\
+User-Agent: *
+Disallow: *.gif$
+Disallow: /example/
+Allow: /publications/
+
+User-Agent: foobot
+Disallow:/
+Allow:/example/page.html
+Allow:/example/allowed.gif
+
+User-Agent: barbot
+User-Agent: bazbot
+Disallow: /example/page.html
+
+User-Agent: quxbot
+
+EOF
+";
+
+#[test]
+fn simple_example_foobot_only_reaches_its_allows() {
+    assert!(allowed(RFC_SIMPLE_EXAMPLE, "foobot", "/example/page.html"));
+    assert!(allowed(
+        RFC_SIMPLE_EXAMPLE,
+        "foobot",
+        "/example/allowed.gif"
+    ));
+    assert!(!allowed(RFC_SIMPLE_EXAMPLE, "foobot", "/"));
+    assert!(!allowed(RFC_SIMPLE_EXAMPLE, "foobot", "/publications/"));
+    assert!(!allowed(
+        RFC_SIMPLE_EXAMPLE,
+        "foobot",
+        "/example/other.html"
+    ));
+}
+
+#[test]
+fn simple_example_grouped_agents_share_rules() {
+    for ua in ["barbot", "bazbot"] {
+        assert!(!allowed(RFC_SIMPLE_EXAMPLE, ua, "/example/page.html"));
+        assert!(allowed(RFC_SIMPLE_EXAMPLE, ua, "/example/other.html"));
+        assert!(allowed(RFC_SIMPLE_EXAMPLE, ua, "/images/a.gif"));
+    }
+}
+
+#[test]
+fn simple_example_empty_group_allows_everything() {
+    // quxbot's group has no rules; it must not fall back to `*`.
+    assert!(allowed(RFC_SIMPLE_EXAMPLE, "quxbot", "/example/page.html"));
+    assert!(allowed(RFC_SIMPLE_EXAMPLE, "quxbot", "/images/a.gif"));
+}
+
+#[test]
+fn simple_example_other_agents_use_the_wildcard_group() {
+    assert!(!allowed(RFC_SIMPLE_EXAMPLE, "otherbot", "/images/a.gif"));
+    assert!(!allowed(
+        RFC_SIMPLE_EXAMPLE,
+        "otherbot",
+        "/example/page.html"
+    ));
+    assert!(allowed(RFC_SIMPLE_EXAMPLE, "otherbot", "/publications/"));
+    assert!(allowed(RFC_SIMPLE_EXAMPLE, "otherbot", "/about"));
+}
+
+// --- §5.2: longest match ---
+
+#[test]
+fn longest_match_wins() {
+    let robots = "\
+User-Agent: foobot
+Allow: /example/page/
+Disallow: /example/page/disallowed.gif
+";
+    assert!(allowed(robots, "foobot", "/example/page/"));
+    assert!(allowed(robots, "foobot", "/example/page/other.html"));
+    assert!(!allowed(robots, "foobot", "/example/page/disallowed.gif"));
+}
+
+// --- §2.2.1: user-agent line ---
+
+#[test]
+fn product_token_matches_case_insensitively() {
+    let robots = "User-agent: ExampleBot\nDisallow: /\n";
+    assert!(!allowed(
+        robots,
+        "ExampleBot/1.1 (+https://www.example.com/bot.html)",
+        "/"
+    ));
+    assert!(!allowed(robots, "examplebot", "/"));
+    assert!(allowed(robots, "OtherBot/1.0", "/"));
+}
+
+#[test]
+fn repeated_groups_for_one_agent_are_merged() {
+    let robots = "\
+user-agent: ExampleBot
+disallow: /foo
+disallow: /bar
+
+user-agent: ExampleBot
+disallow: /baz
+";
+    for path in ["/foo", "/bar", "/baz"] {
+        assert!(!allowed(robots, "ExampleBot", path), "{path}");
+    }
+    assert!(allowed(robots, "ExampleBot", "/qux"));
+}
+
+#[test]
+fn blank_lines_do_not_split_a_group() {
+    let robots = "User-agent: a\n\nUser-agent: b\n\nDisallow: /private\n";
+    assert!(!allowed(robots, "a", "/private"));
+    assert!(!allowed(robots, "b", "/private"));
+}
+
+#[test]
+fn no_matching_group_and_no_wildcard_means_no_rules() {
+    let robots = "User-agent: foobot\nDisallow: /\n";
+    assert!(allowed(robots, "barbot", "/"));
+}
+
+#[test]
+fn rules_before_any_user_agent_are_ignored() {
+    let robots = "Disallow: /\nUser-agent: *\nDisallow: /private\n";
+    assert!(allowed(robots, "anybot", "/"));
+    assert!(!allowed(robots, "anybot", "/private"));
+}
+
+// --- §2.2.2: the allow and disallow lines ---
+
+#[test]
+fn paths_are_compared_percent_normalized() {
+    let robots = "User-agent: *\nDisallow: /foo/bar/ツ\nDisallow: /foo/bar/%62%61%7A\n";
+    assert!(!allowed(robots, "bot", "/foo/bar/%E3%83%84"));
+    assert!(!allowed(robots, "bot", "/foo/bar/ツ"));
+    assert!(!allowed(robots, "bot", "/foo/bar/baz"));
+    assert!(!allowed(robots, "bot", "/foo/bar/%62%61%7A"));
+}
+
+#[test]
+fn query_string_is_part_of_the_match() {
+    let robots = "User-agent: *\nDisallow: /foo/bar?baz=quz\nDisallow: /*?\n";
+    assert!(!allowed(robots, "bot", "/foo/bar?baz=quz"));
+    assert!(!allowed(robots, "bot", "/search?q=robots"));
+    assert!(allowed(robots, "bot", "/search"));
+}
+
+#[test]
+fn equal_length_allow_beats_disallow() {
+    let robots = "User-agent: *\nDisallow: /page\nAllow: /page\n";
+    assert!(allowed(robots, "bot", "/page"));
+}
+
+#[test]
+fn robots_txt_itself_is_always_allowed() {
+    let robots = "User-agent: *\nDisallow: /\n";
+    assert!(allowed(robots, "bot", "/robots.txt"));
+    assert!(!allowed(robots, "bot", "/"));
+}
+
+// --- §2.2.3: special characters ---
+
+#[test]
+fn wildcard_and_end_anchor() {
+    let robots = "\
+User-agent: *
+Disallow: /*.pdf$
+Allow: /this/*/exactly
+Disallow: /this/
+Allow: /that/path/exactly$
+Disallow: /that/
+";
+    assert!(!allowed(robots, "bot", "/files/report.pdf"));
+    assert!(allowed(robots, "bot", "/files/report.pdf?download=1"));
+    assert!(allowed(robots, "bot", "/files/report.pdfx"));
+    assert!(allowed(robots, "bot", "/this/deep/path/exactly"));
+    assert!(!allowed(robots, "bot", "/this/other"));
+    assert!(allowed(robots, "bot", "/that/path/exactly"));
+    assert!(!allowed(robots, "bot", "/that/path/exactly/more"));
+}
+
+#[test]
+fn encoded_special_characters_match_literally() {
+    let robots = "User-agent: *\nDisallow: /path/file-with-a-%2A.html\nDisallow: /path/foo-%24\n";
+    assert!(!allowed(robots, "bot", "/path/file-with-a-*.html"));
+    assert!(allowed(robots, "bot", "/path/file-with-a-b.html"));
+    assert!(!allowed(robots, "bot", "/path/foo-$"));
+}
+
+#[test]
+fn comments_are_ignored() {
+    let robots =
+        "# comment on its own line\nUser-agent: * # all bots\nDisallow: /private # secret\n";
+    assert!(!allowed(robots, "bot", "/private"));
+    assert!(allowed(robots, "bot", "/public"));
+}
+
+// --- §2.3: access method, status codes, limits ---
+
+async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{addr}")
+}
+
+fn local_egress() -> EgressPolicy {
+    EgressPolicy::new(vec!["127.0.0.1".parse().unwrap()])
+}
+
+#[tokio::test]
+async fn status_codes_map_to_allow_or_disallow() {
+    let base = serve(
+        Router::new()
+            .route(
+                "/ok/robots.txt",
+                get(|| async { "User-agent: *\nDisallow: /x\n" }),
+            )
+            .route("/404/robots.txt", get(|| async { StatusCode::NOT_FOUND }))
+            .route(
+                "/500/robots.txt",
+                get(|| async { StatusCode::SERVICE_UNAVAILABLE }),
+            )
+            .route(
+                "/429/robots.txt",
+                get(|| async { StatusCode::TOO_MANY_REQUESTS }),
+            ),
+    )
+    .await;
+    let egress = local_egress();
+
+    let ok = RobotsChecker::fetch(&format!("{base}/ok/robots.txt"), &egress)
+        .await
+        .unwrap();
+    assert!(ok.loaded && !ok.unreachable);
+    assert!(!ok.is_allowed("/x", "bot"));
+
+    // 4xx: unavailable, everything allowed.
+    let missing = RobotsChecker::fetch(&format!("{base}/404/robots.txt"), &egress)
+        .await
+        .unwrap();
+    assert!(!missing.loaded && !missing.unreachable);
+    assert!(missing.is_allowed("/x", "bot"));
+
+    // 5xx and 429: unreachable, everything disallowed.
+    for code in ["500", "429"] {
+        let down = RobotsChecker::fetch(&format!("{base}/{code}/robots.txt"), &egress)
+            .await
+            .unwrap();
+        assert!(down.unreachable, "{code}");
+        assert!(!down.is_allowed("/", "bot"), "{code}");
+        assert_eq!(
+            down.blocking_rule("/page", "bot").as_deref(),
+            Some("Disallow: / (robots.txt unreachable)")
+        );
+    }
+}
+
+#[tokio::test]
+async fn network_error_is_unreachable() {
+    // Bind then drop a listener so the port refuses connections.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    let checker = RobotsChecker::fetch(&format!("http://{addr}/robots.txt"), &local_egress())
+        .await
+        .unwrap();
+    assert!(checker.unreachable);
+    assert!(!checker.is_allowed("/", "bot"));
+}
+
+#[tokio::test]
+async fn follows_up_to_five_redirects() {
+    use axum::extract::Path;
+    use axum::response::Redirect;
+
+    // /hop/N redirects to /hop/N-1; /hop/0 serves the file.
+    let base = serve(Router::new().route(
+        "/hop/{n}",
+        get(|Path(n): Path<u32>| async move {
+            if n == 0 {
+                "User-agent: *\nDisallow: /x\n".into_response()
+            } else {
+                Redirect::temporary(&format!("/hop/{}", n - 1)).into_response()
+            }
+        }),
+    ))
+    .await;
+    let egress = local_egress();
+
+    let five = RobotsChecker::fetch(&format!("{base}/hop/5"), &egress)
+        .await
+        .unwrap();
+    assert!(five.loaded);
+    assert!(!five.is_allowed("/x", "bot"));
+
+    // Six redirects: treated as unavailable.
+    let six = RobotsChecker::fetch(&format!("{base}/hop/6"), &egress)
+        .await
+        .unwrap();
+    assert!(!six.loaded && !six.unreachable);
+    assert!(six.is_allowed("/x", "bot"));
+}
+
+#[tokio::test]
+async fn parses_only_the_first_500_kib() {
+    let mut body = String::from("User-agent: *\nDisallow: /early\n");
+    while body.len() < 500 * 1024 {
+        body.push_str("# padding padding padding padding padding padding padding\n");
+    }
+    body.push_str("Disallow: /late\n");
+    let base = serve(Router::new().route("/robots.txt", get(move || async move { body }))).await;
+
+    let checker = RobotsChecker::fetch(&format!("{base}/robots.txt"), &local_egress())
+        .await
+        .unwrap();
+    assert!(!checker.is_allowed("/early", "bot"));
+    assert!(checker.is_allowed("/late", "bot"));
+}
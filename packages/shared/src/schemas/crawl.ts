@@ -3,9 +3,15 @@ import { z } from "zod";
 export const DEFAULT_CRAWL_USER_AGENT =
   "LLMRankBot/1.0 (+https://llmrank.app/bot)";
 
+export const UrlPatternSchema = z.object({
+  type: z.enum(["glob", "regex"]),
+  pattern: z.string().min(1),
+});
+
 // Cloudflare -> Hetzner: Job submission payload
 export const CrawlJobPayloadSchema = z.object({
-  job_id: z.string(),
+  // Names files on the crawler; anything else is rejected with 422
+  job_id: z.string().regex(/^[A-Za-z0-9_-]+$/),
   callback_url: z.string().url(),
   config: z.object({
     seed_urls: z.array(z.string().url()),
@@ -23,17 +29,151 @@ export const CrawlJobPayloadSchema = z.object({
       .array(
         z.object({
           name: z.string(),
-          type: z.enum(["css_selector", "regex"]),
+          type: z.enum(["css_selector", "regex", "xpath", "json_ld_path"]),
+          // CSS selector, regex, XPath, or dotted JSON-LD path (e.g. "offers.price")
           selector: z.string(),
           attribute: z.string().nullable().optional(),
+          // json_ld_path only: restrict to nodes of this @type
+          schema_type: z.string().nullable().optional(),
+          mode: z.enum(["values", "count", "exists"]).optional().default("values"),
         }),
       )
       .optional()
       .default([]),
+    // URL rules match the path plus query (globs allow any trailing query);
+    // excludes win over includes
+    include_patterns: z.array(UrlPatternSchema).optional().default([]),
+    exclude_patterns: z.array(UrlPatternSchema).optional().default([]),
+    // Query parameter names, `*` wildcards allowed (e.g. "utm_*"). Checked
+    // after canonicalization, so stripped tracking params never match
+    allowed_query_params: z.array(z.string().min(1)).optional().default([]),
+    denied_query_params: z.array(z.string().min(1)).optional().default([]),
+    // Applied to seeds, links, sitemap URLs and canonical tags alike; every
+    // rule is opt-in
+    canonicalization: z
+      .object({
+        strip_tracking_params: z.boolean().default(false),
+        extra_tracking_params: z.array(z.string().min(1)).default([]),
+        sort_query_params: z.boolean().default(false),
+        normalize_percent_encoding: z.boolean().default(false),
+        collapse_index_html: z.boolean().default(false),
+        lowercase_path: z.boolean().default(false),
+      })
+      .optional(),
+    // Limits past which a discovered URL is treated as a crawler trap
+    trap_detection: z
+      .object({
+        enabled: z.boolean().default(true),
+        max_path_length: z.number().int().min(1).default(512),
+        max_query_length: z.number().int().min(1).default(256),
+        max_segment_repeats: z.number().int().min(1).default(2),
+        max_urls_per_template: z.number().int().min(1).default(500),
+        max_query_variants_per_path: z.number().int().min(1).default(50),
+      })
+      .optional(),
+    // Share of matching SimHash bits at which pages count as near-duplicates
+    near_duplicate_threshold: z.number().min(0.5).max(1).default(0.9),
+    // Hosts that count as the site: the seed hosts, their registrable
+    // domains with every subdomain, or the seed hosts plus a list
+    scope: z
+      .discriminatedUnion("type", [
+        z.object({ type: z.literal("host") }),
+        z.object({ type: z.literal("domain") }),
+        z.object({ type: z.literal("hosts"), hosts: z.array(z.string().min(1)) }),
+      ])
+      .default({ type: "host" }),
+    max_pages_per_host: z.number().int().min(1).optional(),
+    // Cache validators from the previous crawl, keyed by URL
+    previous_validators: z
+      .record(
+        z.string(),
+        z.object({
+          etag: z.string().nullable().optional(),
+          last_modified: z.string().nullable().optional(),
+          html_r2_key: z.string(),
+          markdown_r2_key: z.string().nullable().optional(),
+          markdown_tokens: z.number().int().nullable().optional(),
+          content_hash: z.string().nullable().optional(),
+          content_fingerprint: z.string().nullable().optional(),
+          internal_links: z.array(z.string()).optional(),
+        }),
+      )
+      .optional(),
   }),
 });
 
 // Extracted data from a single page
+export const LinkPlacementSchema = z.enum([
+  "nav",
+  "header",
+  "footer",
+  "aside",
+  "content",
+]);
+
+export const ExtractedLinkSchema = z.object({
+  url: z.string(),
+  anchor_text: z.string(),
+  rel: z.string(),
+  is_external: z.boolean(),
+  // Absent for links only the JS renderer found
+  placement: LinkPlacementSchema.optional(),
+});
+
+// One line of a batch's link graph artifact (gzipped JSON lines in R2)
+export const LinkEdgeSchema = z.object({
+  source: z.string(),
+  target: z.string(),
+  anchor_text: z.string(),
+  rel: z.string(),
+  placement: LinkPlacementSchema.optional(),
+});
+
+// Output of one custom extractor; count/exists are set by those modes only
+export const ExtractorResultSchema = z.object({
+  name: z.string(),
+  matches: z.array(z.string()),
+  count: z.number().int().optional(),
+  exists: z.boolean().optional(),
+});
+
+// A structured data problem; `property` is a dotted path from the node
+// (e.g. "offers.priceCurrency"), alternatives joined with "|"
+export const SchemaIssueSchema = z.object({
+  property: z.string(),
+  kind: z.enum(["missing", "invalid"]),
+  message: z.string(),
+});
+
+// Markup a structured data node was read from; Microdata and RDFa items are
+// converted to JSON-LD-shaped nodes
+export const StructuredDataSourceSchema = z.enum(["json_ld", "microdata", "rdfa"]);
+
+// Per-node schema.org validation: errors are missing required properties or
+// invalid values, warnings are missing recommended properties
+export const SchemaNodeReportSchema = z.object({
+  source: StructuredDataSourceSchema.optional().default("json_ld"),
+  types: z.array(z.string()),
+  id: z.string().optional(),
+  errors: z.array(SchemaIssueSchema),
+  warnings: z.array(SchemaIssueSchema),
+  nested_types: z.array(z.string()),
+});
+
+// A quotable unit of main content: blocks under one heading, split at
+// paragraph boundaries. Full text is in the page's Markdown artifact.
+export const ContentPassageSchema = z.object({
+  heading_path: z.array(z.string()),
+  excerpt: z.string(),
+  word_count: z.number().int(),
+  token_count: z.number().int(),
+  has_definition: z.boolean(),
+  has_statistic: z.boolean(),
+  has_list: z.boolean(),
+  has_table: z.boolean(),
+  is_self_contained: z.boolean(),
+});
+
 export const ExtractedDataSchema = z.object({
   h1: z.array(z.string()),
   h2: z.array(z.string()),
@@ -43,12 +183,16 @@ export const ExtractedDataSchema = z.object({
   h6: z.array(z.string()),
   schema_types: z.array(z.string()),
   internal_links: z.array(z.string()),
+  // internal_links with anchor text, rel and placement, in page order
+  internal_link_details: z.array(ExtractedLinkSchema).optional().default([]),
   external_links: z.array(z.string()),
   images_without_alt: z.number().int(),
   has_robots_meta: z.boolean(),
   robots_directives: z.array(z.string()),
   og_tags: z.record(z.string()).optional(),
   structured_data: z.array(z.unknown()).optional(),
+  // Syntax of each structured_data node, index for index
+  structured_data_sources: z.array(StructuredDataSourceSchema).optional().default([]),
   // Readability (Tier 1)
   flesch_score: z.number().nullable().optional(),
   flesch_classification: z.string().nullable().optional(),
@@ -76,6 +220,17 @@ export const ExtractedDataSchema = z.object({
     .optional(),
   // Analytics/tag-manager tools detected in the page (e.g. "ga4", "gtm")
   analytics_tools: z.array(z.string()).optional(),
+  // One entry per configured custom extractor, in config order
+  custom_extractions: z.array(ExtractorResultSchema).optional().default([]),
+  // One report per structured_data node, in the same order
+  schema_validation: z.array(SchemaNodeReportSchema).optional().default([]),
+  // JSON-LD blocks that are not valid JSON (index among non-empty blocks)
+  schema_parse_errors: z
+    .array(z.object({ block_index: z.number().int(), message: z.string() }))
+    .optional()
+    .default([]),
+  // Main content passages in document order, for citation-readiness scoring
+  passages: z.array(ContentPassageSchema).optional().default([]),
 });
 
 // Lighthouse results for a page
@@ -87,9 +242,65 @@ export const LighthouseResultSchema = z.object({
   lh_r2_key: z.string().optional(),
 });
 
+export const AiCrawlerAccessSchema = z.object({
+  user_agent: z.string(),
+  vendor: z.string(),
+  purpose: z.enum(["training", "search_retrieval", "user_fetch"]),
+  named_in_robots: z.boolean(),
+  root_allowed: z.boolean(),
+  blocked_sections: z.array(z.string()).optional().default([]),
+  blocked_page_types: z.array(z.string()).optional().default([]),
+});
+
+export const LlmsTxtReportSchema = z.object({
+  url: z.string(),
+  size_bytes: z.number().int(),
+  token_estimate: z.number().int(),
+  // Empty or HTML served with a 200; has_llms_txt is false for these
+  is_placeholder: z.boolean(),
+  is_valid: z.boolean(),
+  title: z.string().optional(),
+  summary: z.string().optional(),
+  sections: z
+    .array(
+      z.object({
+        title: z.string(),
+        link_count: z.number().int(),
+        optional: z.boolean(),
+      }),
+    )
+    .optional()
+    .default([]),
+  link_count: z.number().int(),
+  errors: z.array(z.string()).optional().default([]),
+  warnings: z.array(z.string()).optional().default([]),
+  external_link_count: z.number().int(),
+  links_checked: z.number().int(),
+  broken_links: z
+    .array(
+      z.object({
+        url: z.string(),
+        status_code: z.number().int().optional(),
+        error: z.string().optional(),
+      }),
+    )
+    .optional()
+    .default([]),
+});
+
+export const LlmsFullTxtSchema = z.object({
+  url: z.string(),
+  size_bytes: z.number().int(),
+  token_estimate: z.number().int(),
+  truncated: z.boolean(),
+});
+
 export const SiteContextSchema = z.object({
   has_llms_txt: z.boolean(),
+  llms_txt: LlmsTxtReportSchema.optional(),
+  llms_full_txt: LlmsFullTxtSchema.optional(),
   ai_crawlers_blocked: z.array(z.string()),
+  ai_crawler_access: z.array(AiCrawlerAccessSchema).optional().default([]),
   has_sitemap: z.boolean(),
   sitemap_analysis: z
     .object({
@@ -97,6 +308,14 @@ export const SiteContextSchema = z.object({
       url_count: z.number().int(),
       stale_url_count: z.number().int(),
       discovered_page_count: z.number().int(),
+      sitemap_count: z.number().int().optional().default(0),
+      urls_with_lastmod: z.number().int().optional().default(0),
+      image_count: z.number().int().optional().default(0),
+      video_count: z.number().int().optional().default(0),
+      news_count: z.number().int().optional().default(0),
+      alternate_count: z.number().int().optional().default(0),
+      // Protocol violations, e.g. "too_many_urls", "invalid_lastmod"
+      issues: z.array(z.string()).optional().default([]),
     })
     .optional(),
   content_hashes: z.record(z.string()), // hash -> url
@@ -114,6 +333,10 @@ export const CrawlPageResultSchema = z.object({
   word_count: z.number().int(),
   content_hash: z.string(),
   html_r2_key: z.string(),
+  // Main content as gzipped Markdown (crawls/{job}/markdown/{hash}.md.gz) and
+  // its estimated token count; absent for non-HTML pages and pages without text
+  markdown_r2_key: z.string().optional(),
+  markdown_tokens: z.number().int().optional(),
   extracted: ExtractedDataSchema,
   lighthouse: LighthouseResultSchema.nullable().optional(),
   timing_ms: z.number(),
@@ -129,6 +352,95 @@ export const CrawlPageResultSchema = z.object({
   site_context: SiteContextSchema.optional(),
   is_cross_domain_redirect: z.boolean().optional().default(false),
   redirect_url: z.string().nullable().optional(),
+  unchanged: z.boolean().optional().default(false),
+  // SimHash of the main-content text, 16 hex digits; absent for thin pages
+  content_fingerprint: z.string().optional(),
+  near_duplicate_of: z.string().optional(),
+  near_duplicate_cluster: z.number().int().optional(),
+});
+
+// URLs the crawler visited but didn't deliver as pages, and why
+export const PageOutcomeSchema = z.object({
+  url: z.string(),
+  outcome: z.enum(["failed", "blocked", "duplicate"]),
+  error_kind: z.string().optional(),
+  error: z.string().optional(),
+  status_code: z.number().int().optional(),
+  robots_rule: z.string().optional(),
+  duplicate_of: z.string().optional(),
+});
+
+// End-of-job comparison of the sitemap with what the crawl found
+export const SitemapReconciliationSchema = z.object({
+  sitemap_url_count: z.number().int(),
+  crawled_sitemap_url_count: z.number().int(),
+  // Crawl didn't cover the whole site, so orphans may be unreached pages
+  partial: z.boolean(),
+  orphan_count: z.number().int(),
+  orphan_urls: z.array(z.string()).optional().default([]),
+  missing_from_sitemap_count: z.number().int(),
+  missing_from_sitemap: z.array(z.string()).optional().default([]),
+  issue_count: z.number().int(),
+  issues: z
+    .array(
+      z.object({
+        url: z.string(),
+        kind: z.enum([
+          "redirect",
+          "non_200",
+          "noindex",
+          "canonicalized_elsewhere",
+          "blocked_by_robots",
+        ]),
+        status_code: z.number().int().optional(),
+        target: z.string().optional(),
+        error_kind: z.string().optional(),
+        // The robots.txt rule behind blocked_by_robots
+        robots_rule: z.string().optional(),
+      }),
+    )
+    .optional()
+    .default([]),
+});
+
+export const CrawlTrapSchema = z.object({
+  kind: z.enum([
+    "repeated_segments",
+    "path_too_long",
+    "query_too_long",
+    "template_cap",
+    "query_variant_cap",
+  ]),
+  pattern: z.string(),
+  example_url: z.string(),
+  skipped_count: z.number().int(),
+});
+
+export const PageLinkMetricsSchema = z.object({
+  url: z.string(),
+  // Fewest clicks from a seed; null when no link path reaches the page
+  click_depth: z.number().int().nullable(),
+  inlinks: z.number().int(),
+  outlinks: z.number().int(),
+  // Share of internal PageRank; sums to 1 across pages
+  pagerank: z.number(),
+  is_dead_end: z.boolean(),
+  is_orphan: z.boolean(),
+});
+
+export const SiteSummarySchema = z.object({
+  partial: z.boolean(),
+  page_count: z.number().int(),
+  orphan_count: z.number().int(),
+  dead_end_count: z.number().int(),
+  unreachable_count: z.number().int(),
+  max_click_depth: z.number().int().optional(),
+  // Pages at each click depth, indexed by depth
+  click_depth_histogram: z.array(z.number().int()),
+  // Highest PageRank first, capped at 500 pages
+  pages: z.array(PageLinkMetricsSchema),
+  // Every delivered page's metrics, as gzipped JSON lines
+  page_metrics_r2_key: z.string().optional(),
 });
 
 // Hetzner -> Cloudflare: Batch result callback
@@ -137,17 +449,53 @@ export const CrawlResultBatchSchema = z.object({
   batch_index: z.number().int(),
   is_final: z.boolean(),
   pages: z.array(CrawlPageResultSchema),
+  outcomes: z.array(PageOutcomeSchema).optional().default([]),
   stats: z.object({
     pages_found: z.number().int(),
     pages_crawled: z.number().int(),
     pages_errored: z.number().int(),
     elapsed_s: z.number(),
+    // Slowest host's pace; per host below, after Crawl-delay and Retry-After
+    effective_rate_per_s: z.number().optional(),
+    effective_rate_per_host: z.record(z.string(), z.number()).optional(),
+    // URLs kept out of the frontier, keyed by the rule that rejected them
+    filtered_by_rule: z.record(z.string(), z.number().int()).optional(),
   }),
+  sitemap_reconciliation: SitemapReconciliationSchema.optional(),
+  // Suspected crawler traps the frontier cut off; final batch only
+  crawl_traps: z.array(CrawlTrapSchema).optional().default([]),
+  // Click depth, internal links and PageRank per page; final batch only
+  site_summary: SiteSummarySchema.optional(),
+  // R2 key of this batch's part of the job's internal link graph
+  link_edges_r2_key: z.string().optional(),
+  // Every near-duplicate cluster, including each cluster's first page
+  near_duplicate_clusters: z
+    .array(
+      z.object({
+        id: z.number().int(),
+        representative_url: z.string(),
+        page_count: z.number().int(),
+        urls: z.array(z.string()),
+      }),
+    )
+    .optional()
+    .default([]),
 });
 
 export type CrawlJobPayload = z.infer<typeof CrawlJobPayloadSchema>;
+export type UrlPattern = z.infer<typeof UrlPatternSchema>;
 export type ExtractedData = z.infer<typeof ExtractedDataSchema>;
 export type LighthouseResult = z.infer<typeof LighthouseResultSchema>;
 export type SiteContext = z.infer<typeof SiteContextSchema>;
+export type LlmsTxtReport = z.infer<typeof LlmsTxtReportSchema>;
+export type LlmsFullTxt = z.infer<typeof LlmsFullTxtSchema>;
+export type AiCrawlerAccess = z.infer<typeof AiCrawlerAccessSchema>;
 export type CrawlPageResult = z.infer<typeof CrawlPageResultSchema>;
+export type PageOutcome = z.infer<typeof PageOutcomeSchema>;
 export type CrawlResultBatch = z.infer<typeof CrawlResultBatchSchema>;
+export type SitemapReconciliation = z.infer<typeof SitemapReconciliationSchema>;
+export type CrawlTrap = z.infer<typeof CrawlTrapSchema>;
+export type PageLinkMetrics = z.infer<typeof PageLinkMetricsSchema>;
+export type SiteSummary = z.infer<typeof SiteSummarySchema>;
+export type ExtractedLink = z.infer<typeof ExtractedLinkSchema>;
+export type LinkEdge = z.infer<typeof LinkEdgeSchema>;